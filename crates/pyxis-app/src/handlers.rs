@@ -1,8 +1,30 @@
+pub mod attachments;
+pub mod bootstrap;
 pub mod config;
+pub mod conflicts;
+pub mod daily_notes;
 pub mod devices;
 pub mod directories;
 pub mod files;
+pub mod sidecar;
 pub mod snapshots;
+pub mod tombstones;
 pub mod tracker;
 pub mod updates;
 pub mod workspaces;
+
+use pyxis_shared::error::AppError;
+
+/*
+ * Commands are async and do their database work on the blocking thread pool,
+ * so a slow query neither stalls the main thread nor holds up other commands
+ */
+pub async fn blocking<T, F>(task: F) -> Result<T, AppError>
+where
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+    T: Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(task)
+        .await
+        .map_err(|e| AppError::Database(format!("Database task failed. {}", e)))?
+}
@@ -0,0 +1,205 @@
+use std::borrow::Cow;
+
+use pyxis_shared::{
+    database::Database,
+    entities::{attachments::Attachment, queue::Source, tombstones::Tombstone},
+    error::AppError,
+    utils::blob_store,
+};
+use rusqlite::Connection;
+use tauri::{
+    http::{
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS},
+        HeaderValue, Request, Response, StatusCode,
+    },
+    Manager, Runtime, State, UriSchemeContext,
+};
+
+use super::blocking;
+use super::tombstones::is_admitted;
+
+#[tauri::command]
+pub async fn add_attachment(
+    file_uid: String,
+    name: String,
+    mime_type: String,
+    content: Vec<u8>,
+    uid: Option<String>,
+    created_at: Option<String>,
+    synced: Option<bool>,
+    database: State<'_, Database>,
+) -> Result<Option<Attachment>, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        let connection = database.get_connection();
+
+        if let (Some(true), Some(uid)) = (synced, &uid) {
+            let created_at = created_at.as_deref().map(Tombstone::millis).unwrap_or(0);
+            if !is_admitted(&connection, Source::Attachment, uid, created_at) {
+                return Ok(None);
+            }
+        }
+
+        /*
+         * The blob is written before the row so that a listener picking up the insert
+         * can always find the content on disk
+         */
+        let hash = blob_store::write(&content)?;
+
+        let attachment = Attachment::new(
+            file_uid,
+            name,
+            mime_type,
+            hash,
+            content.len() as i64,
+            uid,
+            created_at,
+            synced,
+        );
+
+        attachment.create(&connection)?;
+        Ok(Some(attachment))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_attachment(
+    uid: String,
+    database: State<'_, Database>,
+) -> Result<Attachment, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || Ok(Attachment::get_by_uid(&database.get_reader(), &uid)?)).await
+}
+
+#[tauri::command]
+pub async fn list_attachments(
+    file_uid: String,
+    database: State<'_, Database>,
+) -> Result<Vec<Attachment>, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || Ok(Attachment::list(&database.get_reader(), &file_uid)?)).await
+}
+
+#[tauri::command]
+pub async fn delete_attachment(uid: String, database: State<'_, Database>) -> Result<(), AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        let connection = database.get_connection();
+
+        let attachment = Attachment::get_by_uid(&connection, &uid)?;
+        Attachment::delete(&uid, &connection)?;
+
+        remove_unreferenced_blob(&connection, &attachment.hash);
+
+        Ok(())
+    })
+    .await
+}
+
+// Blobs are content-addressed and may be shared by several attachments
+pub fn remove_unreferenced_blob(connection: &Connection, hash: &str) {
+    match Attachment::is_hash_referenced(connection, hash) {
+        Ok(false) => {
+            if let Err(e) = blob_store::remove(hash) {
+                eprintln!("[Attachments] Failed to remove blob! {}", e);
+            }
+        }
+        Ok(true) => {}
+        Err(e) => eprintln!("[Attachments] Failed to check blob references! {}", e),
+    }
+}
+
+// Documents that can run script. Served inline they would run in the app origin.
+const ACTIVE_MIME_TYPES: &[&str] = &[
+    "text/html",
+    "application/xhtml+xml",
+    "image/svg+xml",
+    "text/xml",
+    "application/xml",
+];
+
+fn is_active(mime_type: &str) -> bool {
+    let essence = mime_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    ACTIVE_MIME_TYPES.contains(&essence.as_str())
+}
+
+/*
+ * The mime type comes from the user or from another device, so it is only
+ * used when it is a valid header and not something the webview would execute.
+ * Anything else is served as plain bytes.
+ */
+fn content_type(mime_type: &str) -> HeaderValue {
+    let octet_stream = HeaderValue::from_static("application/octet-stream");
+
+    if is_active(mime_type) {
+        return octet_stream;
+    }
+
+    HeaderValue::from_str(mime_type).unwrap_or(octet_stream)
+}
+
+fn empty_response(status: StatusCode) -> Response<Cow<'static, [u8]>> {
+    Response::builder()
+        .status(status)
+        .body(Cow::Borrowed(&[][..]))
+        .expect("[Attachments] Failed to build response!")
+}
+
+/*
+ * Serves attachment content to the webview as `attachment://localhost/<uid>`
+ * so that images and files can be embedded without going through IPC
+ */
+pub fn attachment_protocol<R: Runtime>(
+    ctx: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+) -> Response<Cow<'static, [u8]>> {
+    let uid = request.uri().path().trim_start_matches('/');
+
+    let attachment = {
+        let database = ctx.app_handle().state::<Database>();
+        let connection = database.get_reader();
+
+        match Attachment::get_by_uid(&connection, uid) {
+            Ok(attachment) => attachment,
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                return empty_response(StatusCode::NOT_FOUND)
+            }
+            Err(e) => {
+                eprintln!("[Attachments] Failed to fetch! {}", e);
+                return empty_response(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
+    };
+
+    match blob_store::read(&attachment.hash) {
+        Ok(content) => {
+            let mut response = Response::builder()
+                .status(StatusCode::OK)
+                .header(CONTENT_TYPE, content_type(&attachment.mime_type))
+                .header(X_CONTENT_TYPE_OPTIONS, "nosniff");
+            if is_active(&attachment.mime_type) {
+                response = response.header(CONTENT_DISPOSITION, "attachment");
+            }
+
+            response
+                .body(Cow::Owned(content))
+                .expect("[Attachments] Failed to build response!")
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => empty_response(StatusCode::NOT_FOUND),
+        Err(e) => {
+            eprintln!("[Attachments] Failed to read blob! {}", e);
+            empty_response(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use pyxis_shared::{
+    database::{ConfigDatabase, Database},
+    entities::{bootstrap::Bootstrap, outbox::OutboxEntry},
+    error::AppError,
+};
+use tauri::State;
+
+use super::blocking;
+
+// The sync worker picks the request up on its next loop
+#[tauri::command]
+pub async fn request_bootstrap(sync_db: State<'_, ConfigDatabase>) -> Result<(), AppError> {
+    let sync_db = sync_db.inner().clone();
+
+    blocking(move || Ok(Bootstrap::request(&sync_db.0.get_connection())?)).await
+}
+
+#[tauri::command]
+pub async fn get_bootstrap(
+    sync_db: State<'_, ConfigDatabase>,
+) -> Result<Option<Bootstrap>, AppError> {
+    let sync_db = sync_db.inner().clone();
+
+    blocking(move || Ok(Bootstrap::get(&sync_db.0.get_reader())?)).await
+}
+
+// Sends every local record to the server again. Returns how many were queued.
+#[tauri::command]
+pub async fn repush_all(database: State<'_, Database>) -> Result<usize, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || Ok(OutboxEntry::capture_all(&database.get_connection())?)).await
+}
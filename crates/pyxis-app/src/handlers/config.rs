@@ -1,68 +1,121 @@
 use pyxis_shared::{
     database::ConfigDatabase,
-    entities::config::{ConfigEntry, Configuration, Features},
+    entities::{
+        config::{ConfigEntry, Configuration, Features},
+        network::NetworkSettings,
+    },
+    error::AppError,
     utils::get_machine_id,
 };
-use tauri::State;
+use rusqlite::OptionalExtension;
+use tauri::{State, Url};
+
+use super::blocking;
 
 #[tauri::command]
-pub fn add_user_data(
+pub async fn add_user_data(
     username: String,
     user_token: String,
     user_id: String,
     features: Option<Features>,
-    sync_db: State<ConfigDatabase>,
-) -> Option<bool> {
-    let content = ConfigEntry::new(Some(user_token), user_id, Some(username), features);
-
-    match content.add(&sync_db.0.get_connection()) {
-        Ok(_) => Some(true),
-        Err(e) => {
-            eprintln!("[Configuration] Failed to add user data to config! {}", e);
-            Some(false)
-        }
-    }
+    sync_db: State<'_, ConfigDatabase>,
+) -> Result<(), AppError> {
+    let sync_db = sync_db.inner().clone();
+
+    blocking(move || {
+        let content = ConfigEntry::new(Some(user_token), user_id, Some(username), features);
+
+        Ok(content.add(&sync_db.0.get_connection())?)
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn remove_user_data(user_id: String, sync_db: State<ConfigDatabase>) -> Option<bool> {
-    let content = ConfigEntry::new(None, user_id, None, None);
-
-    match content.add(&sync_db.0.get_connection()) {
-        Ok(_) => Some(true),
-        Err(e) => {
-            eprintln!(
-                "[Configuration] Failed to remove user data from config! {}",
-                e
-            );
-            Some(false)
-        }
-    }
+pub async fn remove_user_data(
+    user_id: String,
+    sync_db: State<'_, ConfigDatabase>,
+) -> Result<(), AppError> {
+    let sync_db = sync_db.inner().clone();
+
+    blocking(move || {
+        let content = ConfigEntry::new(None, user_id, None, None);
+
+        Ok(content.add(&sync_db.0.get_connection())?)
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn get_config(user_id: String, sync_db: State<ConfigDatabase>) -> Option<Configuration> {
-    match ConfigEntry::get(&sync_db.0.get_connection(), user_id) {
-        Ok(content) => Some(content),
-        Err(e) => {
-            eprintln!("[Configuration] Failed to fetch! {}", e);
-            None
-        }
-    }
+pub async fn get_config(
+    user_id: String,
+    sync_db: State<'_, ConfigDatabase>,
+) -> Result<Configuration, AppError> {
+    let sync_db = sync_db.inner().clone();
+
+    blocking(move || Ok(ConfigEntry::get(&sync_db.0.get_reader(), user_id)?)).await
 }
 
+// None while nobody is signed in
 #[tauri::command]
-pub fn get_logged_in_user(sync_db: State<ConfigDatabase>) -> Option<Configuration> {
-    match ConfigEntry::get_logged_in_user(&sync_db.0.get_connection()) {
-        Ok(config) => Some(config),
-        Err(e) => {
-            eprintln!("[Configuration] Failed to fetch! {}", e);
-            None
-        }
-    }
+pub async fn get_logged_in_user(
+    sync_db: State<'_, ConfigDatabase>,
+) -> Result<Option<Configuration>, AppError> {
+    let sync_db = sync_db.inner().clone();
+
+    blocking(move || Ok(ConfigEntry::get_logged_in_user(&sync_db.0.get_reader()).optional()?)).await
 }
 
 #[tauri::command]
 pub fn get_device_id() -> String {
     get_machine_id::get_machine_id().to_string()
 }
+
+#[tauri::command]
+pub async fn get_network_settings(
+    sync_db: State<'_, ConfigDatabase>,
+) -> Result<NetworkSettings, AppError> {
+    let sync_db = sync_db.inner().clone();
+
+    blocking(move || Ok(NetworkSettings::get(&sync_db.0.get_reader())?)).await
+}
+
+#[tauri::command]
+pub async fn set_network_settings(
+    settings: NetworkSettings,
+    sync_db: State<'_, ConfigDatabase>,
+) -> Result<(), AppError> {
+    let sync_db = sync_db.inner().clone();
+
+    blocking(move || {
+        validate_network_settings(&settings).map_err(AppError::InvalidInput)?;
+
+        // The sync sidecar picks the new settings up on its next loop
+        Ok(settings.save(&sync_db.0.get_connection())?)
+    })
+    .await
+}
+
+fn validate_network_settings(settings: &NetworkSettings) -> Result<(), String> {
+    for url in [&settings.server_url, &settings.proxy_url]
+        .into_iter()
+        .flatten()
+    {
+        let parsed = Url::parse(url).map_err(|e| format!("{}: {}", url, e))?;
+
+        if !["http", "https"].contains(&parsed.scheme()) {
+            return Err(format!("{}: Unsupported scheme", url));
+        }
+    }
+
+    if let Some(certificate) = &settings.ca_certificate {
+        if !certificate.contains("-----BEGIN CERTIFICATE-----") {
+            return Err(String::from("CA certificate is not PEM encoded"));
+        }
+    }
+
+    if settings.connect_timeout_seconds == Some(0) || settings.request_timeout_seconds == Some(0) {
+        return Err(String::from("Timeouts must be at least a second"));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use pyxis_shared::{
+    database::Database,
+    entities::conflicts::{Conflict, Resolution},
+    error::AppError,
+};
+use tauri::State;
+
+use super::blocking;
+
+#[tauri::command]
+pub async fn list_conflicts(
+    include_resolved: Option<bool>,
+    database: State<'_, Database>,
+) -> Result<Vec<Conflict>, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        Ok(Conflict::list(
+            &database.get_reader(),
+            include_resolved.unwrap_or(false),
+        )?)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn resolve_conflict(
+    id: i64,
+    resolution: Resolution,
+    database: State<'_, Database>,
+) -> Result<(), AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        Ok(Conflict::resolve(
+            &database.get_connection(),
+            id,
+            resolution,
+        )?)
+    })
+    .await
+}
@@ -0,0 +1,47 @@
+use chrono::NaiveDate;
+use pyxis_shared::{
+    database::Database,
+    entities::{daily_notes::DailyNote, files::Files},
+    error::AppError,
+};
+use tauri::State;
+
+use super::blocking;
+
+fn parse_date(date: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| AppError::InvalidInput(format!("Invalid date {}! {}", date, e)))
+}
+
+#[tauri::command]
+pub async fn open_daily_note(
+    workspace_uid: String,
+    date: String,
+    database: State<'_, Database>,
+) -> Result<Files, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        let date = parse_date(&date)?;
+
+        DailyNote::open(&database.get_connection(), &workspace_uid, date)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn list_daily_notes(
+    workspace_uid: String,
+    from: String,
+    to: String,
+    database: State<'_, Database>,
+) -> Result<Vec<DailyNote>, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        let (from, to) = (parse_date(&from)?, parse_date(&to)?);
+
+        DailyNote::list(&database.get_reader(), &workspace_uid, from, to)
+    })
+    .await
+}
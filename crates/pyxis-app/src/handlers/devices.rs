@@ -1,26 +1,26 @@
-use pyxis_shared::{database::ConfigDatabase, entities::devices::Device};
+use pyxis_shared::{database::ConfigDatabase, entities::devices::Device, error::AppError};
 use tauri::State;
 
+use super::blocking;
+
 #[tauri::command]
-pub fn add_devices(device_ids: Vec<String>, sync_db: State<ConfigDatabase>) -> Option<bool> {
-    let devices = Device::new(None, device_ids);
+pub async fn add_devices(
+    device_ids: Vec<String>,
+    sync_db: State<'_, ConfigDatabase>,
+) -> Result<(), AppError> {
+    let sync_db = sync_db.inner().clone();
+
+    blocking(move || {
+        let devices = Device::new(None, device_ids);
 
-    match devices.add(&sync_db.0.get_connection()) {
-        Ok(_) => Some(true),
-        Err(e) => {
-            eprintln!("[Device] Failed to add device! {}", e);
-            Some(false)
-        }
-    }
+        Ok(devices.add(&sync_db.0.get_connection())?)
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn list_devices(sync_db: State<ConfigDatabase>) -> Option<Vec<String>> {
-    match Device::list(&sync_db.0.get_connection()) {
-        Ok(ids) => Some(ids),
-        Err(e) => {
-            eprintln!("[Device] Failed to fetch! {}", e);
-            None
-        }
-    }
+pub async fn list_devices(sync_db: State<'_, ConfigDatabase>) -> Result<Vec<String>, AppError> {
+    let sync_db = sync_db.inner().clone();
+
+    blocking(move || Ok(Device::list(&sync_db.0.get_reader())?)).await
 }
@@ -1,9 +1,22 @@
 use chrono::Utc;
-use pyxis_shared::{database::Database, entities::directories::Directory};
+use pyxis_shared::{
+    database::Database,
+    entities::{
+        conflicts::{update_local, update_remote, FieldClocks},
+        directories::Directory,
+        queue::Source,
+        tombstones::Tombstone,
+    },
+    error::AppError,
+};
+use rusqlite::Error;
 use tauri::State;
 
+use super::blocking;
+use super::tombstones::is_admitted;
+
 #[tauri::command]
-pub fn create_dir(
+pub async fn create_dir(
     name: String,
     workspace_uid: String,
     path: String,
@@ -12,75 +25,116 @@ pub fn create_dir(
     updated_at: Option<String>,
     uid: Option<String>,
     synced: Option<bool>,
-    database: State<Database>,
-) -> Option<Directory> {
-    let directory = Directory::new(
-        name,
-        workspace_uid,
-        path,
-        parent_uid,
-        None,
-        created_at,
-        updated_at,
-        uid,
-        synced,
-    );
-
-    match directory.create(&database.get_connection()) {
-        Ok(_) => Some(directory),
-        Err(e) => {
-            eprintln!("[Directories] Failed to create! {}", e);
-            None
+    database: State<'_, Database>,
+) -> Result<Option<Directory>, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        let directory = Directory::new(
+            name,
+            workspace_uid,
+            path,
+            parent_uid,
+            None,
+            created_at,
+            updated_at,
+            uid,
+            synced,
+        );
+
+        let conn = &database.get_connection();
+        if synced == Some(true)
+            && !is_admitted(
+                conn,
+                Source::Directory,
+                &directory.uid,
+                Tombstone::millis(&directory.updated_at),
+            )
+        {
+            return Ok(None);
         }
-    }
+
+        directory.create(conn)?;
+        Ok(Some(directory))
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn list_dirs(
+pub async fn list_dirs(
     workspace_uid: String,
     parent_uid: Option<String>,
-    database: State<Database>,
-) -> Option<Vec<Directory>> {
-    match Directory::list(&database.get_connection(), workspace_uid, parent_uid) {
-        Ok(directories) => Some(directories),
-        Err(e) => {
-            eprintln!("[Directories] Failed to fetch! Error: {e}");
-            None
-        }
-    }
+    database: State<'_, Database>,
+) -> Result<Vec<Directory>, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        Ok(Directory::list(
+            &database.get_reader(),
+            workspace_uid,
+            parent_uid,
+        )?)
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn delete_dir(uid: String, database: State<Database>) -> bool {
-    match Directory::delete(uid, &database.get_connection()) {
-        Ok(_) => true,
-        Err(e) => {
-            eprintln!("[Directories] Failed to delete! {}", e);
-            false
-        }
-    }
+pub async fn delete_dir(uid: String, database: State<'_, Database>) -> Result<(), AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || Ok(Directory::delete(uid, &database.get_connection())?)).await
 }
 
 #[tauri::command]
-pub fn update_dir(
+pub async fn update_dir(
     uid: String,
     name: String,
     workspace_uid: String,
     path: String,
     parent_uid: Option<String>,
-    database: State<Database>,
+    database: State<'_, Database>,
     synced: Option<bool>,
-) -> Option<Directory> {
-    let conn = &database.get_connection();
-    let directory = match Directory::list(conn, workspace_uid.clone(), parent_uid.clone()) {
-        Ok(dir) => dir.into_iter().find(|d| d.uid == uid),
-        Err(e) => {
-            eprintln!("[Directories] Failed to get for update! {}", e);
-            None
+    field_clocks: Option<FieldClocks>,
+) -> Result<Option<Directory>, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        let conn = &database.get_connection();
+        let field_clocks = field_clocks.unwrap_or_default();
+        if synced == Some(true)
+            && !is_admitted(
+                conn,
+                Source::Directory,
+                &uid,
+                Tombstone::last_edit(&field_clocks),
+            )
+        {
+            return Ok(None);
         }
-    };
 
-    if let Some(mut dir) = directory {
+        let mut dir = match Directory::get_by_uid(conn, &uid) {
+            Ok(dir) => dir,
+            // Edited on another device after it was deleted here
+            Err(Error::QueryReturnedNoRows) if synced == Some(true) => {
+                let mut dir = Directory::new(
+                    name,
+                    workspace_uid,
+                    path,
+                    parent_uid,
+                    None,
+                    None,
+                    None,
+                    Some(uid),
+                    synced,
+                );
+                dir.field_clocks = field_clocks;
+
+                dir.create(conn)?;
+                return Ok(Some(dir));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
         dir.name = name;
         dir.workspace_uid = workspace_uid;
         dir.path = path;
@@ -88,31 +142,33 @@ pub fn update_dir(
         dir.updated_at = Utc::now().to_rfc3339();
         dir.synced = synced;
 
-        match dir.update(conn) {
-            Ok(_) => {
-                return Some(dir);
-            }
-            Err(e) => {
-                eprintln!("[Directories] Failed to update! {}", e);
-                return None;
-            }
-        }
-    }
+        // Rows synced from other devices are merged field by field
+        let result = if synced == Some(true) {
+            dir.field_clocks = field_clocks;
+            update_remote(conn, dir)
+        } else {
+            update_local(conn, dir)
+        };
 
-    None
+        Ok(Some(result?))
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn get_directory_id(
+pub async fn get_directory_id(
     path: String,
     workspace_uid: String,
-    database: State<Database>,
-) -> Option<i64> {
-    match Directory::get_by_path(&database.get_connection(), path, workspace_uid) {
-        Ok(directory_id) => Some(directory_id),
-        Err(e) => {
-            eprintln!("[Directory] Failed to fetch id! Error: {e}");
-            None
-        }
-    }
+    database: State<'_, Database>,
+) -> Result<i64, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        Ok(Directory::get_by_path(
+            &database.get_reader(),
+            path,
+            workspace_uid,
+        )?)
+    })
+    .await
 }
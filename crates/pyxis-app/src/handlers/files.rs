@@ -1,12 +1,23 @@
 use chrono::Utc;
 use pyxis_shared::{
     database::Database,
-    entities::files::{Files, Link},
+    entities::{
+        conflicts::{update_local, update_remote, FieldClocks},
+        file_activity::{Activity, FileActivity},
+        files::{Files, Link},
+        queue::Source,
+        tombstones::Tombstone,
+    },
+    error::AppError,
 };
+use rusqlite::Error;
 use tauri::State;
 
+use super::blocking;
+use super::tombstones::is_admitted;
+
 #[tauri::command]
-pub fn create_file(
+pub async fn create_file(
     title: String,
     dir_uid: Option<String>,
     path: String,
@@ -17,59 +28,65 @@ pub fn create_file(
     updated_at: Option<String>,
     uid: Option<String>,
     synced: Option<bool>,
-    database: State<Database>,
-) -> Option<Files> {
-    let file = Files::new(
-        dir_uid,
-        path,
-        title,
-        tags,
-        links,
-        workspace_uid,
-        None,
-        created_at,
-        updated_at,
-        uid,
-        synced,
-    );
-
-    match file.create(&database.get_connection()) {
-        Ok(_) => Some(file),
-        Err(e) => {
-            eprintln!("[Files] Failed to create! {}", e);
-            None
+    pinned: Option<bool>,
+    database: State<'_, Database>,
+) -> Result<Option<Files>, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        let mut file = Files::new(
+            dir_uid,
+            path,
+            title,
+            tags,
+            links,
+            workspace_uid,
+            None,
+            created_at,
+            updated_at,
+            uid,
+            synced,
+        );
+        file.pinned = pinned.unwrap_or(false);
+
+        let conn = &database.get_connection();
+        if synced == Some(true)
+            && !is_admitted(
+                conn,
+                Source::File,
+                &file.uid,
+                Tombstone::millis(&file.updated_at),
+            )
+        {
+            return Ok(None);
         }
-    }
+
+        file.create(conn)?;
+        Ok(Some(file))
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn list_files(
+pub async fn list_files(
     workspace_uid: String,
     dir_uid: Option<String>,
-    database: State<Database>,
-) -> Option<Vec<Files>> {
-    match Files::list(&database.get_connection(), workspace_uid, dir_uid) {
-        Ok(directories) => Some(directories),
-        Err(e) => {
-            eprintln!("[Files] Failed to fetch! Error: {e}");
-            None
-        }
-    }
+    database: State<'_, Database>,
+) -> Result<Vec<Files>, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || Ok(Files::list(&database.get_reader(), workspace_uid, dir_uid)?)).await
 }
 
 #[tauri::command]
-pub fn delete_file(uid: String, database: State<Database>) -> bool {
-    match Files::delete(uid, &database.get_connection()) {
-        Ok(_) => true,
-        Err(e) => {
-            eprintln!("[Files] Failed to delete! {}", e);
-            false
-        }
-    }
+pub async fn delete_file(uid: String, database: State<'_, Database>) -> Result<(), AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || Ok(Files::delete(uid, &database.get_connection())?)).await
 }
 
 #[tauri::command]
-pub fn update_file(
+pub async fn update_file(
     uid: String,
     title: String,
     dir_uid: Option<String>,
@@ -78,47 +95,159 @@ pub fn update_file(
     links: Vec<Link>,
     tags: Vec<String>,
     synced: Option<bool>,
-    database: State<Database>,
-) -> Option<Files> {
-    let conn = &database.get_connection();
-    let file = match Files::list(conn, workspace_uid, dir_uid.clone()) {
-        Ok(f) => f.into_iter().find(|f| f.uid == uid),
-        Err(e) => {
-            eprintln!("[Files] Failed to get for update! {}", e);
-            None
+    pinned: Option<bool>,
+    field_clocks: Option<FieldClocks>,
+    database: State<'_, Database>,
+) -> Result<Option<Files>, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        let conn = &database.get_connection();
+        let field_clocks = field_clocks.unwrap_or_default();
+        if synced == Some(true)
+            && !is_admitted(
+                conn,
+                Source::File,
+                &uid,
+                Tombstone::last_edit(&field_clocks),
+            )
+        {
+            return Ok(None);
         }
-    };
 
-    if let Some(mut file) = file {
+        let mut file = match Files::get_by_uid(conn, &uid) {
+            Ok(f) => f,
+            // Edited on another device after it was deleted here
+            Err(Error::QueryReturnedNoRows) if synced == Some(true) => {
+                let mut file = Files::new(
+                    dir_uid,
+                    path,
+                    title,
+                    tags,
+                    links,
+                    workspace_uid,
+                    None,
+                    None,
+                    None,
+                    Some(uid),
+                    synced,
+                );
+                file.pinned = pinned.unwrap_or(false);
+                file.field_clocks = field_clocks;
+
+                file.create(conn)?;
+                return Ok(Some(file));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
         file.title = title;
+        file.workspace_uid = workspace_uid;
         file.dir_uid = dir_uid;
         file.path = path;
         file.links = links;
         file.tags = tags;
         file.updated_at = Utc::now().to_rfc3339();
         file.synced = synced;
-
-        match file.update(conn) {
-            Ok(_) => {
-                return Some(file);
-            }
-            Err(e) => {
-                eprintln!("[Files] Failed to update! {}", e);
-                return None;
-            }
+        if let Some(pinned) = pinned {
+            file.pinned = pinned;
         }
-    }
 
-    None
+        // Rows synced from other devices are merged field by field
+        let result = if synced == Some(true) {
+            file.field_clocks = field_clocks;
+            update_remote(conn, file)
+        } else {
+            update_local(conn, file)
+        };
+
+        Ok(Some(result?))
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn get_file_id(path: String, workspace_uid: String, database: State<Database>) -> Option<i64> {
-    match Files::get_by_path(&database.get_connection(), path, workspace_uid) {
-        Ok(file_id) => Some(file_id),
-        Err(e) => {
-            eprintln!("[Files] Failed to fetch id! Error: {e}");
-            None
-        }
-    }
+pub async fn get_file_id(
+    path: String,
+    workspace_uid: String,
+    database: State<'_, Database>,
+) -> Result<i64, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        Ok(Files::get_by_path(
+            &database.get_reader(),
+            path,
+            workspace_uid,
+        )?)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn pin_file(uid: String, database: State<'_, Database>) -> Result<(), AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        Ok(Files::set_pinned(
+            &database.get_connection(),
+            uid,
+            true,
+            None,
+        )?)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn unpin_file(uid: String, database: State<'_, Database>) -> Result<(), AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        Ok(Files::set_pinned(
+            &database.get_connection(),
+            uid,
+            false,
+            None,
+        )?)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn list_pinned_files(
+    workspace_uid: String,
+    database: State<'_, Database>,
+) -> Result<Vec<Files>, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || Ok(Files::list_pinned(&database.get_reader(), workspace_uid)?)).await
+}
+
+#[tauri::command]
+pub async fn list_recent_files(
+    limit: i64,
+    database: State<'_, Database>,
+) -> Result<Vec<Files>, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || Ok(Files::list_recent(&database.get_reader(), limit)?)).await
+}
+
+#[tauri::command]
+pub async fn record_file_activity(
+    file_uid: String,
+    activity: Activity,
+    database: State<'_, Database>,
+) -> Result<(), AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        Ok(FileActivity::record(
+            &database.get_connection(),
+            &file_uid,
+            activity,
+        )?)
+    })
+    .await
 }
@@ -0,0 +1,37 @@
+use pyxis_shared::error::AppError;
+use tauri::AppHandle;
+
+use crate::sidecar::{restart_sync_worker, start_sync_worker, stop_sync_worker};
+
+#[tauri::command]
+pub fn start_sync(app: AppHandle) -> Result<(), AppError> {
+    if !start_sync_worker(&app) {
+        return Err(AppError::Sidecar(String::from(
+            "Failed to start the sync worker",
+        )));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_sync(app: AppHandle) -> Result<(), AppError> {
+    if !stop_sync_worker(&app) {
+        return Err(AppError::Sidecar(String::from(
+            "Failed to stop the sync worker",
+        )));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn restart_sync(app: AppHandle) -> Result<(), AppError> {
+    if !restart_sync_worker(&app) {
+        return Err(AppError::Sidecar(String::from(
+            "Failed to restart the sync worker",
+        )));
+    }
+
+    Ok(())
+}
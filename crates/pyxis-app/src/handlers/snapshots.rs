@@ -1,30 +1,30 @@
-use pyxis_shared::{database::Database, entities::snapshots::Snapshots};
+use pyxis_shared::{database::Database, entities::snapshots::Snapshots, error::AppError};
 use tauri::State;
 
+use super::blocking;
+
 #[tauri::command]
-pub fn update_snapshot(
+pub async fn update_snapshot(
     file_uid: String,
     content: Vec<u8>,
-    database: State<Database>,
-) -> Option<bool> {
-    let content = Snapshots::new(file_uid, content, None, 1);
+    database: State<'_, Database>,
+) -> Result<(), AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        let content = Snapshots::new(file_uid, content, None, 1);
 
-    match content.update(&database.get_connection()) {
-        Ok(_) => Some(true),
-        Err(e) => {
-            eprintln!("[Snapshots] Failed to update! {}", e);
-            Some(false)
-        }
-    }
+        Ok(content.update(&database.get_connection())?)
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn get_snapshot(file_uid: String, database: State<Database>) -> Option<Snapshots> {
-    match Snapshots::get(&file_uid, &database.get_connection()) {
-        Ok(snapshot) => Some(snapshot),
-        Err(e) => {
-            eprintln!("[Snapshots] Failed to fetch! {}", e);
-            None
-        }
-    }
+pub async fn get_snapshot(
+    file_uid: String,
+    database: State<'_, Database>,
+) -> Result<Snapshots, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || Ok(Snapshots::get(&file_uid, &database.get_reader())?)).await
 }
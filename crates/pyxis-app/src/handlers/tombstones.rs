@@ -0,0 +1,54 @@
+use pyxis_shared::{
+    database::Database,
+    entities::{attachments::Attachment, queue::Source, tombstones::Tombstone},
+    error::AppError,
+};
+use rusqlite::Connection;
+use tauri::State;
+
+use super::attachments::remove_unreferenced_blob;
+use super::blocking;
+
+// Whether a record received from another device was not deleted here after it was written
+pub fn is_admitted(connection: &Connection, source: Source, uid: &str, edited_at: i64) -> bool {
+    match Tombstone::admit(connection, &source, uid, edited_at) {
+        Ok(true) => true,
+        Ok(false) => {
+            println!(
+                "[Tombstones] Skipping {} {}. Deleted on this device.",
+                source.to_string(),
+                uid
+            );
+            false
+        }
+        Err(e) => {
+            eprintln!("[Tombstones] Failed to check! {}", e);
+            false
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn apply_tombstone(
+    tombstone: Tombstone,
+    database: State<'_, Database>,
+) -> Result<(), AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        let connection = database.get_connection();
+
+        let attachment = match tombstone.source {
+            Source::Attachment => Attachment::get_by_uid(&connection, &tombstone.uid).ok(),
+            _ => None,
+        };
+
+        let deleted = tombstone.apply_remote(&connection)?;
+        if let (true, Some(attachment)) = (deleted, attachment) {
+            remove_unreferenced_blob(&connection, &attachment.hash);
+        }
+
+        Ok(())
+    })
+    .await
+}
@@ -3,58 +3,73 @@ use std::str::FromStr;
 use pyxis_shared::{
     database::ConfigDatabase,
     entities::{queue::Source, tracker::Tracker},
+    error::AppError,
 };
 use tauri::State;
 use uuid::Uuid;
 
+use super::blocking;
+
+fn parse_source(source: &str) -> Result<Source, AppError> {
+    Source::from_str(source)
+        .map_err(|_| AppError::InvalidInput(format!("Unknown source {}", source)))
+}
+
+fn parse_uuid(uuid: &str) -> Result<Uuid, AppError> {
+    Uuid::from_str(uuid).map_err(|e| AppError::InvalidInput(format!("{}: {}", uuid, e)))
+}
+
 #[tauri::command]
-pub fn last_synced_record_id(
-    sync_db: State<ConfigDatabase>,
+pub async fn last_synced_record_id(
+    sync_db: State<'_, ConfigDatabase>,
     sources: Vec<String>,
     device_id: String,
     user_id: String,
-) -> Option<i64> {
-    let sources: Vec<Source> = sources
-        .into_iter()
-        .map(|source| Source::from_str(&source).unwrap())
-        .collect();
-
-    match Tracker::get(
-        &sync_db.0.get_connection(),
-        sources,
-        Uuid::from_str(&device_id).unwrap(),
-        Uuid::from_str(&user_id).unwrap(),
-    ) {
-        Ok(record) => Some(record.record_id),
-        Err(e) => {
-            eprintln!("[Tracker] Failed to fetch! {}", e);
-            Some(0)
+) -> Result<i64, AppError> {
+    let sync_db = sync_db.inner().clone();
+
+    blocking(move || {
+        let sources = sources
+            .iter()
+            .map(|source| parse_source(source))
+            .collect::<Result<Vec<Source>, AppError>>()?;
+
+        match Tracker::get(
+            &sync_db.0.get_reader(),
+            sources,
+            parse_uuid(&device_id)?,
+            parse_uuid(&user_id)?,
+        ) {
+            Ok(record) => Ok(record.record_id),
+            // Nothing read from the device yet
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(0),
+            Err(e) => Err(e.into()),
         }
-    }
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn add_record(
-    sync_db: State<ConfigDatabase>,
+pub async fn add_record(
+    sync_db: State<'_, ConfigDatabase>,
     source: String,
     device_id: String,
     record_id: i64,
     user_id: String,
-) -> Option<bool> {
-    let record = Tracker::new(
-        None,
-        Uuid::from_str(&device_id).unwrap(),
-        Source::from_str(&source).unwrap(),
-        record_id,
-        Uuid::from_str(&user_id).unwrap(),
-        None,
-    );
-
-    match record.add(&sync_db.0.get_connection()) {
-        Ok(_) => Some(true),
-        Err(e) => {
-            eprintln!("[Tracker] Failed to add! {}", e);
-            Some(false)
-        }
-    }
+) -> Result<(), AppError> {
+    let sync_db = sync_db.inner().clone();
+
+    blocking(move || {
+        let record = Tracker::new(
+            None,
+            parse_uuid(&device_id)?,
+            parse_source(&source)?,
+            record_id,
+            parse_uuid(&user_id)?,
+            None,
+        );
+
+        Ok(record.add(&sync_db.0.get_connection())?)
+    })
+    .await
 }
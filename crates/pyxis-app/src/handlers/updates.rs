@@ -1,35 +1,39 @@
-use pyxis_shared::{database::Database, entities::updates::Updates};
+use pyxis_shared::{database::Database, entities::updates::Updates, error::AppError};
 use tauri::State;
 
+use super::blocking;
+
 #[tauri::command]
-pub fn insert_updates(
+pub async fn insert_updates(
     file_uid: String,
     snapshot_id: i64,
     content: Vec<u8>,
-    database: State<Database>,
-) -> Option<bool> {
-    let update = Updates::new(file_uid, content, None, snapshot_id);
+    database: State<'_, Database>,
+) -> Result<(), AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        let update = Updates::new(file_uid, content, None, snapshot_id);
 
-    match update.insert(&database.get_connection()) {
-        Ok(_) => Some(true),
-        Err(e) => {
-            eprintln!("[Updates] Failed to update! {}", e);
-            Some(false)
-        }
-    }
+        Ok(update.insert(&database.get_connection())?)
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn get_updates(
+pub async fn get_updates(
     file_uid: String,
     snapshot_id: i64,
-    database: State<Database>,
-) -> Option<Vec<Vec<u8>>> {
-    match Updates::get(&file_uid, snapshot_id, &database.get_connection()) {
-        Ok(content) => Some(content),
-        Err(e) => {
-            eprintln!("[Updates] Failed to fetch! {}", e);
-            None
-        }
-    }
+    database: State<'_, Database>,
+) -> Result<Vec<Vec<u8>>, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        Ok(Updates::get(
+            &file_uid,
+            snapshot_id,
+            &database.get_reader(),
+        )?)
+    })
+    .await
 }
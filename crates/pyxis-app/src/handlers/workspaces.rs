@@ -1,94 +1,176 @@
 use chrono::Utc;
-use pyxis_shared::{database::Database, entities::workspaces::Workspace};
+use pyxis_shared::{
+    database::Database,
+    entities::{
+        conflicts::{update_local, update_remote, FieldClocks},
+        daily_notes::DailyNote,
+        queue::Source,
+        tombstones::Tombstone,
+        workspaces::{Workspace, WorkspaceSettings},
+    },
+    error::AppError,
+};
+use rusqlite::Error;
 use tauri::State;
 
+use super::blocking;
+use super::tombstones::is_admitted;
+
 #[tauri::command]
-pub fn create_workspace(
+pub async fn create_workspace(
     name: String,
     selected: bool,
     created_at: Option<String>,
     updated_at: Option<String>,
     uid: Option<String>,
     synced: Option<bool>,
-    database: State<Database>,
-) -> Option<Workspace> {
-    let workspace = Workspace::new(name, selected, None, created_at, updated_at, uid, synced);
-
-    match workspace.create(&database.get_connection()) {
-        Ok(_) => Some(workspace),
-        Err(e) => {
-            eprintln!("[Workspaces] Failed to create! {}", e);
-            None
+    settings: Option<WorkspaceSettings>,
+    database: State<'_, Database>,
+) -> Result<Option<Workspace>, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        let workspace = Workspace::new(
+            name, selected, None, created_at, updated_at, uid, synced, settings,
+        );
+
+        let conn = &database.get_connection();
+        if synced == Some(true)
+            && !is_admitted(
+                conn,
+                Source::Workspace,
+                &workspace.uid,
+                Tombstone::millis(&workspace.updated_at),
+            )
+        {
+            return Ok(None);
         }
-    }
+
+        workspace.create(conn)?;
+        Ok(Some(workspace))
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn list_workspaces(database: State<Database>) -> Option<Vec<Workspace>> {
-    match Workspace::list(&database.get_connection()) {
-        Ok(workspaces) => Some(workspaces),
-        Err(e) => {
-            eprintln!("[Workspaces] Failed to fetch! Error: {e}");
-            None
-        }
-    }
+pub async fn list_workspaces(database: State<'_, Database>) -> Result<Vec<Workspace>, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || Ok(Workspace::list(&database.get_reader())?)).await
 }
 
 #[tauri::command]
-pub fn delete_workspace(uid: String, database: State<Database>) -> bool {
-    match Workspace::delete(uid, &database.get_connection()) {
-        Ok(_) => true,
-        Err(e) => {
-            eprintln!("[Workspaces] Failed to delete! {}", e);
-            false
-        }
-    }
+pub async fn delete_workspace(uid: String, database: State<'_, Database>) -> Result<(), AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || Ok(Workspace::delete(uid, &database.get_connection())?)).await
 }
 
 #[tauri::command]
-pub fn update_workspace(
+pub async fn update_workspace(
     uid: String,
     name: String,
     selected: bool,
-    database: State<Database>,
+    database: State<'_, Database>,
     synced: Option<bool>,
-) -> Option<Workspace> {
-    let conn = &database.get_connection();
-    let workspace = match Workspace::list(conn) {
-        Ok(w) => w.into_iter().find(|w| w.uid == uid),
-        Err(e) => {
-            eprintln!("[Workspaces] Failed to get for update! {}", e);
-            None
+    settings: Option<WorkspaceSettings>,
+    field_clocks: Option<FieldClocks>,
+) -> Result<Option<Workspace>, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        let conn = &database.get_connection();
+        let field_clocks = field_clocks.unwrap_or_default();
+        if synced == Some(true)
+            && !is_admitted(
+                conn,
+                Source::Workspace,
+                &uid,
+                Tombstone::last_edit(&field_clocks),
+            )
+        {
+            return Ok(None);
         }
-    };
 
-    if let Some(mut workspace) = workspace {
+        let mut workspace = match Workspace::get_by_uid(conn, &uid) {
+            Ok(w) => w,
+            // Edited on another device after it was deleted here
+            Err(Error::QueryReturnedNoRows) if synced == Some(true) => {
+                let mut workspace = Workspace::new(
+                    name,
+                    selected,
+                    None,
+                    None,
+                    None,
+                    Some(uid),
+                    synced,
+                    settings,
+                );
+                workspace.field_clocks = field_clocks;
+
+                workspace.create(conn)?;
+                return Ok(Some(workspace));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
         workspace.name = name;
         workspace.selected = selected;
         workspace.updated_at = Utc::now().to_rfc3339();
         workspace.synced = synced;
-
-        match workspace.update(conn) {
-            Ok(_) => {
-                return Some(workspace);
-            }
-            Err(e) => {
-                eprintln!("[Workspaces] Failed to update! {}", e);
-                return None;
-            }
+        if let Some(settings) = settings {
+            workspace.settings = settings;
         }
-    }
 
-    None
+        // Rows synced from other devices are merged field by field
+        let result = if synced == Some(true) {
+            workspace.field_clocks = field_clocks;
+            update_remote(conn, workspace)
+        } else {
+            update_local(conn, workspace)
+        };
+
+        Ok(Some(result?))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_workspace_id(
+    name: String,
+    database: State<'_, Database>,
+) -> Result<i64, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || Ok(Workspace::get_by_name(&database.get_reader(), name)?)).await
 }
 
 #[tauri::command]
-pub fn get_workspace_id(name: String, database: State<Database>) -> Option<i64> {
-    match Workspace::get_by_name(&database.get_connection(), name) {
-        Ok(workspace_id) => Some(workspace_id),
-        Err(e) => {
-            eprintln!("[Workspaces] Failed to get id! Error: {e}");
-            None
+pub async fn get_workspace_settings(
+    uid: String,
+    database: State<'_, Database>,
+) -> Result<WorkspaceSettings, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || Ok(Workspace::get_settings(&database.get_reader(), uid)?)).await
+}
+
+#[tauri::command]
+pub async fn update_workspace_settings(
+    uid: String,
+    settings: WorkspaceSettings,
+    synced: Option<bool>,
+    database: State<'_, Database>,
+) -> Result<WorkspaceSettings, AppError> {
+    let database = database.inner().clone();
+
+    blocking(move || {
+        if let Some(format) = &settings.daily_note_format {
+            DailyNote::validate_format(format)?;
         }
-    }
+
+        Workspace::update_settings(&database.get_connection(), uid, &settings, synced)?;
+        Ok(settings)
+    })
+    .await
 }
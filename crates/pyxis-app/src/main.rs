@@ -1,49 +1,68 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 mod handlers;
-mod hooks;
 mod migrations;
 mod sidecar;
 
+use handlers::attachments::{
+    add_attachment, attachment_protocol, delete_attachment, get_attachment, list_attachments,
+};
+use handlers::bootstrap::{get_bootstrap, repush_all, request_bootstrap};
 use handlers::config::{
-    add_user_data, get_config, get_device_id, get_logged_in_user, remove_user_data,
+    add_user_data, get_config, get_device_id, get_logged_in_user, get_network_settings,
+    remove_user_data, set_network_settings,
 };
+use handlers::conflicts::{list_conflicts, resolve_conflict};
+use handlers::daily_notes::{list_daily_notes, open_daily_note};
 use handlers::devices::{add_devices, list_devices};
 use handlers::directories::{create_dir, delete_dir, get_directory_id, list_dirs, update_dir};
-use handlers::files::{create_file, delete_file, get_file_id, list_files, update_file};
+use handlers::files::{
+    create_file, delete_file, get_file_id, list_files, list_pinned_files, list_recent_files,
+    pin_file, record_file_activity, unpin_file, update_file,
+};
+use handlers::sidecar::{restart_sync, start_sync, stop_sync};
 use handlers::snapshots::{get_snapshot, update_snapshot};
+use handlers::tombstones::apply_tombstone;
 use handlers::tracker::{add_record, last_synced_record_id};
 use handlers::updates::{get_updates, insert_updates};
 use handlers::workspaces::{
-    create_workspace, delete_workspace, get_workspace_id, list_workspaces, update_workspace,
+    create_workspace, delete_workspace, get_workspace_id, get_workspace_settings, list_workspaces,
+    update_workspace, update_workspace_settings,
 };
-use hooks::content_hook;
-use migrations::{run_config_migrations, run_migrations};
+use migrations::{run_config_migrations, run_migrations, MigrationError};
 use pyxis_shared::database::{ConfigDatabase, Database};
-use sidecar::start_sync_worker;
+use sidecar::{start_sync_worker, SyncSidecar};
 use tauri::{App, Manager};
 
+// Read-only connections per database, next to the single writer
+const DOCS_READERS: usize = 4;
+const SYNC_READERS: usize = 2;
+
 fn main() {
-    let mut database = Database::create_connection("pyxis_docs");
+    let mut database = Database::create_pool("pyxis_docs", DOCS_READERS);
 
     /*
      * SQLite locks the database file during inserts and updates. Hence, a different
      * database to handle queue operations
      */
-    let mut sync_db = ConfigDatabase(Database::create_connection("pyxis_sync"));
+    let mut sync_db = ConfigDatabase(Database::create_pool("pyxis_sync", SYNC_READERS));
 
+    /*
+     * Opening a database written by a newer version would corrupt it the first
+     * time an older query touches a changed table
+     */
     match run_migrations(&mut database) {
         Ok(_) => println!("Migration successful!"),
+        Err(e @ MigrationError::NewerSchema { .. }) => panic!("{}. Aborting...", e),
         Err(e) => eprintln!("Migration failed! Error: {}", e),
     }
 
     match run_config_migrations(&mut sync_db.0) {
         Ok(_) => println!("Migration successful (config)!"),
+        Err(e @ MigrationError::NewerSchema { .. }) => panic!("{} (config). Aborting...", e),
         Err(e) => eprintln!("Migration failed (config)! Error: {}", e),
     }
 
-    database.set_update_hook(content_hook, &sync_db);
-
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
@@ -52,6 +71,8 @@ fn main() {
         .plugin(tauri_plugin_prevent_default::init())
         .manage(database)
         .manage(sync_db)
+        .manage(SyncSidecar::default())
+        .register_uri_scheme_protocol("attachment", attachment_protocol)
         .invoke_handler(tauri::generate_handler![
             create_workspace,
             list_workspaces,
@@ -80,7 +101,31 @@ fn main() {
             get_workspace_id,
             add_record,
             get_logged_in_user,
-            get_device_id
+            get_device_id,
+            get_network_settings,
+            set_network_settings,
+            get_workspace_settings,
+            update_workspace_settings,
+            open_daily_note,
+            list_daily_notes,
+            pin_file,
+            unpin_file,
+            list_pinned_files,
+            list_recent_files,
+            record_file_activity,
+            add_attachment,
+            get_attachment,
+            list_attachments,
+            delete_attachment,
+            list_conflicts,
+            resolve_conflict,
+            apply_tombstone,
+            start_sync,
+            stop_sync,
+            restart_sync,
+            request_bootstrap,
+            get_bootstrap,
+            repush_all,
         ])
         .setup(|app: &mut App| {
             let window = app
@@ -89,7 +134,7 @@ fn main() {
             // Doing this in tauri config does not allow super + arrow keys to work
             let _ = window.maximize();
             // window.open_devtools();
-            start_sync_worker(app, window);
+            start_sync_worker(app.handle());
             Ok(())
         })
         .run(tauri::generate_context!())
@@ -1,22 +1,34 @@
+mod attachments;
 mod config;
+mod conflicts;
 mod devices;
 mod directories;
+mod file_activity;
 mod files;
 mod listener_queue;
+mod outbox;
 mod snapshots;
+mod sql_migration;
+mod tombstones;
 mod tracker;
 mod updates;
 mod workspaces;
 
-use std::{collections::HashMap, fmt::Debug, rc::Rc};
+use std::{collections::HashSet, fmt::Debug, rc::Rc};
 
+use attachments::AttachmentsMigration;
 use config::ConfigurationMigration;
+use conflicts::ConflictsMigration;
 use devices::DevicesMigration;
 use directories::DirectoriesMigration;
+use file_activity::FileActivityMigration;
 use files::FilesMigration;
 use listener_queue::ListenerQueueMigration;
-use rusqlite::{types::ToSqlOutput, Error, Row, ToSql, Transaction};
+use outbox::OutboxMigration;
+use rusqlite::{types::ToSqlOutput, Connection, Error, Row, ToSql, Transaction};
 use snapshots::SnapshotsMigration;
+use sql_migration::SqlMigration;
+use tombstones::TombstonesMigration;
 use tracker::TrackerMigration;
 use updates::UpdatesMigration;
 use workspaces::WorkspaceMigration;
@@ -50,11 +62,43 @@ impl ToSql for MigrationStatus {
 pub trait Migrations {
     fn get_name(&self) -> String;
     fn run(&self, transaction: &Transaction) -> Result<usize, Error>;
-    fn clone_box(&self) -> Box<dyn MigrationsTrait>;
 }
 
+#[derive(Debug)]
+pub enum MigrationError {
+    Sqlite(Error),
+    // The database was written by a newer version of the app
+    NewerSchema { found: i64, supported: i64 },
+}
+
+impl std::fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MigrationError::Sqlite(e) => write!(f, "{}", e),
+            MigrationError::NewerSchema { found, supported } => write!(
+                f,
+                "Database schema version {} is newer than the supported version {}",
+                found, supported
+            ),
+        }
+    }
+}
+
+impl From<Error> for MigrationError {
+    fn from(e: Error) -> Self {
+        MigrationError::Sqlite(e)
+    }
+}
+
+/*
+ * Migrations are numbered and run in order. The version of the last applied
+ * migration is kept in `PRAGMA user_version`. Every migration also keeps its
+ * name keyed status row in the `migrations` table, which is how installs that
+ * predate versioning are brought up to date without re-running anything.
+ * Versions must never be reused or reordered once released.
+ */
 struct Migration {
-    entites: Rc<Vec<Box<dyn MigrationsTrait>>>,
+    entites: Rc<Vec<(i64, Box<dyn MigrationsTrait>)>>,
 }
 
 impl Migration {
@@ -64,18 +108,37 @@ impl Migration {
         Ok((name, status))
     }
 
-    fn dup_check(&self) -> bool {
-        let map: HashMap<String, bool> = HashMap::new();
+    fn sanity_check(&self) -> bool {
+        let mut names: HashSet<String> = HashSet::new();
+        let mut last_version = 0;
 
-        for entity in self.entites.iter() {
-            if map.contains_key(&entity.get_name()) {
+        for (version, entity) in self.entites.iter() {
+            if !names.insert(entity.get_name()) || *version <= last_version {
                 return false;
             }
+
+            last_version = *version;
         }
 
         true
     }
 
+    fn latest_version(&self) -> i64 {
+        self.entites
+            .last()
+            .map(|(version, _)| *version)
+            .unwrap_or(0)
+    }
+
+    fn get_version(conn: &Connection) -> Result<i64, Error> {
+        conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+    }
+
+    fn set_version(conn: &Connection, version: i64) -> Result<(), Error> {
+        // Pragmas do not accept bound parameters
+        conn.execute_batch(&format!("PRAGMA user_version = {}", version))
+    }
+
     fn init(&self, database: &Database) -> Result<usize, Error> {
         let sql = "CREATE TABLE IF NOT EXISTS migrations (
             id   INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -83,57 +146,34 @@ impl Migration {
             status TEXT NOT NULL
         )";
 
-        if !self.dup_check() {
-            panic!("Migration names must be unique!");
+        if !self.sanity_check() {
+            panic!("Migration names must be unique and versions must be increasing!");
         }
 
         database.get_connection().execute(sql, ())
     }
 
-    fn list_migrations_to_run(&self, database: &Database) -> Self {
-        let names = self.entites.iter().map(|e| e.get_name());
-        let str_names = names
-            .map(|name| format!("'{}'", name))
-            .collect::<Vec<String>>()
-            .join(",");
-
+    fn list_succeeded(&self, database: &Database) -> Result<HashSet<String>, Error> {
         let conn = database.get_connection();
+        let mut sql = conn.prepare("SELECT name, status FROM migrations")?;
 
-        let query = format!(
-            "SELECT name, status FROM migrations WHERE name IN ({})",
-            str_names
-        );
-        let mut sql = conn.prepare(&query).expect("Failed to prepare statement!");
-
-        let migrations_iter = sql
-            .query_map([], |row| Migration::from_row(row))
-            .expect("Failed to execute query!");
-
-        let migrations: Vec<(String, String)> =
-            migrations_iter.collect::<Result<Vec<_>, _>>().unwrap();
+        let migrations = sql
+            .query_map([], Migration::from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
 
-        let filtered_entities = {
-            let mut entities: Vec<Box<dyn MigrationsTrait>> = vec![];
-
-            for entity in self.entites.iter() {
-                if (migrations
-                    .iter()
-                    .find(|m| m.0 == entity.get_name() && m.1 == String::from("success")))
-                .is_none()
-                {
-                    entities.push(entity.clone_box())
-                }
-            }
-
-            entities
-        };
-
-        Self {
-            entites: Rc::new(filtered_entities),
-        }
+        Ok(migrations
+            .into_iter()
+            .filter(|(_, status)| status == "success")
+            .map(|(name, _)| name)
+            .collect())
     }
 
-    fn run(&self, entity: &Box<dyn MigrationsTrait>, database: &mut Database) -> Result<(), Error> {
+    fn run(
+        &self,
+        version: i64,
+        entity: &dyn MigrationsTrait,
+        database: &mut Database,
+    ) -> Result<(), Error> {
         database.get_connection().execute(
             "INSERT INTO migrations (name, status) VALUES (?1, ?2) ON CONFLICT(name) DO UPDATE SET status=?2",
             (entity, MigrationStatus::InProgress),
@@ -141,11 +181,14 @@ impl Migration {
 
         let mut conn = database.get_connection();
         let transaction = conn.transaction()?;
-        match entity.run(&transaction) {
+        match entity
+            .run(&transaction)
+            .and_then(|_| Migration::set_version(&transaction, version))
+        {
             Ok(_) => {
                 println!(
-                    "Migration ran for entity: {:?}. Attempting to commit!",
-                    entity
+                    "Migration {} ran for entity: {:?}. Attempting to commit!",
+                    version, entity
                 );
                 match transaction.commit() {
                     Ok(_) => {
@@ -175,72 +218,235 @@ impl Migration {
             }
         }
     }
+
+    fn migrate(&self, database: &mut Database) -> Result<(), MigrationError> {
+        self.init(database)?;
+
+        let current_version = Migration::get_version(&database.get_connection())?;
+        let latest_version = self.latest_version();
+        println!(
+            "Schema version: {}, latest: {}",
+            current_version, latest_version
+        );
+
+        if current_version > latest_version {
+            return Err(MigrationError::NewerSchema {
+                found: current_version,
+                supported: latest_version,
+            });
+        }
+
+        let succeeded = self.list_succeeded(database)?;
+
+        for (version, entity) in self.entites.iter() {
+            if *version <= current_version {
+                continue;
+            }
+
+            // Applied before the schema was versioned
+            if succeeded.contains(&entity.get_name()) {
+                Migration::set_version(&database.get_connection(), *version)?;
+                continue;
+            }
+
+            // Later migrations may depend on this one, so stop at the first failure
+            self.run(*version, entity.as_ref(), database)?;
+        }
+
+        Ok(())
+    }
 }
 
-pub fn run_migrations(database: &mut Database) -> Result<(), Error> {
+pub fn run_migrations(database: &mut Database) -> Result<(), MigrationError> {
     let migration = Migration {
         entites: Rc::new(vec![
-            Box::new(WorkspaceMigration {
-                name: String::from("workspace_migration"),
-            }),
-            Box::new(DirectoriesMigration {
-                name: String::from("directories_migration"),
-            }),
-            Box::new(FilesMigration {
-                name: String::from("files_migration"),
-            }),
-            Box::new(SnapshotsMigration {
-                name: String::from("snapshots_migration"),
-            }),
-            Box::new(UpdatesMigration {
-                name: String::from("updates_migration"),
-            }),
+            (
+                1,
+                Box::new(WorkspaceMigration {
+                    name: String::from("workspace_migration"),
+                }),
+            ),
+            (
+                2,
+                Box::new(DirectoriesMigration {
+                    name: String::from("directories_migration"),
+                }),
+            ),
+            (
+                3,
+                Box::new(FilesMigration {
+                    name: String::from("files_migration"),
+                }),
+            ),
+            (
+                4,
+                Box::new(SnapshotsMigration {
+                    name: String::from("snapshots_migration"),
+                }),
+            ),
+            (
+                5,
+                Box::new(UpdatesMigration {
+                    name: String::from("updates_migration"),
+                }),
+            ),
+            (
+                6,
+                Box::new(SqlMigration::new(
+                    "workspace_settings_migration",
+                    &["ALTER TABLE workspaces ADD COLUMN settings TEXT"],
+                )),
+            ),
+            (
+                7,
+                Box::new(SqlMigration::new(
+                    "files_pinned_migration",
+                    &["ALTER TABLE files ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0"],
+                )),
+            ),
+            (
+                8,
+                Box::new(FileActivityMigration {
+                    name: String::from("file_activity_migration"),
+                }),
+            ),
+            (
+                9,
+                Box::new(AttachmentsMigration {
+                    name: String::from("attachments_migration"),
+                }),
+            ),
+            (
+                10,
+                // Clocks of the last edit to each synced field, used to merge concurrent edits
+                Box::new(SqlMigration::new(
+                    "field_clocks_migration",
+                    &[
+                        "ALTER TABLE workspaces ADD COLUMN field_clocks TEXT",
+                        "ALTER TABLE directories ADD COLUMN field_clocks TEXT",
+                        "ALTER TABLE files ADD COLUMN field_clocks TEXT",
+                    ],
+                )),
+            ),
+            (
+                11,
+                Box::new(ConflictsMigration {
+                    name: String::from("conflicts_migration"),
+                }),
+            ),
+            (
+                12,
+                Box::new(OutboxMigration {
+                    name: String::from("outbox_migration"),
+                }),
+            ),
+            (
+                13,
+                Box::new(TombstonesMigration {
+                    name: String::from("tombstones_migration"),
+                }),
+            ),
         ]),
     };
 
-    migration.init(database)?;
-    println!("Migration init successful");
-
-    let migrations_to_run = migration.list_migrations_to_run(database);
-    println!("Migrations to run: {:?}", migrations_to_run.entites);
-
-    for entity in migrations_to_run.entites.iter() {
-        migration.run(entity, database)?;
-    }
+    migration.migrate(database)?;
+    println!("Migrations up to date");
 
     Ok(())
 }
 
-pub fn run_config_migrations(database: &mut Database) -> Result<(), Error> {
+pub fn run_config_migrations(database: &mut Database) -> Result<(), MigrationError> {
     let migration = Migration {
         entites: Rc::new(vec![
-            Box::new(ListenerQueueMigration {
-                name: String::from("listener_queue_migration"),
-            }),
-            Box::new(ConfigurationMigration {
-                name: String::from("configuration_migration"),
-            }),
-            Box::new(DevicesMigration {
-                name: String::from("devices_migration"),
-            }),
-            Box::new(TrackerMigration {
-                name: String::from("tracker_migration"),
-            }),
+            (
+                1,
+                Box::new(ListenerQueueMigration {
+                    name: String::from("listener_queue_migration"),
+                }),
+            ),
+            (
+                2,
+                Box::new(ConfigurationMigration {
+                    name: String::from("configuration_migration"),
+                }),
+            ),
+            (
+                3,
+                Box::new(DevicesMigration {
+                    name: String::from("devices_migration"),
+                }),
+            ),
+            (
+                4,
+                Box::new(TrackerMigration {
+                    name: String::from("tracker_migration"),
+                }),
+            ),
+            (
+                5,
+                // Lets the sidecar tell which outbox entries already made it into the queue
+                Box::new(SqlMigration::new(
+                    "listener_queue_outbox_migration",
+                    &["ALTER TABLE listener_queue ADD COLUMN outbox_id INTEGER"],
+                )),
+            ),
+            (
+                6,
+                // Lets pending changes to the same record be folded together
+                Box::new(SqlMigration::new(
+                    "listener_queue_coalesce_migration",
+                    &[
+                        "ALTER TABLE listener_queue ADD COLUMN record_uid TEXT",
+                        "ALTER TABLE listener_queue ADD COLUMN queued_at TEXT",
+                        "CREATE INDEX IF NOT EXISTS listener_queue_record_idx ON listener_queue (source, record_uid)",
+                    ],
+                )),
+            ),
+            (
+                7,
+                Box::new(SqlMigration::new(
+                    "network_settings_migration",
+                    &["CREATE TABLE IF NOT EXISTS network_settings (
+                        id       INTEGER PRIMARY KEY CHECK (id = 1),
+                        settings TEXT NOT NULL
+                    )"],
+                )),
+            ),
+            (
+                8,
+                // Lets a full download from the server resume where it stopped
+                Box::new(SqlMigration::new(
+                    "bootstrap_migration",
+                    &[
+                        "CREATE TABLE IF NOT EXISTS bootstrap (
+                            id           INTEGER PRIMARY KEY CHECK (id = 1),
+                            status       TEXT NOT NULL,
+                            requested_at TEXT NOT NULL,
+                            finished_at  TEXT,
+                            error        TEXT
+                        )",
+                        "CREATE TABLE IF NOT EXISTS bootstrap_progress (
+                            device_id TEXT NOT NULL,
+                            source    TEXT NOT NULL,
+                            record_id INTEGER NOT NULL,
+                            PRIMARY KEY (device_id, source)
+                        )",
+                    ],
+                )),
+            ),
+            (
+                9,
+                // Sent with every write so the server can tell a retry from a new change
+                Box::new(SqlMigration::new(
+                    "listener_queue_idempotency_migration",
+                    &["ALTER TABLE listener_queue ADD COLUMN idempotency_key TEXT"],
+                )),
+            ),
         ]),
     };
 
-    migration.init(database)?;
-    println!("Migration init successful (config)");
-
-    let migrations_to_run = migration.list_migrations_to_run(database);
-    println!(
-        "Migrations to run (config): {:?}",
-        migrations_to_run.entites
-    );
-
-    for entity in migrations_to_run.entites.iter() {
-        migration.run(entity, database)?;
-    }
+    migration.migrate(database)?;
+    println!("Migrations up to date (config)");
 
     Ok(())
 }
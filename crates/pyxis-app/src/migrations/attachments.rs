@@ -0,0 +1,56 @@
+use super::{Migrations, MigrationsTrait};
+use rusqlite::{
+    types::{FromSql, ToSqlOutput},
+    ToSql, Transaction,
+};
+use std::fmt::Debug;
+
+#[derive(Debug, Clone)]
+pub struct AttachmentsMigration {
+    pub name: String,
+}
+
+impl ToSql for AttachmentsMigration {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::Owned(rusqlite::types::Value::Text(
+            self.name.clone(),
+        )))
+    }
+}
+
+impl FromSql for AttachmentsMigration {
+    fn column_result(
+        value: rusqlite::types::ValueRef<'_>,
+    ) -> Result<Self, rusqlite::types::FromSqlError> {
+        value.as_str().map(|s| AttachmentsMigration {
+            name: s.to_string(),
+        })
+    }
+}
+
+impl Migrations for AttachmentsMigration {
+    fn run(&self, transaction: &Transaction) -> Result<usize, rusqlite::Error> {
+        let sql = "CREATE TABLE IF NOT EXISTS attachments (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            uid         TEXT NOT NULL UNIQUE,
+            file_id     INTEGER NOT NULL,
+            name        TEXT NOT NULL,
+            mime_type   TEXT NOT NULL,
+            hash        TEXT NOT NULL,
+            size        INTEGER NOT NULL,
+            created_at  TEXT NOT NULL,
+            synced      INTEGER,
+
+            FOREIGN KEY (file_id) REFERENCES files(id)
+            ON DELETE CASCADE
+        )";
+
+        transaction.execute(sql, ())
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl MigrationsTrait for AttachmentsMigration {}
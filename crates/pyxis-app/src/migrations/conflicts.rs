@@ -0,0 +1,56 @@
+use super::{Migrations, MigrationsTrait};
+use rusqlite::{
+    types::{FromSql, ToSqlOutput},
+    ToSql, Transaction,
+};
+use std::fmt::Debug;
+
+#[derive(Debug, Clone)]
+pub struct ConflictsMigration {
+    pub name: String,
+}
+
+impl ToSql for ConflictsMigration {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::Owned(rusqlite::types::Value::Text(
+            self.name.clone(),
+        )))
+    }
+}
+
+impl FromSql for ConflictsMigration {
+    fn column_result(
+        value: rusqlite::types::ValueRef<'_>,
+    ) -> Result<Self, rusqlite::types::FromSqlError> {
+        value.as_str().map(|s| ConflictsMigration {
+            name: s.to_string(),
+        })
+    }
+}
+
+impl Migrations for ConflictsMigration {
+    fn run(&self, transaction: &Transaction) -> Result<usize, rusqlite::Error> {
+        let sql = "CREATE TABLE IF NOT EXISTS conflicts (
+            id              INTEGER PRIMARY KEY AUTOINCREMENT,
+            source          TEXT NOT NULL,
+            record_uid      TEXT NOT NULL,
+            field           TEXT NOT NULL,
+            local_value     TEXT NOT NULL,
+            remote_value    TEXT NOT NULL,
+            local_clock     TEXT NOT NULL,
+            remote_clock    TEXT NOT NULL,
+            winner          TEXT NOT NULL,
+            resolution      TEXT,
+            created_at      TEXT NOT NULL,
+            resolved_at     TEXT
+        )";
+
+        transaction.execute(sql, ())
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl MigrationsTrait for ConflictsMigration {}
@@ -51,11 +51,6 @@ impl Migrations for DirectoriesMigration {
     fn get_name(&self) -> String {
         self.name.clone()
     }
-
-    fn clone_box(&self) -> Box<dyn MigrationsTrait> {
-        let name = self.get_name();
-        Box::new(Self { name })
-    }
 }
 
 impl MigrationsTrait for DirectoriesMigration {}
@@ -0,0 +1,53 @@
+use super::{Migrations, MigrationsTrait};
+use rusqlite::{
+    types::{FromSql, ToSqlOutput},
+    ToSql, Transaction,
+};
+use std::fmt::Debug;
+
+#[derive(Debug, Clone)]
+pub struct FileActivityMigration {
+    pub name: String,
+}
+
+impl ToSql for FileActivityMigration {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::Owned(rusqlite::types::Value::Text(
+            self.name.clone(),
+        )))
+    }
+}
+
+impl FromSql for FileActivityMigration {
+    fn column_result(
+        value: rusqlite::types::ValueRef<'_>,
+    ) -> Result<Self, rusqlite::types::FromSqlError> {
+        value.as_str().map(|s| FileActivityMigration {
+            name: s.to_string(),
+        })
+    }
+}
+
+impl Migrations for FileActivityMigration {
+    fn run(&self, transaction: &Transaction) -> Result<usize, rusqlite::Error> {
+        let sql = "CREATE TABLE IF NOT EXISTS file_activity (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            file_id     INTEGER NOT NULL UNIQUE,
+            opened_at   TEXT,
+            edited_at   TEXT,
+            open_count  INTEGER NOT NULL DEFAULT 0,
+            edit_count  INTEGER NOT NULL DEFAULT 0,
+
+            FOREIGN KEY (file_id) REFERENCES files(id)
+            ON DELETE CASCADE
+        )";
+
+        transaction.execute(sql, ())
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl MigrationsTrait for FileActivityMigration {}
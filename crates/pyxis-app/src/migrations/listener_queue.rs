@@ -46,11 +46,6 @@ impl Migrations for ListenerQueueMigration {
     fn get_name(&self) -> String {
         self.name.clone()
     }
-
-    fn clone_box(&self) -> Box<dyn MigrationsTrait> {
-        let name = self.get_name();
-        Box::new(Self { name })
-    }
 }
 
 impl MigrationsTrait for ListenerQueueMigration {}
@@ -0,0 +1,97 @@
+use super::{Migrations, MigrationsTrait};
+use rusqlite::{
+    types::{FromSql, ToSqlOutput},
+    ToSql, Transaction,
+};
+use std::fmt::Debug;
+
+#[derive(Debug, Clone)]
+pub struct OutboxMigration {
+    pub name: String,
+}
+
+impl ToSql for OutboxMigration {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::Owned(rusqlite::types::Value::Text(
+            self.name.clone(),
+        )))
+    }
+}
+
+impl FromSql for OutboxMigration {
+    fn column_result(
+        value: rusqlite::types::ValueRef<'_>,
+    ) -> Result<Self, rusqlite::types::FromSqlError> {
+        value.as_str().map(|s| OutboxMigration {
+            name: s.to_string(),
+        })
+    }
+}
+
+/*
+ * (table, operation, capture only local changes, has a uid column)
+ *
+ * Rows written with `synced` set came from another device and must not be sent back.
+ * Attachments and updates are immutable, and snapshots and updates go away with
+ * their file, so those deletes are not captured.
+ */
+const CAPTURED: &[(&str, &str, bool, bool)] = &[
+    ("workspaces", "insert", true, true),
+    ("workspaces", "update", true, true),
+    ("workspaces", "delete", false, true),
+    ("directories", "insert", true, true),
+    ("directories", "update", true, true),
+    ("directories", "delete", false, true),
+    ("files", "insert", true, true),
+    ("files", "update", true, true),
+    ("files", "delete", false, true),
+    ("attachments", "insert", true, true),
+    ("attachments", "delete", false, true),
+    ("snapshots", "insert", false, false),
+    ("snapshots", "update", false, false),
+    ("updates", "insert", false, false),
+];
+
+fn trigger_sql(table: &str, operation: &str, local_only: bool, has_uid: bool) -> String {
+    let row = if operation == "delete" { "OLD" } else { "NEW" };
+
+    format!(
+        "CREATE TRIGGER IF NOT EXISTS outbox_{table}_{operation} \
+        AFTER {} ON {table} FOR EACH ROW {} \
+        BEGIN \
+            INSERT INTO outbox (source, operation, row_id, uid, created_at) \
+            VALUES ('{table}', '{operation}', {row}.id, {}, strftime('%Y-%m-%dT%H:%M:%fZ', 'now')); \
+        END",
+        operation.to_uppercase(),
+        if local_only { "WHEN NEW.synced IS NULL" } else { "" },
+        if has_uid { format!("{row}.uid") } else { String::from("NULL") },
+    )
+}
+
+impl Migrations for OutboxMigration {
+    fn run(&self, transaction: &Transaction) -> Result<usize, rusqlite::Error> {
+        let sql = "CREATE TABLE IF NOT EXISTS outbox (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            source      TEXT NOT NULL,
+            operation   TEXT NOT NULL,
+            row_id      INTEGER NOT NULL,
+            uid         TEXT,
+            created_at  TEXT NOT NULL
+        )";
+
+        let mut changed = transaction.execute(sql, ())?;
+
+        for (table, operation, local_only, has_uid) in CAPTURED {
+            changed +=
+                transaction.execute(&trigger_sql(table, operation, *local_only, *has_uid), ())?;
+        }
+
+        Ok(changed)
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl MigrationsTrait for OutboxMigration {}
@@ -0,0 +1,62 @@
+use super::{Migrations, MigrationsTrait};
+use rusqlite::{
+    types::{FromSql, ToSqlOutput},
+    ToSql, Transaction,
+};
+use std::fmt::Debug;
+
+/*
+ * A migration made of plain SQL statements, run in order inside the migration's
+ * transaction. Suited for ALTERs and backfills that do not need any Rust logic.
+ */
+#[derive(Debug, Clone)]
+pub struct SqlMigration {
+    pub name: String,
+    pub steps: Vec<&'static str>,
+}
+
+impl SqlMigration {
+    pub fn new(name: &str, steps: &[&'static str]) -> Self {
+        Self {
+            name: String::from(name),
+            steps: steps.to_vec(),
+        }
+    }
+}
+
+impl ToSql for SqlMigration {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::Owned(rusqlite::types::Value::Text(
+            self.name.clone(),
+        )))
+    }
+}
+
+impl FromSql for SqlMigration {
+    fn column_result(
+        value: rusqlite::types::ValueRef<'_>,
+    ) -> Result<Self, rusqlite::types::FromSqlError> {
+        value.as_str().map(|s| SqlMigration {
+            name: s.to_string(),
+            steps: vec![],
+        })
+    }
+}
+
+impl Migrations for SqlMigration {
+    fn run(&self, transaction: &Transaction) -> Result<usize, rusqlite::Error> {
+        let mut changed = 0;
+
+        for step in self.steps.iter() {
+            changed += transaction.execute(step, ())?;
+        }
+
+        Ok(changed)
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl MigrationsTrait for SqlMigration {}
@@ -0,0 +1,101 @@
+use super::{Migrations, MigrationsTrait};
+use rusqlite::{
+    types::{FromSql, ToSqlOutput},
+    ToSql, Transaction,
+};
+use std::fmt::Debug;
+
+#[derive(Debug, Clone)]
+pub struct TombstonesMigration {
+    pub name: String,
+}
+
+impl ToSql for TombstonesMigration {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::Owned(rusqlite::types::Value::Text(
+            self.name.clone(),
+        )))
+    }
+}
+
+impl FromSql for TombstonesMigration {
+    fn column_result(
+        value: rusqlite::types::ValueRef<'_>,
+    ) -> Result<Self, rusqlite::types::FromSqlError> {
+        value.as_str().map(|s| TombstonesMigration {
+            name: s.to_string(),
+        })
+    }
+}
+
+// (table, uid of the file the row belongs to)
+const TOMBSTONED: &[(&str, &str)] = &[
+    ("workspaces", "NULL"),
+    ("directories", "NULL"),
+    ("files", "OLD.uid"),
+    (
+        "attachments",
+        "(SELECT uid FROM files WHERE id = OLD.file_id)",
+    ),
+];
+
+/*
+ * Replaces the outbox delete triggers. Deletes now leave a tombstone behind, and
+ * a delete received from another device, which stores its tombstone first, is
+ * not captured again.
+ */
+fn trigger_sql(table: &str, file_uid: &str) -> String {
+    format!(
+        "CREATE TRIGGER outbox_{table}_delete \
+        AFTER DELETE ON {table} FOR EACH ROW \
+        WHEN NOT EXISTS (SELECT 1 FROM tombstones WHERE source = '{table}' AND uid = OLD.uid) \
+        BEGIN \
+            INSERT INTO tombstones (source, uid, file_uid, deleted_at) \
+            VALUES ('{table}', OLD.uid, {file_uid}, strftime('%Y-%m-%dT%H:%M:%fZ', 'now')); \
+            INSERT INTO outbox (source, operation, row_id, uid, created_at) \
+            VALUES ('{table}', 'delete', OLD.id, OLD.uid, strftime('%Y-%m-%dT%H:%M:%fZ', 'now')); \
+        END"
+    )
+}
+
+// A record created again, here or on another device, is no longer deleted
+fn clear_trigger_sql(table: &str) -> String {
+    format!(
+        "CREATE TRIGGER IF NOT EXISTS tombstones_{table}_insert \
+        AFTER INSERT ON {table} FOR EACH ROW \
+        BEGIN \
+            DELETE FROM tombstones WHERE source = '{table}' AND uid = NEW.uid; \
+        END"
+    )
+}
+
+impl Migrations for TombstonesMigration {
+    fn run(&self, transaction: &Transaction) -> Result<usize, rusqlite::Error> {
+        let sql = "CREATE TABLE IF NOT EXISTS tombstones (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            source      TEXT NOT NULL,
+            uid         TEXT NOT NULL,
+            file_uid    TEXT,
+            deleted_at  TEXT NOT NULL,
+
+            UNIQUE (source, uid)
+        )";
+
+        let mut changed = transaction.execute(sql, ())?;
+
+        for (table, file_uid) in TOMBSTONED {
+            changed += transaction
+                .execute(&format!("DROP TRIGGER IF EXISTS outbox_{table}_delete"), ())?;
+            changed += transaction.execute(&trigger_sql(table, file_uid), ())?;
+            changed += transaction.execute(&clear_trigger_sql(table), ())?;
+        }
+
+        Ok(changed)
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl MigrationsTrait for TombstonesMigration {}
@@ -1,34 +1,249 @@
-use tauri::{App, Emitter, WebviewWindow};
-use tauri_plugin_shell::process::CommandEvent;
+use std::{
+    cmp::min,
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    sync::{Mutex, MutexGuard},
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_shell::process::{CommandChild, CommandEvent, TerminatedPayload};
 use tauri_plugin_shell::ShellExt;
 
-pub fn start_sync_worker(app: &App, window: WebviewWindow) {
-    match app.shell().sidecar("pyxis-sync") {
-        Ok(sidecar_command) => {
-            match sidecar_command
-                .args(["--pid", &std::process::id().to_string()])
-                .spawn()
-            {
-                Ok((mut rx, _child)) => {
-                    tauri::async_runtime::spawn(async move {
-                        // read events such as stdout
-                        while let Some(event) = rx.recv().await {
-                            if let CommandEvent::Stdout(line_bytes) = event {
-                                let line = String::from_utf8_lossy(&line_bytes);
-                                window
-                                    .emit("Sidecar_Message", Some(format!("'{}'", line)))
-                                    .expect("[Sidecar] failed to emit event");
+// Automatic restarts allowed within the window before giving up
+const MAX_RESTARTS: usize = 5;
+const RESTART_WINDOW: Duration = Duration::from_secs(10 * 60);
+const MAX_BACKOFF_SECONDS: u64 = 60;
+// The log is rotated once when it grows past this size
+const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024;
+
+#[derive(Default)]
+struct SidecarState {
+    child: Option<CommandChild>,
+    // Bumped on every spawn so that the exit of an old child is not acted upon
+    generation: u64,
+    // Whether the sidecar is meant to be running
+    enabled: bool,
+    restarts: VecDeque<Instant>,
+}
+
+/*
+ * Supervises the sync sidecar. A sidecar that exits while sync is enabled is
+ * restarted with an exponential backoff. If it keeps dying, supervision stops
+ * and the UI is told, so it can offer to start sync again by hand.
+ */
+#[derive(Default)]
+pub struct SyncSidecar(Mutex<SidecarState>);
+
+impl SyncSidecar {
+    fn lock(&self) -> MutexGuard<'_, SidecarState> {
+        match self.0.lock() {
+            Ok(state) => state,
+            Err(e) => e.into_inner(),
+        }
+    }
+}
+
+pub fn start_sync_worker(app: &AppHandle) -> bool {
+    let sidecar = app.state::<SyncSidecar>();
+    let mut state = sidecar.lock();
+
+    state.enabled = true;
+    spawn(app, &mut state)
+}
+
+pub fn stop_sync_worker(app: &AppHandle) -> bool {
+    let sidecar = app.state::<SyncSidecar>();
+    let mut state = sidecar.lock();
+
+    state.enabled = false;
+    match state.child.take() {
+        Some(child) => match child.kill() {
+            Ok(_) => true,
+            Err(e) => {
+                eprintln!("[Sidecar] Failed to stop sidecar. Error: {}", e);
+                false
+            }
+        },
+        None => true,
+    }
+}
+
+pub fn restart_sync_worker(app: &AppHandle) -> bool {
+    stop_sync_worker(app);
+
+    let sidecar = app.state::<SyncSidecar>();
+    sidecar.lock().restarts.clear();
+
+    start_sync_worker(app)
+}
+
+fn spawn(app: &AppHandle, state: &mut SidecarState) -> bool {
+    if state.child.is_some() {
+        return true;
+    }
+
+    let sidecar_command = match app.shell().sidecar("pyxis-sync") {
+        Ok(sidecar_command) => sidecar_command,
+        Err(e) => {
+            eprintln!("[Sidecar] Failed to spawn sidecar. Error: {}", e);
+            return false;
+        }
+    };
+
+    match sidecar_command
+        .args(["--pid", &std::process::id().to_string()])
+        .spawn()
+    {
+        Ok((mut rx, child)) => {
+            state.generation += 1;
+            state.child = Some(child);
+
+            let generation = state.generation;
+            let app = app.clone();
+
+            tauri::async_runtime::spawn(async move {
+                let mut log = open_log();
+
+                while let Some(event) = rx.recv().await {
+                    match event {
+                        CommandEvent::Stdout(line_bytes) => {
+                            let line = String::from_utf8_lossy(&line_bytes);
+                            if let Err(e) = app.emit("Sidecar_Message", Some(format!("'{}'", line)))
+                            {
+                                eprintln!("[Sidecar] Failed to emit event. Error: {}", e);
                             }
                         }
-                    });
-                }
-                Err(e) => {
-                    eprintln!("[Sidecar] Failed to spawn sidecar. Error: {}", e);
+                        CommandEvent::Stderr(line_bytes) => {
+                            write_log(&mut log, &String::from_utf8_lossy(&line_bytes));
+                        }
+                        CommandEvent::Error(e) => {
+                            write_log(&mut log, &format!("[Sidecar] Error: {}", e));
+                        }
+                        CommandEvent::Terminated(payload) => {
+                            write_log(
+                                &mut log,
+                                &format!(
+                                    "[Sidecar] Exited. Code: {:?}, Signal: {:?}",
+                                    payload.code, payload.signal
+                                ),
+                            );
+                            on_terminated(&app, generation, payload);
+                        }
+                        _ => {}
+                    }
                 }
-            }
+            });
+
+            true
         }
         Err(e) => {
             eprintln!("[Sidecar] Failed to spawn sidecar. Error: {}", e);
+            false
         }
     }
 }
+
+fn on_terminated(app: &AppHandle, generation: u64, payload: TerminatedPayload) {
+    let sidecar = app.state::<SyncSidecar>();
+    let mut state = sidecar.lock();
+
+    // Stopped or replaced on purpose
+    if state.generation != generation {
+        return;
+    }
+
+    state.child = None;
+
+    if !state.enabled {
+        return;
+    }
+
+    let now = Instant::now();
+    while let Some(&restart) = state.restarts.front() {
+        if now.duration_since(restart) <= RESTART_WINDOW {
+            break;
+        }
+        state.restarts.pop_front();
+    }
+
+    if state.restarts.len() >= MAX_RESTARTS {
+        state.enabled = false;
+        eprintln!(
+            "[Sidecar] Exited {} times in {} minutes. Not restarting.",
+            MAX_RESTARTS + 1,
+            RESTART_WINDOW.as_secs() / 60
+        );
+
+        if let Err(e) = app.emit("Sidecar_Stopped", payload) {
+            eprintln!("[Sidecar] Failed to emit event. Error: {}", e);
+        }
+        return;
+    }
+
+    let delay = min(2u64.pow(state.restarts.len() as u32), MAX_BACKOFF_SECONDS);
+    state.restarts.push_back(now);
+    eprintln!(
+        "[Sidecar] Exited unexpectedly. Code: {:?}. Restarting in {}s...",
+        payload.code, delay
+    );
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        sleep(Duration::from_secs(delay));
+
+        let sidecar = app.state::<SyncSidecar>();
+        let mut state = sidecar.lock();
+
+        // Stopped or started by hand while waiting
+        if state.enabled && state.generation == generation {
+            spawn(&app, &mut state);
+        }
+    });
+}
+
+fn get_log_path() -> PathBuf {
+    let mut path = dirs::data_local_dir().expect("Failed to find local data directory");
+    path.push("pyxis");
+    path.push("logs");
+
+    path
+}
+
+fn open_log() -> Option<File> {
+    let dir = get_log_path();
+    let path = dir.join("pyxis-sync.log");
+
+    if let Err(e) = fs::create_dir_all(&dir) {
+        eprintln!("[Sidecar] Failed to create log directory. Error: {}", e);
+        return None;
+    }
+
+    let size = fs::metadata(&path)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    if size > MAX_LOG_SIZE {
+        let _ = fs::rename(&path, dir.join("pyxis-sync.log.1"));
+    }
+
+    match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(file) => Some(file),
+        Err(e) => {
+            eprintln!("[Sidecar] Failed to open log file. Error: {}", e);
+            None
+        }
+    }
+}
+
+fn write_log(log: &mut Option<File>, line: &str) {
+    let line = line.trim_end();
+    eprintln!("[Sync] {}", line);
+
+    if let Some(file) = log {
+        let _ = writeln!(file, "{} {}", Utc::now().to_rfc3339(), line);
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    error::Error,
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
+
+use aws_config as Config;
+use aws_sdk_s3::{self as S3, primitives::ByteStream};
+use pyxis_shared::utils::blob_store;
+use tokio::fs;
+
+use crate::config::BlobStoreConfig;
+
+// DynamoDB items are capped at 400 KB. Larger payloads are kept as blobs.
+const MAX_INLINE_PAYLOAD: usize = 300 * 1024;
+
+// Where a payload kept as a blob is, and the sha256 it must match
+#[derive(Debug, Clone)]
+pub struct PayloadRef {
+    pub reference: String,
+    pub checksum: String,
+}
+
+enum Backend {
+    Local(PathBuf),
+    // Any S3-compatible service
+    S3 { client: S3::Client, bucket: String },
+}
+
+/*
+ * Blobs are namespaced per user and addressed by their sha256 hash. They are
+ * kept in a bucket when one is configured, with an endpoint pointing at a
+ * self-hosted service if needed, and on the local filesystem otherwise.
+ */
+pub struct BlobStore {
+    backend: Backend,
+}
+
+impl BlobStore {
+    pub async fn new(settings: &BlobStoreConfig) -> Result<BlobStore, Box<dyn Error>> {
+        if let Some(bucket) = &settings.bucket {
+            let config = Config::load_from_env().await;
+            let mut builder = S3::config::Builder::from(&config);
+            if let Some(endpoint) = &settings.endpoint {
+                builder = builder.endpoint_url(endpoint).force_path_style(true);
+            }
+
+            return Ok(BlobStore {
+                backend: Backend::S3 {
+                    client: S3::Client::from_conf(builder.build()),
+                    bucket: bucket.clone(),
+                },
+            });
+        }
+
+        let root = settings.path.clone();
+        fs::create_dir_all(&root).await?;
+
+        Ok(BlobStore {
+            backend: Backend::Local(root),
+        })
+    }
+
+    // Whether the store can be reached, for readiness checks
+    pub async fn check(&self) -> Result<(), io::Error> {
+        match &self.backend {
+            Backend::Local(root) => match fs::metadata(root).await?.is_dir() {
+                true => Ok(()),
+                false => Err(io::Error::new(
+                    ErrorKind::NotFound,
+                    format!("{} is not a directory", root.display()),
+                )),
+            },
+            Backend::S3 { client, bucket } => client
+                .head_bucket()
+                .bucket(bucket)
+                .send()
+                .await
+                .map(|_| ())
+                .map_err(io::Error::other),
+        }
+    }
+
+    fn get_key(user_id: &str, hash: &str) -> Result<String, io::Error> {
+        if !blob_store::is_valid_hash(hash) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid blob hash: {}", hash),
+            ));
+        }
+
+        Ok(format!("{}/{}", user_id, hash))
+    }
+
+    pub async fn exists(&self, user_id: &str, hash: &str) -> Result<bool, io::Error> {
+        let key = BlobStore::get_key(user_id, hash)?;
+
+        match &self.backend {
+            Backend::Local(root) => fs::try_exists(root.join(key)).await,
+            Backend::S3 { client, bucket } => {
+                match client.head_object().bucket(bucket).key(key).send().await {
+                    Ok(_) => Ok(true),
+                    Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(false),
+                    Err(e) => Err(io::Error::other(e)),
+                }
+            }
+        }
+    }
+
+    pub async fn put(&self, user_id: &str, hash: &str, content: &[u8]) -> Result<(), io::Error> {
+        let key = BlobStore::get_key(user_id, hash)?;
+
+        match &self.backend {
+            Backend::Local(root) => {
+                let path = root.join(key);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).await?;
+                }
+
+                let tmp_path = path.with_extension("tmp");
+                fs::write(&tmp_path, content).await?;
+                fs::rename(&tmp_path, &path).await
+            }
+            Backend::S3 { client, bucket } => client
+                .put_object()
+                .bucket(bucket)
+                .key(key)
+                .body(ByteStream::from(content.to_vec()))
+                .send()
+                .await
+                .map(|_| ())
+                .map_err(io::Error::other),
+        }
+    }
+
+    pub async fn get(&self, user_id: &str, hash: &str) -> Result<Vec<u8>, io::Error> {
+        let key = BlobStore::get_key(user_id, hash)?;
+
+        match &self.backend {
+            Backend::Local(root) => fs::read(root.join(key)).await,
+            Backend::S3 { client, bucket } => {
+                let object = match client.get_object().bucket(bucket).key(key).send().await {
+                    Ok(object) => object,
+                    Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => {
+                        return Err(io::Error::new(
+                            ErrorKind::NotFound,
+                            format!("No blob {}", hash),
+                        ));
+                    }
+                    Err(e) => return Err(io::Error::other(e)),
+                };
+
+                let content = object.body.collect().await.map_err(io::Error::other)?;
+                Ok(content.into_bytes().to_vec())
+            }
+        }
+    }
+
+    // Reads a blob and checks it still matches the checksum it was stored with
+    pub async fn get_verified(
+        &self,
+        user_id: &str,
+        hash: &str,
+        checksum: &str,
+    ) -> Result<Vec<u8>, io::Error> {
+        let content = self.get(user_id, hash).await?;
+        if blob_store::hash(&content) != checksum {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Blob {} does not match its checksum", hash),
+            ));
+        }
+
+        Ok(content)
+    }
+
+    /*
+     * Moves a payload too large for a DynamoDB item into the store. Returns what
+     * the item holds instead, an empty payload and a reference to the blob. Blobs
+     * are addressed by their hash, so for now the reference and the checksum are
+     * the same value.
+     */
+    pub async fn offload(
+        &self,
+        user_id: &str,
+        payload: String,
+    ) -> Result<(String, Option<PayloadRef>), io::Error> {
+        if payload.len() <= MAX_INLINE_PAYLOAD {
+            return Ok((payload, None));
+        }
+
+        let hash = blob_store::hash(payload.as_bytes());
+        if !self.exists(user_id, &hash).await? {
+            self.put(user_id, &hash, payload.as_bytes()).await?;
+        }
+
+        Ok((
+            String::new(),
+            Some(PayloadRef {
+                reference: hash.clone(),
+                checksum: hash,
+            }),
+        ))
+    }
+
+    pub async fn resolve(
+        &self,
+        user_id: &str,
+        payload_ref: &PayloadRef,
+    ) -> Result<String, io::Error> {
+        let content = self
+            .get_verified(user_id, &payload_ref.reference, &payload_ref.checksum)
+            .await?;
+
+        String::from_utf8(content).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+    }
+}
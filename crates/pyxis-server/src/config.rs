@@ -0,0 +1,292 @@
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+// Path of the TOML file. Without it, pyxis-server.toml is read if there is one.
+const CONFIG_FILE_ENV: &str = "PYXIS_SERVER_CONFIG";
+const DEFAULT_CONFIG_FILE: &str = "pyxis-server.toml";
+const MIN_METRICS_TOKEN_LENGTH: usize = 16;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    port: Option<u16>,
+    auth_secret: Option<String>,
+    subscription_request_sns: Option<String>,
+    metrics_token: Option<String>,
+    tables: FileTables,
+    blob_store: FileBlobStore,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileTables {
+    users: Option<String>,
+    tokens: Option<String>,
+    user_features: Option<String>,
+    documents_sync: Option<String>,
+    snapshots_sync: Option<String>,
+    updates_sync: Option<String>,
+    changes_sync: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileBlobStore {
+    bucket: Option<String>,
+    endpoint: Option<String>,
+    path: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tables {
+    pub users: String,
+    pub tokens: String,
+    pub user_features: String,
+    pub documents_sync: String,
+    pub snapshots_sync: String,
+    pub updates_sync: String,
+    pub changes_sync: String,
+}
+
+impl Tables {
+    pub fn all(&self) -> [&str; 7] {
+        [
+            &self.users,
+            &self.tokens,
+            &self.user_features,
+            &self.documents_sync,
+            &self.snapshots_sync,
+            &self.updates_sync,
+            &self.changes_sync,
+        ]
+    }
+}
+
+// Blobs go to the bucket when one is set and under `path` otherwise
+#[derive(Debug, Clone)]
+pub struct BlobStoreConfig {
+    pub bucket: Option<String>,
+    pub endpoint: Option<String>,
+    pub path: PathBuf,
+}
+
+/*
+ * Server settings, loaded once at boot. Every setting can be given in the
+ * TOML file or as an environment variable, and the variable wins when both
+ * are set. Nothing reads the environment after this.
+ */
+#[derive(Clone)]
+pub struct Config {
+    pub port: u16,
+    pub auth_secret: String,
+    pub subscription_request_sns: String,
+    // Bearer token for /metrics. Without one, /metrics is not served.
+    pub metrics_token: Option<String>,
+    pub tables: Tables,
+    pub blob_store: BlobStoreConfig,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    // Everything wrong with the settings, so that they can be fixed in one go
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => {
+                write!(f, "Failed to read {}. Error: {}", path.display(), e)
+            }
+            ConfigError::Parse(path, e) => {
+                write!(f, "Failed to parse {}. Error: {}", path.display(), e)
+            }
+            ConfigError::Invalid(problems) => {
+                write!(f, "Invalid configuration:")?;
+                for problem in problems {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+// An environment variable, or the file setting it overrides. Empty counts as unset.
+fn setting(name: &str, file_value: Option<String>) -> Option<String> {
+    env::var(name)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .or(file_value.filter(|value| !value.is_empty()))
+}
+
+struct Validator {
+    problems: Vec<String>,
+}
+
+impl Validator {
+    fn required(&mut self, name: &str, key: &str, value: Option<String>) -> String {
+        value.unwrap_or_else(|| {
+            self.problems
+                .push(format!("{} (or `{}` in the file) is not set", name, key));
+            String::new()
+        })
+    }
+
+    // DynamoDB table names are 3 to 255 letters, digits, '_', '-' or '.'
+    fn table(&mut self, name: &str, key: &str, value: Option<String>) -> String {
+        let table = self.required(name, key, value);
+        let is_valid = (3..=255).contains(&table.len())
+            && table
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || ['_', '-', '.'].contains(&c));
+
+        if !table.is_empty() && !is_valid {
+            self.problems
+                .push(format!("{} is not a valid table name: {}", name, table));
+        }
+
+        table
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Config, ConfigError> {
+        let file_config = match env::var(CONFIG_FILE_ENV) {
+            Ok(path) => Config::read_file(Path::new(&path))?,
+            Err(_) if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Config::read_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            Err(_) => FileConfig::default(),
+        };
+
+        Config::from_file_config(file_config)
+    }
+
+    fn read_file(path: &Path) -> Result<FileConfig, ConfigError> {
+        let content =
+            fs::read_to_string(path).map_err(|e| ConfigError::Read(path.to_path_buf(), e))?;
+
+        toml::from_str(&content).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))
+    }
+
+    fn from_file_config(file: FileConfig) -> Result<Config, ConfigError> {
+        let mut validator = Validator { problems: vec![] };
+
+        let port = match env::var("PORT").ok().filter(|port| !port.is_empty()) {
+            Some(port) => port.parse::<u16>().unwrap_or_else(|_| {
+                validator
+                    .problems
+                    .push(format!("PORT is not a port number: {}", port));
+                0
+            }),
+            None => file.port.unwrap_or_else(|| {
+                validator
+                    .problems
+                    .push(String::from("PORT (or `port` in the file) is not set"));
+                0
+            }),
+        };
+
+        let auth_secret = validator.required(
+            "AUTH_SECRET",
+            "auth_secret",
+            setting("AUTH_SECRET", file.auth_secret),
+        );
+
+        let subscription_request_sns = validator.required(
+            "SUBSCRIPTION_REQUEST_SNS",
+            "subscription_request_sns",
+            setting("SUBSCRIPTION_REQUEST_SNS", file.subscription_request_sns),
+        );
+        if !subscription_request_sns.is_empty() && !subscription_request_sns.starts_with("arn:") {
+            validator.problems.push(format!(
+                "SUBSCRIPTION_REQUEST_SNS is not a topic ARN: {}",
+                subscription_request_sns
+            ));
+        }
+
+        let metrics_token = setting("METRICS_TOKEN", file.metrics_token);
+        if metrics_token
+            .as_ref()
+            .is_some_and(|token| token.len() < MIN_METRICS_TOKEN_LENGTH)
+        {
+            validator.problems.push(format!(
+                "METRICS_TOKEN is shorter than {} characters",
+                MIN_METRICS_TOKEN_LENGTH
+            ));
+        }
+
+        let tables = Tables {
+            users: validator.table(
+                "USERS_TABLE",
+                "tables.users",
+                setting("USERS_TABLE", file.tables.users),
+            ),
+            tokens: validator.table(
+                "TOKENS_TABLE",
+                "tables.tokens",
+                setting("TOKENS_TABLE", file.tables.tokens),
+            ),
+            user_features: validator.table(
+                "USER_FEATURES_TABLE",
+                "tables.user_features",
+                setting("USER_FEATURES_TABLE", file.tables.user_features),
+            ),
+            documents_sync: validator.table(
+                "DOCUMENTS_SYNC_TABLE",
+                "tables.documents_sync",
+                setting("DOCUMENTS_SYNC_TABLE", file.tables.documents_sync),
+            ),
+            snapshots_sync: validator.table(
+                "SNAPSHOTS_SYNC_TABLE",
+                "tables.snapshots_sync",
+                setting("SNAPSHOTS_SYNC_TABLE", file.tables.snapshots_sync),
+            ),
+            updates_sync: validator.table(
+                "UPDATES_SYNC_TABLE",
+                "tables.updates_sync",
+                setting("UPDATES_SYNC_TABLE", file.tables.updates_sync),
+            ),
+            changes_sync: validator.table(
+                "CHANGES_SYNC_TABLE",
+                "tables.changes_sync",
+                setting("CHANGES_SYNC_TABLE", file.tables.changes_sync),
+            ),
+        };
+
+        let blob_store = BlobStoreConfig {
+            bucket: setting("BLOB_STORE_BUCKET", file.blob_store.bucket),
+            endpoint: setting("BLOB_STORE_ENDPOINT", file.blob_store.endpoint),
+            path: PathBuf::from(
+                setting("BLOB_STORE_PATH", file.blob_store.path).unwrap_or(String::from("blobs")),
+            ),
+        };
+        if blob_store.endpoint.is_some() && blob_store.bucket.is_none() {
+            validator.problems.push(String::from(
+                "BLOB_STORE_ENDPOINT is set without BLOB_STORE_BUCKET",
+            ));
+        }
+
+        if !validator.problems.is_empty() {
+            return Err(ConfigError::Invalid(validator.problems));
+        }
+
+        Ok(Config {
+            port,
+            auth_secret,
+            subscription_request_sns,
+            metrics_token,
+            tables,
+            blob_store,
+        })
+    }
+}
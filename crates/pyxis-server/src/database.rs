@@ -1,5 +1,8 @@
+pub mod change_repository;
+pub mod cursor;
 pub mod documents_repository;
 pub mod features_repository;
+pub mod item;
 pub mod token_repository;
 pub mod updates_repository;
 pub mod user_repository;
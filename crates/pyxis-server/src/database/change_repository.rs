@@ -0,0 +1,305 @@
+use std::sync::Arc;
+
+use aws_sdk_dynamodb::{
+    self as DynamoDB,
+    operation::transact_write_items::TransactWriteItemsError,
+    types::{AttributeValue, Put, TransactWriteItem, Update},
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::item::{self, Item};
+use crate::{
+    blob_client::PayloadRef,
+    config::Config,
+    error::{ErrorCode, ServerError},
+};
+
+// Holds the last sequence handed out for a user. Changes start at 1.
+const COUNTER_SK: &str = "0";
+// Writes racing for the same user retry this many times before giving up
+const MAX_SEQUENCE_ATTEMPTS: usize = 10;
+
+/*
+ * An entry in the change log of a user. Every document and update written by
+ * any device of the user is appended with the next sequence, so a device can
+ * follow the writes of all the others with a single cursor.
+ */
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Change {
+    pub seq: i64,
+    pub device_id: String,
+    // document or update
+    pub kind: String,
+    pub source: String,
+    pub operation: String,
+    // Record id of a document, in the list of the device that wrote it
+    pub record_id: Option<i64>,
+    // Sort key of an update
+    pub update_id: Option<String>,
+    pub snapshot_id: Option<i64>,
+    pub file_uid: Option<String>,
+    pub payload: String,
+    // How an update payload is written, none for json
+    pub encoding: Option<String>,
+    // Set while the payload is kept in the blob store
+    #[serde(skip)]
+    pub blob: Option<PayloadRef>,
+    pub timestamp: i64,
+}
+
+impl TryFrom<&Item> for Change {
+    type Error = ServerError;
+
+    fn try_from(value: &Item) -> Result<Self, Self::Error> {
+        Ok(Change {
+            seq: item::number(value, "sk")?,
+            device_id: item::string(value, "device_id")?,
+            kind: item::string(value, "kind")?,
+            source: item::string(value, "source")?,
+            operation: item::string(value, "operation")?,
+            record_id: item::optional_number(value, "record_id"),
+            update_id: item::optional_string(value, "update_id"),
+            snapshot_id: item::optional_number(value, "snapshot_id"),
+            file_uid: item::optional_string(value, "file_uid"),
+            payload: item::string(value, "payload")?,
+            encoding: item::optional_string(value, "encoding"),
+            blob: match (
+                item::optional_string(value, "payload_ref"),
+                item::optional_string(value, "payload_checksum"),
+            ) {
+                (Some(reference), Some(checksum)) => Some(PayloadRef {
+                    reference,
+                    checksum,
+                }),
+                _ => None,
+            },
+            timestamp: item::optional_number(value, "timestamp").unwrap_or(0),
+        })
+    }
+}
+
+// Outcome of a conditional write of a record and its change
+#[derive(Debug, PartialEq)]
+pub enum WriteResult {
+    Written,
+    // The same write was already stored. Nothing was written.
+    Replayed,
+    // A different record is stored under the same key. Nothing was written.
+    Conflict,
+}
+
+pub struct ChangeList {
+    pub changes: Vec<Change>,
+    // Last sequence read, whether or not the change was returned
+    pub next_cursor: i64,
+    pub has_more: bool,
+}
+
+pub struct ChangeRepository {
+    client: Arc<DynamoDB::Client>,
+    table_name: String,
+}
+
+impl ChangeRepository {
+    pub fn new(client: Arc<DynamoDB::Client>, config: &Config) -> Self {
+        Self {
+            client,
+            table_name: config.tables.changes_sync.clone(),
+        }
+    }
+
+    async fn last_sequence(&self, user_id: &str) -> Result<i64, ServerError> {
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("pk", AttributeValue::S(user_id.to_owned()))
+            .key("sk", AttributeValue::N(COUNTER_SK.to_owned()))
+            .consistent_read(true)
+            .send()
+            .await?;
+
+        Ok(response
+            .item
+            .as_ref()
+            .and_then(|counter| item::optional_number(counter, "last_seq"))
+            .unwrap_or(0))
+    }
+
+    // Moves the counter on by one, only if nobody else moved it since it was read
+    fn counter_update(&self, user_id: &str, last_seq: i64) -> Result<Update, ServerError> {
+        let update = Update::builder()
+            .table_name(&self.table_name)
+            .key("pk", AttributeValue::S(user_id.to_owned()))
+            .key("sk", AttributeValue::N(COUNTER_SK.to_owned()))
+            .update_expression("SET #last_seq = :seq")
+            .expression_attribute_names("#last_seq", "last_seq")
+            .expression_attribute_values(":seq", AttributeValue::N((last_seq + 1).to_string()));
+
+        let update = match last_seq {
+            0 => update.condition_expression("attribute_not_exists(#last_seq)"),
+            _ => update
+                .condition_expression("#last_seq = :last_seq")
+                .expression_attribute_values(":last_seq", AttributeValue::N(last_seq.to_string())),
+        };
+
+        Ok(update.build()?)
+    }
+
+    fn entry(&self, user_id: &str, seq: i64, change: &Change) -> Result<Put, ServerError> {
+        let timestamp = Utc::now().timestamp();
+
+        let mut entry = Put::builder()
+            .table_name(&self.table_name)
+            .item("pk", AttributeValue::S(user_id.to_owned()))
+            .item("sk", AttributeValue::N(seq.to_string()))
+            .item("device_id", AttributeValue::S(change.device_id.clone()))
+            .item("kind", AttributeValue::S(change.kind.clone()))
+            .item("source", AttributeValue::S(change.source.clone()))
+            .item("operation", AttributeValue::S(change.operation.clone()))
+            .item("payload", AttributeValue::S(change.payload.clone()))
+            .item("timestamp", AttributeValue::N(timestamp.to_string()))
+            .condition_expression("attribute_not_exists(sk)");
+
+        if let Some(record_id) = change.record_id {
+            entry = entry.item("record_id", AttributeValue::N(record_id.to_string()));
+        }
+        if let Some(update_id) = &change.update_id {
+            entry = entry.item("update_id", AttributeValue::S(update_id.clone()));
+        }
+        if let Some(snapshot_id) = change.snapshot_id {
+            entry = entry.item("snapshot_id", AttributeValue::N(snapshot_id.to_string()));
+        }
+        if let Some(file_uid) = &change.file_uid {
+            entry = entry.item("file_uid", AttributeValue::S(file_uid.clone()));
+        }
+        if let Some(encoding) = &change.encoding {
+            entry = entry.item("encoding", AttributeValue::S(encoding.clone()));
+        }
+        if let Some(blob) = &change.blob {
+            entry = entry
+                .item("payload_ref", AttributeValue::S(blob.reference.clone()))
+                .item("payload_checksum", AttributeValue::S(blob.checksum.clone()));
+        }
+
+        Ok(entry.build()?)
+    }
+
+    /*
+     * Writes a record together with its change in one transaction. The record
+     * is expected to carry a condition. Returns false when the condition failed,
+     * in which case neither is written. The seq and timestamp of the change are
+     * assigned here.
+     *
+     * The counter moves in the same transaction, on the condition that it still
+     * holds the sequence read before. A change is therefore visible only once
+     * every sequence below it is, and a write that doesn't commit takes none.
+     * Losing the counter to a concurrent write means reading it again.
+     */
+    pub async fn write_with(
+        &self,
+        user_id: &str,
+        change: Change,
+        record: Put,
+    ) -> Result<bool, ServerError> {
+        for _ in 0..MAX_SEQUENCE_ATTEMPTS {
+            let last_seq = self.last_sequence(user_id).await?;
+
+            let result = self
+                .client
+                .transact_write_items()
+                .transact_items(TransactWriteItem::builder().put(record.clone()).build())
+                .transact_items(
+                    TransactWriteItem::builder()
+                        .put(self.entry(user_id, last_seq + 1, &change)?)
+                        .build(),
+                )
+                .transact_items(
+                    TransactWriteItem::builder()
+                        .update(self.counter_update(user_id, last_seq)?)
+                        .build(),
+                )
+                .send()
+                .await;
+
+            let e = match result {
+                Ok(_) => return Ok(true),
+                Err(e) => e,
+            };
+
+            // Reasons come in the order of the items: record, change, counter
+            let reasons: Vec<Option<&str>> = match e.as_service_error() {
+                Some(TransactWriteItemsError::TransactionCanceledException(canceled)) => canceled
+                    .cancellation_reasons()
+                    .iter()
+                    .map(|reason| reason.code())
+                    .collect(),
+                _ => return Err(e.into()),
+            };
+
+            match reasons.as_slice() {
+                [Some("ConditionalCheckFailed"), ..] => return Ok(false),
+                // Another write took the sequence first
+                [_, Some("ConditionalCheckFailed"), _] | [_, _, Some("ConditionalCheckFailed")] => {
+                    continue
+                }
+                _ if reasons.contains(&Some("TransactionConflict")) => continue,
+                _ => return Err(e.into()),
+            }
+        }
+
+        Err(ServerError::failure(
+            ErrorCode::DatabaseError,
+            format!(
+                "[Changes] No sequence taken after {} attempts",
+                MAX_SEQUENCE_ATTEMPTS
+            ),
+        ))
+    }
+
+    /*
+     * Changes after `since`, in order, leaving out the ones written by the device
+     * asking. They are filtered here rather than in the query so that the cursor
+     * moves past them too.
+     */
+    pub async fn list_since(
+        &self,
+        user_id: &str,
+        since: i64,
+        device_id: &str,
+    ) -> Result<ChangeList, ServerError> {
+        // The counter shares the partition under sequence 0 and is never a change
+        let since = since.max(0);
+
+        let response = self
+            .client
+            .query()
+            .table_name(&self.table_name)
+            .key_condition_expression("#pk = :pk AND #seq > :since")
+            .expression_attribute_names("#pk", "pk")
+            .expression_attribute_names("#seq", "sk")
+            .expression_attribute_values(":pk", AttributeValue::S(user_id.to_owned()))
+            .expression_attribute_values(":since", AttributeValue::N(since.to_string()))
+            .send()
+            .await?;
+
+        let changes = response
+            .items
+            .unwrap_or_default()
+            .iter()
+            .map(Change::try_from)
+            .collect::<Result<Vec<Change>, ServerError>>()?;
+
+        let next_cursor = changes.last().map(|change| change.seq).unwrap_or(since);
+
+        Ok(ChangeList {
+            changes: changes
+                .into_iter()
+                .filter(|change| change.device_id != device_id)
+                .collect(),
+            next_cursor,
+            has_more: response.last_evaluated_key.is_some(),
+        })
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use aws_sdk_dynamodb::types::AttributeValue;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{ErrorCode, ServerError};
+
+// Largest page a client can ask for
+pub const MAX_PAGE_SIZE: i32 = 1000;
+
+pub struct Page<T> {
+    pub items: Vec<T>,
+    // None once there is nothing left to read
+    pub next_cursor: Option<String>,
+}
+
+// No limit leaves the page at 1 MB, as DynamoDB does
+pub fn page_size(limit: Option<i32>) -> Option<i32> {
+    limit.map(|limit| limit.clamp(1, MAX_PAGE_SIZE))
+}
+
+#[derive(Serialize, Deserialize)]
+enum SortKey {
+    S(String),
+    N(String),
+}
+
+/*
+ * Cursors handed to clients hold the sort key of the last item read, hex
+ * encoded so that clients treat them as opaque. The partition key comes from
+ * the request that pages through, so a cursor can't be used to read a
+ * partition the caller isn't asking for.
+ */
+pub fn encode(last_evaluated_key: &HashMap<String, AttributeValue>) -> Option<String> {
+    let sort_key = match last_evaluated_key.get("sk")? {
+        AttributeValue::S(value) => SortKey::S(value.clone()),
+        AttributeValue::N(value) => SortKey::N(value.clone()),
+        _ => return None,
+    };
+
+    let json = serde_json::to_vec(&sort_key).ok()?;
+    Some(json.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+pub fn decode(cursor: &str, pk: &str) -> Result<HashMap<String, AttributeValue>, ServerError> {
+    let malformed = || ServerError::new(ErrorCode::BadRequest, "Malformed cursor");
+
+    if !cursor.len().is_multiple_of(2) || !cursor.is_ascii() {
+        return Err(malformed());
+    }
+
+    let json = (0..cursor.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&cursor[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| malformed())?;
+
+    let sort_key = match serde_json::from_slice::<SortKey>(&json).map_err(|_| malformed())? {
+        SortKey::S(value) => AttributeValue::S(value),
+        SortKey::N(value) => {
+            AttributeValue::N(value.parse::<i64>().map_err(|_| malformed())?.to_string())
+        }
+    };
+
+    Ok(HashMap::from([
+        (String::from("pk"), AttributeValue::S(pk.to_owned())),
+        (String::from("sk"), sort_key),
+    ]))
+}
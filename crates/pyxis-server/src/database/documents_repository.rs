@@ -1,10 +1,25 @@
-use std::{collections::HashMap, env, error::Error, str::FromStr, sync::Arc};
+use std::{str::FromStr, sync::Arc};
 
-use aws_sdk_dynamodb::{self as DynamoDB, types::AttributeValue};
+use aws_sdk_dynamodb::{
+    self as DynamoDB,
+    types::{AttributeValue, Put},
+};
 use chrono::Utc;
 use pyxis_shared::entities::queue::Source;
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    blob_client::PayloadRef,
+    config::Config,
+    error::{ErrorCode, ServerError},
+};
+
+use super::{
+    change_repository::{Change, ChangeRepository, WriteResult},
+    cursor::{self, Page},
+    item::{self, Item},
+};
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Document {
     pub pk: String,
@@ -13,117 +28,158 @@ pub struct Document {
     pub operation: String,
     pub source: String,
     pub file_uid: Option<String>,
+    pub idempotency_key: Option<String>,
+    // Set while the payload is kept in the blob store
+    #[serde(skip)]
+    pub blob: Option<PayloadRef>,
 }
 
-impl From<&HashMap<String, AttributeValue>> for Document {
-    fn from(value: &HashMap<String, AttributeValue>) -> Self {
-        Document {
-            pk: value
-                .get("pk")
-                .and_then(|v| v.as_s().ok())
-                .cloned()
-                .expect("pk should exist"),
-            sk: value
-                .get("sk")
-                .and_then(|v| v.as_n().ok())
-                .cloned()
-                .expect("sk should exist")
-                .parse::<i64>()
-                .unwrap(),
-            payload: value
-                .get("payload")
-                .and_then(|v| v.as_s().ok())
-                .cloned()
-                .expect("payload should exist"),
-            operation: value
-                .get("operation")
-                .and_then(|v| v.as_s().ok())
-                .cloned()
-                .expect("operation should exist"),
-            source: value
-                .get("source")
-                .and_then(|v| v.as_s().ok())
-                .cloned()
-                .expect("source should exist"),
-            file_uid: value.get("file_uid").and_then(|v| v.as_s().ok()).cloned(),
+impl Document {
+    // Documents written before keys were sent are compared by content
+    pub fn is_same_write(&self, other: &Document) -> bool {
+        match (&self.idempotency_key, &other.idempotency_key) {
+            (Some(key), Some(other_key)) => key == other_key,
+            _ => {
+                self.payload == other.payload
+                    && self.blob.as_ref().map(|blob| &blob.checksum)
+                        == other.blob.as_ref().map(|blob| &blob.checksum)
+                    && self.operation == other.operation
+                    && self.source == other.source
+            }
         }
     }
 }
 
+impl TryFrom<&Item> for Document {
+    type Error = ServerError;
+
+    fn try_from(value: &Item) -> Result<Self, Self::Error> {
+        Ok(Document {
+            pk: item::string(value, "pk")?,
+            sk: item::number(value, "sk")?,
+            payload: item::string(value, "payload")?,
+            operation: item::string(value, "operation")?,
+            source: item::string(value, "source")?,
+            file_uid: item::optional_string(value, "file_uid"),
+            idempotency_key: item::optional_string(value, "idempotency_key"),
+            blob: match (
+                item::optional_string(value, "payload_ref"),
+                item::optional_string(value, "payload_checksum"),
+            ) {
+                (Some(reference), Some(checksum)) => Some(PayloadRef {
+                    reference,
+                    checksum,
+                }),
+                _ => None,
+            },
+        })
+    }
+}
+
 pub struct DocumentRepository {
     client: Arc<DynamoDB::Client>,
+    documents_table: String,
+    snapshots_table: String,
 }
 
 impl DocumentRepository {
-    pub fn new(client: Arc<DynamoDB::Client>) -> Self {
-        Self { client }
-    }
-
-    fn get_documents_table_name() -> String {
-        env::var("DOCUMENTS_SYNC_TABLE").unwrap()
+    pub fn new(client: Arc<DynamoDB::Client>, config: &Config) -> Self {
+        Self {
+            client,
+            documents_table: config.tables.documents_sync.clone(),
+            snapshots_table: config.tables.snapshots_sync.clone(),
+        }
     }
 
-    fn get_snapshots_table_name() -> String {
-        env::var("SNAPSHOTS_SYNC_TABLE").unwrap()
+    fn get_table_name(&self, is_snapshot: bool) -> &str {
+        if is_snapshot {
+            &self.snapshots_table
+        } else {
+            &self.documents_table
+        }
     }
 
-    pub async fn create(&self, document: Document) -> Result<i64, Box<dyn Error>> {
+    /*
+     * Writes a document at its record id, unless the id is already taken. A
+     * document sent again is answered as if it had just been written, while a
+     * different one at the same id is a conflict and leaves the stored one as is.
+     */
+    pub async fn create(
+        &self,
+        document: Document,
+        changes: &ChangeRepository,
+        user_id: &str,
+        change: Change,
+    ) -> Result<WriteResult, ServerError> {
         let timestamp = Utc::now().timestamp();
-        let Document {
-            pk,
-            sk,
-            payload,
-            operation,
-            source,
-            file_uid,
-        } = document;
-
-        let pk_av = AttributeValue::S(pk);
-        let sk_av = AttributeValue::N(sk.to_string());
-        let payload_av = AttributeValue::S(payload);
-        let op_av = AttributeValue::S(operation);
-        let timestamp_av = AttributeValue::N(timestamp.to_string());
-        let source_av = AttributeValue::S(source.to_string());
-
-        let table_name = if Source::from_str(&source).unwrap() == Source::Snapshot {
-            DocumentRepository::get_snapshots_table_name()
-        } else {
-            DocumentRepository::get_documents_table_name()
-        };
+        let table_name =
+            self.get_table_name(Source::from_str(&document.source) == Ok(Source::Snapshot));
 
-        let query = self
+        let mut put = Put::builder()
+            .table_name(table_name)
+            .item("pk", AttributeValue::S(document.pk.clone()))
+            .item("sk", AttributeValue::N(document.sk.to_string()))
+            .item("payload", AttributeValue::S(document.payload.clone()))
+            .item("operation", AttributeValue::S(document.operation.clone()))
+            .item("timestamp", AttributeValue::N(timestamp.to_string()))
+            .item("source", AttributeValue::S(document.source.clone()))
+            .condition_expression("attribute_not_exists(sk)");
+
+        if let Some(file_uid) = &document.file_uid {
+            put = put.item("file_uid", AttributeValue::S(file_uid.clone()));
+        }
+        if let Some(key) = &document.idempotency_key {
+            put = put.item("idempotency_key", AttributeValue::S(key.clone()));
+        }
+        if let Some(blob) = &document.blob {
+            put = put
+                .item("payload_ref", AttributeValue::S(blob.reference.clone()))
+                .item("payload_checksum", AttributeValue::S(blob.checksum.clone()));
+        }
+
+        if changes.write_with(user_id, change, put.build()?).await? {
+            return Ok(WriteResult::Written);
+        }
+
+        let stored = self
             .client
-            .put_item()
+            .get_item()
             .table_name(table_name)
-            .item("pk", pk_av)
-            .item("sk", sk_av)
-            .item("payload", payload_av)
-            .item("operation", op_av)
-            .item("timestamp", timestamp_av)
-            .item("source", source_av);
-
-        if let Some(file_uid) = file_uid {
-            let file_uid_av = AttributeValue::S(file_uid);
-            query.item("file_uid", file_uid_av).send().await?;
+            .key("pk", AttributeValue::S(document.pk.clone()))
+            .key("sk", AttributeValue::N(document.sk.to_string()))
+            .consistent_read(true)
+            .send()
+            .await?
+            .item
+            .ok_or_else(|| {
+                ServerError::failure(
+                    ErrorCode::DatabaseError,
+                    "[Documents] Rejected write but no document stored",
+                )
+            })?;
+        let stored = Document::try_from(&stored)?;
+
+        if stored.is_same_write(&document) {
+            Ok(WriteResult::Replayed)
         } else {
-            query.send().await?;
+            Ok(WriteResult::Conflict)
         }
-
-        Ok(sk)
     }
 
+    /*
+     * One page of the documents a device sent after `record_id`. A page ends at
+     * `limit` documents or at 1 MB, whichever comes first.
+     */
     pub async fn list_by_record_id(
         &self,
         user_id: String,
         device_id: String,
         record_id: i64,
         is_snapshot: bool,
-    ) -> Result<Vec<Document>, Box<dyn Error>> {
-        let table_name = if is_snapshot {
-            "snapshots_sync"
-        } else {
-            "documents_sync"
-        };
+        limit: Option<i32>,
+        start_key: Option<Item>,
+    ) -> Result<Page<Document>, ServerError> {
+        let table_name = self.get_table_name(is_snapshot);
 
         let records_iter = self
             .client
@@ -137,14 +193,24 @@ impl DocumentRepository {
                 ":pk",
                 AttributeValue::S(format!("{}/{}", user_id, device_id)),
             )
+            .set_limit(limit)
+            .set_exclusive_start_key(start_key)
             .send()
             .await?;
 
         let items = records_iter
             .items
-            .expect("[Documents] Failed to get synced documents");
-        let records: Vec<Document> = items.iter().map(|v| v.into()).collect();
-
-        Ok(records)
+            .unwrap_or_default()
+            .iter()
+            .map(Document::try_from)
+            .collect::<Result<Vec<Document>, ServerError>>()?;
+
+        Ok(Page {
+            items,
+            next_cursor: records_iter
+                .last_evaluated_key
+                .as_ref()
+                .and_then(cursor::encode),
+        })
     }
 }
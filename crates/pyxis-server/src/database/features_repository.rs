@@ -1,35 +1,26 @@
-use std::{collections::HashMap, env, error::Error, sync::Arc};
+use std::{collections::HashMap, sync::Arc};
 
 use aws_sdk_dynamodb::{self as DynamoDB, types::AttributeValue};
 use serde::{Deserialize, Serialize};
 
+use super::item::{self, Item};
+use crate::{config::Config, error::ServerError};
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Feature {
     pub user_id: String,
     pub features: HashMap<String, String>,
 }
 
-impl From<&HashMap<String, AttributeValue>> for Feature {
-    fn from(value: &HashMap<String, AttributeValue>) -> Self {
-        Feature {
-            user_id: value
-                .get("user_id")
-                .and_then(|v| v.as_s().ok())
-                .cloned()
-                .expect("user_id should exist"),
-            features: value
-                .get("features")
-                .and_then(|v| v.as_m().ok())
-                .map(|m| convert_map_to_json(m))
-                .expect("features should exist"),
-        }
-    }
-}
+impl TryFrom<&Item> for Feature {
+    type Error = ServerError;
 
-fn convert_map_to_json(map: &HashMap<String, AttributeValue>) -> HashMap<String, String> {
-    map.iter()
-        .filter_map(|(k, v)| v.as_s().ok().map(|s| (k.clone(), s.to_string())))
-        .collect()
+    fn try_from(value: &Item) -> Result<Self, Self::Error> {
+        Ok(Feature {
+            user_id: item::string(value, "user_id")?,
+            features: item::string_map(value, "features")?,
+        })
+    }
 }
 
 fn convert_json_to_map(json: &HashMap<String, String>) -> HashMap<String, AttributeValue> {
@@ -40,18 +31,18 @@ fn convert_json_to_map(json: &HashMap<String, String>) -> HashMap<String, Attrib
 
 pub struct FeaturesRepository {
     client: Arc<DynamoDB::Client>,
+    table_name: String,
 }
 
 impl FeaturesRepository {
-    pub fn new(client: Arc<DynamoDB::Client>) -> Self {
-        Self { client }
-    }
-
-    fn get_table_name() -> String {
-        env::var("USER_FEATURES_TABLE").unwrap()
+    pub fn new(client: Arc<DynamoDB::Client>, config: &Config) -> Self {
+        Self {
+            client,
+            table_name: config.tables.user_features.clone(),
+        }
     }
 
-    pub async fn upsert(&self, features: &Feature) -> Result<(), Box<dyn Error + Send + Sync>> {
+    pub async fn upsert(&self, features: &Feature) -> Result<(), ServerError> {
         let Feature { user_id, features } = features;
 
         let user_id_av = AttributeValue::S(user_id.to_owned());
@@ -59,32 +50,26 @@ impl FeaturesRepository {
 
         self.client
             .update_item()
-            .table_name(FeaturesRepository::get_table_name())
+            .table_name(&self.table_name)
             .key("user_id", user_id_av.clone())
             .update_expression("SET #features = :features")
             .expression_attribute_names("#features", "features")
             .expression_attribute_values(":features", features_av)
             .send()
-            .await
-            .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync>)?;
+            .await?;
 
         Ok(())
     }
 
-    pub async fn get(
-        &self,
-        user_id: String,
-    ) -> Result<Option<Feature>, Box<dyn std::error::Error + Send + Sync>> {
+    pub async fn get(&self, user_id: String) -> Result<Option<Feature>, ServerError> {
         let feature = self
             .client
             .get_item()
-            .table_name(FeaturesRepository::get_table_name())
+            .table_name(&self.table_name)
             .key("user_id", AttributeValue::S(user_id))
             .send()
             .await?;
 
-        let records: Option<Feature> = feature.item().and_then(|v| Some(Feature::from(v)));
-
-        Ok(records)
+        feature.item().map(Feature::try_from).transpose()
     }
 }
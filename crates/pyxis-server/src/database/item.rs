@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+
+use aws_sdk_dynamodb::types::AttributeValue;
+
+use crate::error::{ErrorCode, ServerError};
+
+pub type Item = HashMap<String, AttributeValue>;
+
+// Items missing an attribute were written by something other than this server
+fn missing(key: &str) -> ServerError {
+    ServerError::failure(
+        ErrorCode::DatabaseError,
+        format!("[Item] Malformed item, no {}", key),
+    )
+}
+
+pub fn optional_string(item: &Item, key: &str) -> Option<String> {
+    item.get(key).and_then(|v| v.as_s().ok()).cloned()
+}
+
+pub fn optional_number(item: &Item, key: &str) -> Option<i64> {
+    item.get(key)
+        .and_then(|v| v.as_n().ok())
+        .and_then(|n| n.parse::<i64>().ok())
+}
+
+pub fn string(item: &Item, key: &str) -> Result<String, ServerError> {
+    optional_string(item, key).ok_or_else(|| missing(key))
+}
+
+pub fn number(item: &Item, key: &str) -> Result<i64, ServerError> {
+    optional_number(item, key).ok_or_else(|| missing(key))
+}
+
+pub fn string_list(item: &Item, key: &str) -> Result<Vec<String>, ServerError> {
+    item.get(key)
+        .and_then(|v| v.as_l().ok())
+        .map(|list| list.iter().filter_map(|e| e.as_s().ok()).cloned().collect())
+        .ok_or_else(|| missing(key))
+}
+
+pub fn string_map(item: &Item, key: &str) -> Result<HashMap<String, String>, ServerError> {
+    item.get(key)
+        .and_then(|v| v.as_m().ok())
+        .map(|map| {
+            map.iter()
+                .filter_map(|(k, v)| v.as_s().ok().map(|s| (k.clone(), s.to_string())))
+                .collect()
+        })
+        .ok_or_else(|| missing(key))
+}
@@ -1,4 +1,4 @@
-use std::{env, error::Error, sync::Arc};
+use std::sync::Arc;
 
 use aws_sdk_dynamodb::{self as DynamoDB, types::AttributeValue};
 use chrono::{Duration, Utc};
@@ -7,6 +7,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use super::user_repository::UserWithoutPassword;
+use crate::{config::Config, error::ServerError};
 use serde_with::{chrono::TimeDelta, serde_as, DurationSeconds};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -28,18 +29,20 @@ pub struct UserToken {
 
 pub struct TokenRepository {
     client: Arc<DynamoDB::Client>,
+    table_name: String,
+    auth_secret: String,
 }
 
 impl TokenRepository {
-    pub fn new(client: Arc<DynamoDB::Client>) -> Self {
-        Self { client }
+    pub fn new(client: Arc<DynamoDB::Client>, config: &Config) -> Self {
+        Self {
+            client,
+            table_name: config.tables.tokens.clone(),
+            auth_secret: config.auth_secret.clone(),
+        }
     }
 
-    fn get_table_name() -> String {
-        env::var("TOKENS_TABLE").unwrap()
-    }
-
-    pub async fn create(&self, user: UserWithoutPassword) -> Result<UserToken, Box<dyn Error>> {
+    pub async fn create(&self, user: UserWithoutPassword) -> Result<UserToken, ServerError> {
         let duration = Duration::hours(24 * 30);
 
         let exp = Utc::now()
@@ -56,11 +59,7 @@ impl TokenRepository {
         let token = encode(
             &Header::new(jsonwebtoken::Algorithm::HS256),
             &claim,
-            &EncodingKey::from_secret(
-                env::var("AUTH_SECRET")
-                    .expect("No authentication secret specified!")
-                    .as_ref(),
-            ),
+            &EncodingKey::from_secret(self.auth_secret.as_bytes()),
         )?;
 
         let user_token = UserToken {
@@ -77,7 +76,7 @@ impl TokenRepository {
 
         self.client
             .put_item()
-            .table_name(TokenRepository::get_table_name())
+            .table_name(&self.table_name)
             .item("user_id", user_id_av)
             .item("device_id", device_id_av)
             .item("user_token", token_av)
@@ -88,10 +87,10 @@ impl TokenRepository {
         Ok(user_token)
     }
 
-    pub async fn delete(&self, user_id: &Uuid, device_id: &Uuid) -> Result<(), Box<dyn Error>> {
+    pub async fn delete(&self, user_id: &Uuid, device_id: &Uuid) -> Result<(), ServerError> {
         self.client
             .delete_item()
-            .table_name(TokenRepository::get_table_name())
+            .table_name(&self.table_name)
             .key("user_id", AttributeValue::S(user_id.to_string()))
             .key("device_id", AttributeValue::S(device_id.to_string()))
             .send()
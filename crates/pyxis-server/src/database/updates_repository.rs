@@ -1,84 +1,170 @@
-use std::{collections::HashMap, env, error::Error, sync::Arc};
+use std::{str::FromStr, sync::Arc};
 
-use aws_sdk_dynamodb::{self as DynamoDB, types::AttributeValue};
+use aws_sdk_dynamodb::{
+    self as DynamoDB,
+    types::{AttributeValue, Put},
+};
 use chrono::Utc;
+use pyxis_shared::{entities::updates::Updates, payload::UpdateEncoding};
 use serde::{Deserialize, Serialize};
 
+use super::{
+    change_repository::{Change, ChangeRepository, WriteResult},
+    cursor::{self, Page},
+    item::{self, Item},
+};
+use crate::{
+    config::Config,
+    error::{ErrorCode, ServerError},
+};
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Update {
     pub pk: String, // user_id/device_id
-    pub sk: String, // snapshot_id/file_uid
+    pub sk: String, // snapshot_id/file_uid/idempotency_key
     pub payload: String,
+    // Not stored for updates written as json
+    pub encoding: UpdateEncoding,
 }
 
-impl From<&HashMap<String, AttributeValue>> for Update {
-    fn from(value: &HashMap<String, AttributeValue>) -> Self {
-        Update {
-            pk: value
-                .get("pk")
-                .and_then(|v| v.as_s().ok())
-                .cloned()
-                .expect("pk should exist"),
-            sk: value
-                .get("sk")
-                .and_then(|v| v.as_s().ok())
-                .cloned()
-                .expect("sk should exist"),
-            payload: value
-                .get("payload")
-                .and_then(|v| v.as_s().ok())
-                .cloned()
-                .expect("payload should exist"),
+impl Update {
+    /*
+     * Rewrites the payload for a reader that only knows encodings up to `target`.
+     * Anything older is left as is, since readers know every encoding before the
+     * one they ask for.
+     */
+    pub fn transcode(&mut self, target: UpdateEncoding) -> Result<(), ServerError> {
+        if self.encoding <= target {
+            return Ok(());
         }
+
+        let mut parts = self.sk.splitn(3, '/');
+        let (snapshot_id, file_uid) = match (parts.next(), parts.next()) {
+            (Some(snapshot_id), Some(file_uid)) => match snapshot_id.parse::<i64>() {
+                Ok(snapshot_id) => (snapshot_id, file_uid),
+                Err(e) => return Err(ServerError::failure(ErrorCode::DatabaseError, e)),
+            },
+            _ => {
+                return Err(ServerError::failure(
+                    ErrorCode::DatabaseError,
+                    "[Updates] Malformed sort key",
+                ))
+            }
+        };
+
+        let content = self
+            .encoding
+            .decode_content(&self.payload)
+            .map_err(ServerError::internal)?;
+        let update = Updates::new(file_uid.to_owned(), content, None, snapshot_id);
+        let (encoding, payload) = target.encode(&update).map_err(ServerError::internal)?;
+
+        self.encoding = encoding;
+        self.payload = payload;
+
+        Ok(())
+    }
+}
+
+impl TryFrom<&Item> for Update {
+    type Error = ServerError;
+
+    fn try_from(value: &Item) -> Result<Self, Self::Error> {
+        Ok(Update {
+            pk: item::string(value, "pk")?,
+            sk: item::string(value, "sk")?,
+            payload: item::string(value, "payload")?,
+            encoding: item::optional_string(value, "encoding")
+                .and_then(|v| UpdateEncoding::from_str(&v).ok())
+                .unwrap_or_default(),
+        })
     }
 }
 
 pub struct UpdateRepository {
     client: Arc<DynamoDB::Client>,
+    table_name: String,
 }
 
 impl UpdateRepository {
-    pub fn new(client: Arc<DynamoDB::Client>) -> Self {
-        Self { client }
-    }
-
-    fn get_table_name() -> String {
-        env::var("UPDATES_SYNC_TABLE").unwrap()
+    pub fn new(client: Arc<DynamoDB::Client>, config: &Config) -> Self {
+        Self {
+            client,
+            table_name: config.tables.updates_sync.clone(),
+        }
     }
 
-    pub async fn create(&self, document: Update) -> Result<(), Box<dyn Error>> {
+    /*
+     * The sort key ends with the idempotency key of the update, so an update
+     * sent again finds the copy already stored and nothing is written twice.
+     */
+    pub async fn create(
+        &self,
+        document: Update,
+        changes: &ChangeRepository,
+        user_id: &str,
+        change: Change,
+    ) -> Result<WriteResult, ServerError> {
         let timestamp = Utc::now().timestamp();
-        let Update { pk, sk, payload } = document;
-
-        let pk_av = AttributeValue::S(pk);
-        let sk_av = AttributeValue::S(sk);
-        let payload_av = AttributeValue::S(payload);
-        let timestamp_av = AttributeValue::N(timestamp.to_string());
-
-        self.client
-            .put_item()
-            .table_name(UpdateRepository::get_table_name())
-            .item("pk", pk_av)
-            .item("sk", sk_av)
-            .item("payload", payload_av)
-            .item("timestamp", timestamp_av)
+
+        let mut put = Put::builder()
+            .table_name(&self.table_name)
+            .item("pk", AttributeValue::S(document.pk.clone()))
+            .item("sk", AttributeValue::S(document.sk.clone()))
+            .item("payload", AttributeValue::S(document.payload.clone()))
+            .item("timestamp", AttributeValue::N(timestamp.to_string()))
+            .condition_expression("attribute_not_exists(sk)");
+
+        if document.encoding != UpdateEncoding::Json {
+            put = put.item(
+                "encoding",
+                AttributeValue::S(document.encoding.as_str().to_owned()),
+            );
+        }
+
+        if changes.write_with(user_id, change, put.build()?).await? {
+            return Ok(WriteResult::Written);
+        }
+
+        let stored = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("pk", AttributeValue::S(document.pk))
+            .key("sk", AttributeValue::S(document.sk))
+            .consistent_read(true)
             .send()
-            .await?;
+            .await?
+            .item
+            .ok_or_else(|| {
+                ServerError::failure(
+                    ErrorCode::DatabaseError,
+                    "[Updates] Rejected write but no update stored",
+                )
+            })?;
+        let stored = Update::try_from(&stored)?;
 
-        Ok(())
+        if stored.payload == document.payload && stored.encoding == document.encoding {
+            Ok(WriteResult::Replayed)
+        } else {
+            Ok(WriteResult::Conflict)
+        }
     }
 
+    // One page of the updates a device sent on top of a snapshot
     pub async fn get_by_snapshot_id(
         &self,
         user_id: String,
         device_id: String,
         file_uid: String,
         snapshot_id: i64,
-    ) -> Result<Vec<Update>, Box<dyn Error>> {
+        limit: Option<i32>,
+        start_key: Option<Item>,
+    ) -> Result<Page<Update>, ServerError> {
         let records_iter = self
             .client
             .query()
-            .table_name(UpdateRepository::get_table_name())
+            .table_name(&self.table_name)
             .key_condition_expression("#pk = :pk AND begins_with(#sk, :file_snapshot)")
             .expression_attribute_names("#sk", "sk")
             .expression_attribute_names("#pk", "pk")
@@ -90,12 +176,24 @@ impl UpdateRepository {
                 ":pk",
                 AttributeValue::S(format!("{}/{}", user_id, device_id)),
             )
+            .set_limit(limit)
+            .set_exclusive_start_key(start_key)
             .send()
             .await?;
 
-        let items = records_iter.items.expect("[Updates] Failed to list!");
-        let records: Vec<Update> = items.iter().map(|v| v.into()).collect();
+        let items = records_iter
+            .items
+            .unwrap_or_default()
+            .iter()
+            .map(Update::try_from)
+            .collect::<Result<Vec<Update>, ServerError>>()?;
 
-        Ok(records)
+        Ok(Page {
+            items,
+            next_cursor: records_iter
+                .last_evaluated_key
+                .as_ref()
+                .and_then(cursor::encode),
+        })
     }
 }
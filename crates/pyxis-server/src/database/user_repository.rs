@@ -2,9 +2,15 @@ use aws_sdk_dynamodb::{self as DynamoDB, types::AttributeValue};
 use pwhash::bcrypt;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use std::{collections::HashMap, env, error::Error, str::FromStr, sync::Arc};
+use std::{str::FromStr, sync::Arc};
 use uuid::Uuid;
 
+use super::item::{self, Item};
+use crate::{
+    config::Config,
+    error::{ErrorCode, ServerError},
+};
+
 #[serde_as]
 #[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
 pub struct UserWithPassword {
@@ -22,56 +28,37 @@ pub struct UserWithoutPassword {
     pub device_id: Uuid,
 }
 
-impl From<&HashMap<String, AttributeValue>> for UserWithPassword {
-    fn from(value: &HashMap<String, AttributeValue>) -> Self {
-        UserWithPassword {
-            user_id: value
-                .get("user_id")
-                .and_then(|v| v.as_s().ok())
-                .cloned()
-                .expect("user_id should exist"),
-            username: value
-                .get("username")
-                .and_then(|v| v.as_s().ok())
-                .cloned()
-                .expect("username should exist"),
-            password: value
-                .get("password")
-                .and_then(|v| v.as_s().ok())
-                .cloned()
-                .expect("password should exist"),
-            device_ids: value
-                .get("device_ids")
-                .and_then(|v| v.as_l().ok())
-                .map(|list| {
-                    list.iter()
-                        .filter_map(|e| e.as_s().ok())
-                        .cloned()
-                        .collect::<Vec<String>>()
-                })
-                .expect("device_ids should exist"),
-        }
+impl TryFrom<&Item> for UserWithPassword {
+    type Error = ServerError;
+
+    fn try_from(value: &Item) -> Result<Self, Self::Error> {
+        Ok(UserWithPassword {
+            user_id: item::string(value, "user_id")?,
+            username: item::string(value, "username")?,
+            password: item::string(value, "password")?,
+            device_ids: item::string_list(value, "device_ids")?,
+        })
     }
 }
 
 pub struct UserRepository {
     client: Arc<DynamoDB::Client>,
+    table_name: String,
 }
 
 impl UserRepository {
-    pub fn new(client: Arc<DynamoDB::Client>) -> Self {
-        Self { client }
-    }
-
-    fn get_table_name() -> String {
-        env::var("USERS_TABLE").unwrap()
+    pub fn new(client: Arc<DynamoDB::Client>, config: &Config) -> Self {
+        Self {
+            client,
+            table_name: config.tables.users.clone(),
+        }
     }
 
     pub async fn create(
         &self,
         user: UserWithoutPassword,
         password: String,
-    ) -> Result<UserWithoutPassword, Box<dyn Error>> {
+    ) -> Result<UserWithoutPassword, ServerError> {
         let hashed_pwd = pwhash::bcrypt::hash(password.clone())?;
 
         let user_id_av = AttributeValue::S(user.user_id.to_string());
@@ -81,7 +68,7 @@ impl UserRepository {
 
         self.client
             .put_item()
-            .table_name(UserRepository::get_table_name())
+            .table_name(&self.table_name)
             .item("user_id", user_id_av)
             .item("device_ids", device_id_av)
             .item("username", username_av)
@@ -97,7 +84,7 @@ impl UserRepository {
         user_id: String,
         device_ids: Vec<String>,
         username: String,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), ServerError> {
         let user_id_av = AttributeValue::S(user_id.to_string());
         let username_av = AttributeValue::S(username.to_string());
         let device_id_av = AttributeValue::L(
@@ -109,7 +96,7 @@ impl UserRepository {
 
         self.client
             .update_item()
-            .table_name(UserRepository::get_table_name())
+            .table_name(&self.table_name)
             .key("user_id", user_id_av.clone())
             .key("username", username_av)
             .update_expression("SET #device_ids = :device_ids")
@@ -121,10 +108,10 @@ impl UserRepository {
         Ok(())
     }
 
-    pub async fn delete(&self, user_id: &Uuid) -> Result<(), Box<dyn Error>> {
+    pub async fn delete(&self, user_id: &Uuid) -> Result<(), ServerError> {
         self.client
             .delete_item()
-            .table_name(UserRepository::get_table_name())
+            .table_name(&self.table_name)
             .key("user_id", AttributeValue::S(user_id.to_string()))
             .send()
             .await?;
@@ -132,11 +119,11 @@ impl UserRepository {
         Ok(())
     }
 
-    pub async fn get(&self, username: &str) -> Result<Option<UserWithPassword>, Box<dyn Error>> {
+    pub async fn get(&self, username: &str) -> Result<Option<UserWithPassword>, ServerError> {
         let user_iter = self
             .client
             .query()
-            .table_name(UserRepository::get_table_name())
+            .table_name(&self.table_name)
             .index_name("username-gsi")
             .key_condition_expression("#username=:username")
             .expression_attribute_names("#username", "username")
@@ -145,20 +132,19 @@ impl UserRepository {
             .await?;
 
         if let Some(users) = user_iter.items {
-            let user_map: Vec<UserWithPassword> = users.iter().map(|v| v.into()).collect();
-            if user_map.len() > 0 {
-                return Ok(Some(user_map[0].clone()));
+            if let Some(user) = users.first() {
+                return Ok(Some(UserWithPassword::try_from(user)?));
             }
         }
 
         Ok(None)
     }
 
-    pub async fn get_devices(&self, username: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    pub async fn get_devices(&self, username: &str) -> Result<Vec<String>, ServerError> {
         let user_iter = self
             .client
             .query()
-            .table_name(UserRepository::get_table_name())
+            .table_name(&self.table_name)
             .index_name("username-gsi")
             .key_condition_expression("#username=:username")
             .expression_attribute_names("#username", "username")
@@ -167,7 +153,10 @@ impl UserRepository {
             .await?;
 
         if let Some(users) = user_iter.items {
-            let users: Vec<UserWithPassword> = users.iter().map(|v| v.into()).collect();
+            let users = users
+                .iter()
+                .map(UserWithPassword::try_from)
+                .collect::<Result<Vec<UserWithPassword>, ServerError>>()?;
             let devices: Vec<String> = users.into_iter().flat_map(|u| u.device_ids).collect();
 
             return Ok(devices);
@@ -181,9 +170,11 @@ impl UserRepository {
         username: String,
         password: String,
         device_id: String,
-    ) -> Result<Option<UserWithoutPassword>, Box<dyn Error>> {
+    ) -> Result<Option<UserWithoutPassword>, ServerError> {
         println!("Fetching user {}", username);
 
+        let device_uuid = Uuid::from_str(&device_id)
+            .map_err(|_| ServerError::new(ErrorCode::BadRequest, "Invalid device id"))?;
         let user = self.get(&username).await?;
 
         if let Some(users) = user {
@@ -213,9 +204,9 @@ impl UserRepository {
 
             if verification {
                 return Ok(Some(UserWithoutPassword {
-                    user_id: Uuid::from_str(&user_id)?,
+                    user_id: Uuid::from_str(&user_id).map_err(ServerError::internal)?,
                     username: username.clone(),
-                    device_id: Uuid::from_str(&device_id)?,
+                    device_id: device_uuid,
                 }));
             }
         }
@@ -0,0 +1,223 @@
+use std::{error::Error, fmt, io};
+
+use aws_sdk_dynamodb::error::{BuildError, DisplayErrorContext, SdkError};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use jsonwebtoken::errors::ErrorKind as JwtErrorKind;
+use pyxis_shared::payload::PayloadError;
+
+/*
+ * Codes sent to clients with every error. They are part of the API, so a code
+ * is never renamed or reused for something else once released.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    BadRequest,
+    // A sync message the server can't read
+    InvalidPayload,
+    Unauthorized,
+    InvalidCredentials,
+    InvalidToken,
+    TokenExpired,
+    SyncDisabled,
+    NotFound,
+    // A different record is stored under the same key
+    Conflict,
+    UserExists,
+    DatabaseError,
+    StorageError,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::BadRequest => "bad_request",
+            ErrorCode::InvalidPayload => "invalid_payload",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::InvalidCredentials => "invalid_credentials",
+            ErrorCode::InvalidToken => "invalid_token",
+            ErrorCode::TokenExpired => "token_expired",
+            ErrorCode::SyncDisabled => "sync_disabled",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::Conflict => "conflict",
+            ErrorCode::UserExists => "user_exists",
+            ErrorCode::DatabaseError => "database_error",
+            ErrorCode::StorageError => "storage_error",
+            ErrorCode::InternalError => "internal_error",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ErrorCode::BadRequest => StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidPayload => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::Unauthorized
+            | ErrorCode::InvalidCredentials
+            | ErrorCode::InvalidToken
+            | ErrorCode::TokenExpired => StatusCode::UNAUTHORIZED,
+            ErrorCode::SyncDisabled => StatusCode::FORBIDDEN,
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::Conflict | ErrorCode::UserExists => StatusCode::CONFLICT,
+            ErrorCode::DatabaseError | ErrorCode::StorageError | ErrorCode::InternalError => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    // Sent when the error has nothing more useful to tell the client
+    fn default_message(&self) -> &'static str {
+        match self {
+            ErrorCode::BadRequest => "Bad request",
+            ErrorCode::InvalidPayload => "Invalid payload",
+            ErrorCode::Unauthorized => "Not signed in",
+            ErrorCode::InvalidCredentials => "Password verification failed!",
+            ErrorCode::InvalidToken => "Invalid token",
+            ErrorCode::TokenExpired => "Token expired",
+            ErrorCode::SyncDisabled => "Sync is not enabled",
+            ErrorCode::NotFound => "Not found",
+            ErrorCode::Conflict => "Conflicting record",
+            ErrorCode::UserExists => "Username taken!",
+            ErrorCode::DatabaseError => "Database error",
+            ErrorCode::StorageError => "Storage error",
+            ErrorCode::InternalError => "Internal server error",
+        }
+    }
+}
+
+/*
+ * Errors returned by handlers and repositories. The message is shown to the
+ * client, while the detail of a failure is only logged.
+ */
+#[derive(Debug)]
+pub struct ServerError {
+    pub code: ErrorCode,
+    pub message: String,
+    detail: Option<String>,
+}
+
+impl ServerError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        ServerError {
+            code,
+            message: message.into(),
+            detail: None,
+        }
+    }
+
+    // A failure the client can't do anything about, with its cause kept for the logs
+    pub fn failure(code: ErrorCode, detail: impl fmt::Display) -> Self {
+        ServerError {
+            code,
+            message: code.default_message().to_owned(),
+            detail: Some(detail.to_string()),
+        }
+    }
+
+    pub fn from_code(code: ErrorCode) -> Self {
+        ServerError::new(code, code.default_message())
+    }
+
+    pub fn internal(detail: impl fmt::Display) -> Self {
+        ServerError::failure(ErrorCode::InternalError, detail)
+    }
+
+    // Adds what was being done when the error happened to the logged detail
+    pub fn context(mut self, context: &str) -> Self {
+        let detail = self.detail.as_deref().unwrap_or(&self.message);
+        self.detail = Some(format!("{} {}", context, detail));
+        self
+    }
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.detail {
+            Some(detail) => write!(f, "[{}] {}", self.code.as_str(), detail),
+            None => write!(f, "[{}] {}", self.code.as_str(), self.message),
+        }
+    }
+}
+
+impl Error for ServerError {}
+
+impl IntoResponse for ServerError {
+    fn into_response(self) -> Response {
+        let status = self.code.status();
+        if status.is_server_error() {
+            eprintln!("[Server] {}", self);
+        } else {
+            println!("[Server] {}", self);
+        }
+
+        let body = serde_json::json!({
+            "error": {
+                "code": self.code.as_str(),
+                "message": self.message,
+            }
+        });
+
+        // Read by the metrics layer
+        let mut response = (status, Json(body)).into_response();
+        response.extensions_mut().insert(self.code);
+        response
+    }
+}
+
+impl<E, R> From<SdkError<E, R>> for ServerError
+where
+    E: Error + 'static,
+    R: fmt::Debug,
+{
+    fn from(value: SdkError<E, R>) -> Self {
+        ServerError::failure(ErrorCode::DatabaseError, DisplayErrorContext(&value))
+    }
+}
+
+impl From<BuildError> for ServerError {
+    fn from(value: BuildError) -> Self {
+        ServerError::failure(ErrorCode::DatabaseError, value)
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for ServerError {
+    fn from(value: jsonwebtoken::errors::Error) -> Self {
+        match value.kind() {
+            JwtErrorKind::ExpiredSignature => ServerError::failure(ErrorCode::TokenExpired, value),
+            // Signing failures come from the server keys, not from the token
+            JwtErrorKind::InvalidEcdsaKey
+            | JwtErrorKind::InvalidRsaKey(_)
+            | JwtErrorKind::RsaFailedSigning
+            | JwtErrorKind::InvalidKeyFormat
+            | JwtErrorKind::Crypto(_) => ServerError::internal(value),
+            _ => ServerError::failure(ErrorCode::InvalidToken, value),
+        }
+    }
+}
+
+impl From<pwhash::error::Error> for ServerError {
+    fn from(value: pwhash::error::Error) -> Self {
+        ServerError::internal(format!("bcrypt: {}", value))
+    }
+}
+
+impl From<io::Error> for ServerError {
+    fn from(value: io::Error) -> Self {
+        match value.kind() {
+            io::ErrorKind::NotFound => ServerError::failure(ErrorCode::NotFound, value),
+            io::ErrorKind::InvalidInput => {
+                ServerError::new(ErrorCode::BadRequest, value.to_string())
+            }
+            _ => ServerError::failure(ErrorCode::StorageError, value),
+        }
+    }
+}
+
+impl From<PayloadError> for ServerError {
+    fn from(value: PayloadError) -> Self {
+        ServerError::new(ErrorCode::InvalidPayload, value.to_string())
+    }
+}
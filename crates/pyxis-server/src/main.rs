@@ -1,24 +1,68 @@
+pub mod blob_client;
+pub mod config;
 pub mod database;
 pub mod dynamo_client;
+pub mod error;
 pub mod server;
 pub mod sns_client;
+pub mod telemetry;
 
+use blob_client::BlobStore;
+use config::Config;
 use dotenv::dotenv;
 use dynamo_client::Dynamo;
 use server::router::create_route;
 use sns_client::SNS;
-use std::{env, error::Error, sync::Arc};
+use std::{error::Error, process::ExitCode, sync::Arc, time::Duration};
+
+// Histograms are drained into their buckets on this interval
+const METRICS_UPKEEP_INTERVAL: Duration = Duration::from_secs(5);
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
+async fn main() -> ExitCode {
     dotenv().ok();
 
+    // A missing setting stops the server here rather than failing requests later
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("[Config] {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match serve(config).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("[Server] Stopped. Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn serve(config: Config) -> Result<(), Box<dyn Error>> {
     let dynamo = Dynamo::new().await?;
     let sns = SNS::create_client().await?;
+    let blobs = BlobStore::new(&config.blob_store).await?;
 
-    let app = create_route(Arc::new(dynamo), Arc::new(sns));
+    let metrics = telemetry::install()?;
+    let upkeep = metrics.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(METRICS_UPKEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            upkeep.run_upkeep();
+        }
+    });
 
-    let port = env::var("PORT").unwrap();
+    let port = config.port;
+    let app = create_route(
+        Arc::new(dynamo),
+        Arc::new(sns),
+        Arc::new(blobs),
+        metrics,
+        Arc::new(config),
+    );
 
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
     axum::serve(listener, app).await?;
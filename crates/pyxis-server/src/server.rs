@@ -1,4 +1,5 @@
 pub mod auth;
+pub mod health;
 mod middlewares;
 pub mod router;
 pub mod sync;
@@ -1,8 +1,11 @@
 use crate::server::router::AWSConnectionState;
-use axum::{extract::State, http::StatusCode, Extension, Json};
+use axum::{extract::State, Extension, Json};
 use serde::Serialize;
 
-use crate::database::{token_repository::Claims, user_repository::UserRepository};
+use crate::{
+    database::{token_repository::Claims, user_repository::UserRepository},
+    error::ServerError,
+};
 
 #[derive(Serialize)]
 pub struct DevicesResponse {
@@ -13,13 +16,13 @@ pub struct DevicesResponse {
 pub async fn get_devices(
     State(connections): State<AWSConnectionState>,
     Extension(claims): Extension<Claims>,
-) -> Result<Json<DevicesResponse>, StatusCode> {
-    let user_repository = UserRepository::new(connections.dynamo.connection.clone());
-    match user_repository.get_devices(&claims.user.username).await {
-        Ok(devices) => Ok(Json(DevicesResponse { devices })),
-        Err(e) => {
-            println!("Error while getting user: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
-    }
+) -> Result<Json<DevicesResponse>, ServerError> {
+    let user_repository =
+        UserRepository::new(connections.dynamo.connection.clone(), &connections.config);
+    let devices = user_repository
+        .get_devices(&claims.user.username)
+        .await
+        .map_err(|e| e.context("Error while getting user:"))?;
+
+    Ok(Json(DevicesResponse { devices }))
 }
@@ -1,29 +1,29 @@
 use crate::server::router::AWSConnectionState;
 use axum::{extract::State, http::StatusCode, Extension};
 
-use crate::database::token_repository::{Claims, TokenRepository};
+use crate::{
+    database::token_repository::{Claims, TokenRepository},
+    error::ServerError,
+};
 
 #[axum_macros::debug_handler]
 pub async fn sign_out(
     Extension(claims): Extension<Claims>,
     State(connections): State<AWSConnectionState>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<StatusCode, ServerError> {
     let Claims {
         user,
         exp: _,
         iat: _,
     } = claims;
 
-    let token_repository = TokenRepository::new(connections.dynamo.connection.clone());
+    let token_repository =
+        TokenRepository::new(connections.dynamo.connection.clone(), &connections.config);
 
-    let delete_response = token_repository
+    token_repository
         .delete(&user.user_id, &user.device_id)
-        .await;
-
-    if let Err(e) = delete_response {
-        println!("Error while signing out! {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
-    }
+        .await
+        .map_err(|e| e.context("Error while signing out!"))?;
 
     Ok(StatusCode::OK)
 }
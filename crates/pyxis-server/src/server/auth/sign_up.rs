@@ -1,16 +1,14 @@
 use crate::server::router::AWSConnectionState;
-use axum::{
-    extract::State,
-    http::StatusCode,
-    response::{IntoResponse, Response},
-    Json,
-};
+use axum::{extract::State, Json};
 use serde::Deserialize;
 use uuid::Uuid;
 
-use crate::database::{
-    token_repository::{TokenRepository, UserToken},
-    user_repository::{UserRepository, UserWithoutPassword},
+use crate::{
+    database::{
+        token_repository::{TokenRepository, UserToken},
+        user_repository::{UserRepository, UserWithoutPassword},
+    },
+    error::{ErrorCode, ServerError},
 };
 
 #[derive(Deserialize)]
@@ -24,7 +22,7 @@ pub struct SignUpPayload {
 pub async fn sign_up(
     State(connections): State<AWSConnectionState>,
     Json(user): Json<SignUpPayload>,
-) -> Result<Json<UserToken>, Response> {
+) -> Result<Json<UserToken>, ServerError> {
     let SignUpPayload {
         device_id,
         password,
@@ -32,28 +30,20 @@ pub async fn sign_up(
     } = user;
 
     let user_repository: UserRepository =
-        UserRepository::new(connections.dynamo.connection.clone());
-    let token_repository = TokenRepository::new(connections.dynamo.connection.clone());
+        UserRepository::new(connections.dynamo.connection.clone(), &connections.config);
+    let token_repository =
+        TokenRepository::new(connections.dynamo.connection.clone(), &connections.config);
 
-    match user_repository.get(&username).await {
-        Ok(user) => {
-            if let Some(_) = user {
-                println!("Username taken!");
-                return Err((StatusCode::CONFLICT, format!("Username taken!")).into_response());
-            }
-        }
-        Err(e) => {
-            println!("Error while trying to validate username: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Signup failed! Unable to fetch username."),
-            )
-                .into_response());
-        }
-    };
+    let existing = user_repository
+        .get(&username)
+        .await
+        .map_err(|e| e.context("Error while trying to validate username:"))?;
+    if existing.is_some() {
+        return Err(ServerError::from_code(ErrorCode::UserExists));
+    }
 
     let user_id = Uuid::new_v4();
-    let user = match user_repository
+    let user = user_repository
         .create(
             UserWithoutPassword {
                 device_id,
@@ -63,29 +53,12 @@ pub async fn sign_up(
             password,
         )
         .await
-    {
-        Ok(user) => user,
-        Err(e) => {
-            println!("Error while trying to create user: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Signup failed! Error while creating user."),
-            )
-                .into_response());
-        }
-    };
+        .map_err(|e| e.context("Error while trying to create user:"))?;
 
-    let user_token = match token_repository.create(user).await {
-        Ok(token) => token,
-        Err(e) => {
-            println!("Error while trying to create token: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Signup failed! Error while creating token."),
-            )
-                .into_response());
-        }
-    };
+    let user_token = token_repository
+        .create(user)
+        .await
+        .map_err(|e| e.context("Error while trying to create token:"))?;
 
-    return Ok(Json(user_token));
+    Ok(Json(user_token))
 }
@@ -1,8 +1,9 @@
 use crate::{
     database::features_repository::{Feature, FeaturesRepository},
+    error::ServerError,
     server::router::AWSConnectionState,
 };
-use axum::{extract::State, http::StatusCode, Extension, Json};
+use axum::{extract::State, Extension, Json};
 use serde::Serialize;
 
 use crate::database::token_repository::Claims;
@@ -16,16 +17,13 @@ pub struct FeaturesResponse {
 pub async fn get_subscription(
     State(connections): State<AWSConnectionState>,
     Extension(claims): Extension<Claims>,
-) -> Result<Json<FeaturesResponse>, StatusCode> {
-    let features_repository = FeaturesRepository::new(connections.dynamo.connection.clone());
-    match features_repository
+) -> Result<Json<FeaturesResponse>, ServerError> {
+    let features_repository =
+        FeaturesRepository::new(connections.dynamo.connection.clone(), &connections.config);
+    let features = features_repository
         .get(claims.user.user_id.to_string())
         .await
-    {
-        Ok(features) => Ok(Json(FeaturesResponse { features })),
-        Err(e) => {
-            println!("Error while getting features: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
-    }
+        .map_err(|e| e.context("Error while getting features:"))?;
+
+    Ok(Json(FeaturesResponse { features }))
 }
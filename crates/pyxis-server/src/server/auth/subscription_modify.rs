@@ -1,4 +1,4 @@
-use std::{collections::HashMap, env};
+use std::collections::HashMap;
 
 use axum::{extract::State, http::StatusCode, Extension, Json};
 use serde::Deserialize;
@@ -9,6 +9,7 @@ use crate::{
         features_repository::{Feature, FeaturesRepository},
         token_repository::Claims,
     },
+    error::ServerError,
     server::router::AWSConnectionState,
 };
 
@@ -23,11 +24,16 @@ pub async fn modify_subscription(
     Extension(claims): Extension<Claims>,
     State(connections): State<AWSConnectionState>,
     Json(payload): Json<SubscriptionPayload>,
-) -> Result<StatusCode, StatusCode> {
-    let AWSConnectionState { dynamo, sns } = connections;
+) -> Result<StatusCode, ServerError> {
+    let AWSConnectionState {
+        dynamo,
+        sns,
+        config,
+        ..
+    } = connections;
     let client = sns.client.clone();
     let features_repostiory: FeaturesRepository =
-        FeaturesRepository::new(dynamo.connection.clone());
+        FeaturesRepository::new(dynamo.connection.clone(), &config);
 
     let Claims {
         user,
@@ -35,62 +41,51 @@ pub async fn modify_subscription(
         iat: _,
     } = claims;
 
-    let feature = match features_repostiory.get(user.user_id.to_string()).await {
-        Ok(features) => {
-            if let Some(features) = features {
-                let Feature {
-                    user_id,
-                    mut features,
-                } = features;
+    let existing = features_repostiory
+        .get(user.user_id.to_string())
+        .await
+        .map_err(|e| e.context("[Features] Failed to get existing features!"))?;
 
-                features.insert(payload.key, payload.value.to_string());
+    let feature = if let Some(features) = existing {
+        let Feature {
+            user_id,
+            mut features,
+        } = features;
 
-                Feature { user_id, features }
-            } else {
-                let mut features = HashMap::new();
-                features.insert(payload.key, payload.value.to_string());
+        features.insert(payload.key, payload.value.to_string());
 
-                Feature {
-                    user_id: user.user_id.to_string(),
-                    features,
-                }
-            }
-        }
-        Err(e) => {
-            eprintln!("[Features] Failed to get existing features! Error: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        Feature { user_id, features }
+    } else {
+        let mut features = HashMap::new();
+        features.insert(payload.key, payload.value.to_string());
+
+        Feature {
+            user_id: user.user_id.to_string(),
+            features,
         }
     };
 
-    let create_response = features_repostiory.upsert(&feature).await;
+    features_repostiory
+        .upsert(&feature)
+        .await
+        .map_err(|e| e.context("Error while creating subscription."))?;
 
-    if let Ok(_) = create_response {
-        if payload.value == String::from("requested") {
-            match client
-                .publish()
-                .topic_arn(
-                    env::var("SUBSCRIPTION_REQUEST_SNS")
-                        .expect("[Features] Subscription SNS topic should be specified"),
-                )
-                .message(json!(feature).to_string())
-                .send()
-                .await
-            {
-                Ok(_) => {
-                    println!("Published message");
-                    return Ok(StatusCode::CREATED);
-                }
-                Err(e) => {
-                    eprintln!("[Features] Failed to send SNS notification! Error: {:?}", e);
-                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
-                }
-            };
-        }
-    } else {
-        if let Err(e) = create_response {
-            eprintln!("Error while creating subscription. {}", e);
-        }
+    if payload.value == String::from("requested") {
+        client
+            .publish()
+            .topic_arn(&config.subscription_request_sns)
+            .message(json!(feature).to_string())
+            .send()
+            .await
+            .map_err(|e| {
+                ServerError::internal(format!(
+                    "[Features] Failed to send SNS notification! Error: {:?}",
+                    e
+                ))
+            })?;
+
+        println!("Published message");
     }
 
-    Err(StatusCode::INTERNAL_SERVER_ERROR)
+    Ok(StatusCode::CREATED)
 }
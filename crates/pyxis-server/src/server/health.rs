@@ -0,0 +1,4 @@
+pub mod checks;
+pub mod healthz;
+pub mod metrics_get;
+pub mod readyz;
@@ -0,0 +1,67 @@
+use axum::{http::StatusCode, Json};
+use futures::future::try_join_all;
+use serde_json::{Map, Value};
+
+use crate::{blob_client::BlobStore, config::Tables, dynamo_client::Dynamo};
+
+pub type Check = (&'static str, Result<(), String>);
+
+// Every table the server uses exists and can be reached with its credentials
+pub async fn dynamodb(dynamo: &Dynamo, tables: &Tables) -> Result<(), String> {
+    try_join_all(tables.all().into_iter().map(|table| async move {
+        dynamo
+            .connection
+            .describe_table()
+            .table_name(table)
+            .send()
+            .await
+            .map_err(|e| {
+                format!(
+                    "{}: {}",
+                    table,
+                    aws_sdk_dynamodb::error::DisplayErrorContext(e)
+                )
+            })
+    }))
+    .await
+    .map(|_| ())
+}
+
+pub async fn blob_store(blobs: &BlobStore) -> Result<(), String> {
+    blobs.check().await.map_err(|e| e.to_string())
+}
+
+/*
+ * 200 when every check passed and 503 otherwise. Each check is listed with
+ * "ok" or what went wrong, so a probe failure can be read off the response.
+ */
+pub fn report(checks: Vec<Check>) -> (StatusCode, Json<Value>) {
+    let healthy = checks.iter().all(|(_, result)| result.is_ok());
+
+    let checks: Map<String, Value> = checks
+        .into_iter()
+        .map(|(name, result)| {
+            let status = match result {
+                Ok(()) => String::from("ok"),
+                Err(e) => {
+                    eprintln!("[Health] {} check failed. Error: {}", name, e);
+                    e
+                }
+            };
+            (name.to_owned(), Value::String(status))
+        })
+        .collect();
+
+    let (status, body) = match healthy {
+        true => (StatusCode::OK, "ok"),
+        false => (StatusCode::SERVICE_UNAVAILABLE, "unavailable"),
+    };
+
+    (
+        status,
+        Json(serde_json::json!({
+            "status": body,
+            "checks": checks,
+        })),
+    )
+}
@@ -0,0 +1,13 @@
+use axum::{http::StatusCode, Json};
+use serde_json::Value;
+
+use super::checks::report;
+
+/*
+ * Liveness. The configuration is validated at boot and storage is left to
+ * /readyz, so that a storage outage doesn't get the server restarted.
+ */
+#[axum_macros::debug_handler]
+pub async fn healthz() -> (StatusCode, Json<Value>) {
+    report(vec![])
+}
@@ -0,0 +1,17 @@
+use axum::{
+    extract::State,
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::IntoResponse,
+};
+
+use crate::server::router::AWSConnectionState;
+
+// Prometheus text format
+#[axum_macros::debug_handler]
+pub async fn metrics_get(State(connections): State<AWSConnectionState>) -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        connections.metrics.render(),
+    )
+}
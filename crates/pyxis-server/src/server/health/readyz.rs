@@ -0,0 +1,16 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde_json::Value;
+
+use super::checks::{self, report};
+use crate::server::router::AWSConnectionState;
+
+// Readiness. Whether requests can be served, storage included.
+#[axum_macros::debug_handler]
+pub async fn readyz(State(connections): State<AWSConnectionState>) -> (StatusCode, Json<Value>) {
+    let (dynamodb, blob_store) = tokio::join!(
+        checks::dynamodb(&connections.dynamo, &connections.config.tables),
+        checks::blob_store(&connections.blobs)
+    );
+
+    report(vec![("dynamodb", dynamodb), ("blob_store", blob_store)])
+}
@@ -1,2 +1,4 @@
 pub mod auth;
+pub mod metrics_auth;
+pub mod request_metrics;
 pub mod sync_check;
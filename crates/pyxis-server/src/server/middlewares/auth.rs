@@ -1,46 +1,45 @@
-use std::env;
-
-use axum::{body::Body, extract::Request, http::StatusCode, middleware::Next, response::Response};
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
 
 use jsonwebtoken::{decode, DecodingKey, TokenData, Validation};
 
-use crate::database::token_repository::Claims;
+use crate::{
+    database::token_repository::Claims,
+    error::{ErrorCode, ServerError},
+    server::router::AWSConnectionState,
+};
 
-pub async fn check_token(mut request: Request<Body>, next: Next) -> Result<Response, StatusCode> {
-    let token: Vec<&str> = request
+pub async fn check_token(
+    State(connections): State<AWSConnectionState>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Result<Response, ServerError> {
+    let token = request
         .headers()
         .get("authorization")
         .and_then(|header| header.to_str().ok())
-        .ok_or(StatusCode::UNAUTHORIZED)?
-        .split(' ')
-        .collect();
-
-    let token = token[1];
+        .and_then(|header| header.split(' ').nth(1))
+        .ok_or_else(|| ServerError::from_code(ErrorCode::Unauthorized))?;
 
-    let key = DecodingKey::from_secret(
-        env::var("AUTH_SECRET")
-            .expect("[Auth middleware] No authentication secret specified!")
-            .as_bytes(),
-    );
+    let key = DecodingKey::from_secret(connections.config.auth_secret.as_bytes());
 
-    match decode::<Claims>(
+    let TokenData { claims, header: _ } = decode::<Claims>(
         token,
         &key,
         &Validation::new(jsonwebtoken::Algorithm::HS256),
-    ) {
-        Ok(TokenData { claims, header: _ }) => {
-            println!("Inserting claims");
-            request.extensions_mut().insert(claims);
-            let response = next.run(request).await;
-            Ok(response)
-        }
-        Err(e) => {
-            println!(
-                "[Auth Middleware] Failed to verify token signature. Endpoint: {}, Error: {}",
-                request.uri().to_string(),
-                e
-            );
-            Err(StatusCode::UNAUTHORIZED)
-        }
-    }
+    )
+    .map_err(|e| {
+        ServerError::from(e).context(&format!(
+            "[Auth Middleware] Failed to verify token signature. Endpoint: {}, Error:",
+            request.uri()
+        ))
+    })?;
+
+    println!("Inserting claims");
+    request.extensions_mut().insert(claims);
+    Ok(next.run(request).await)
 }
@@ -0,0 +1,49 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    error::{ErrorCode, ServerError},
+    server::router::AWSConnectionState,
+};
+
+// Compares every byte so that the time taken does not tell how much of the token matched
+fn tokens_match(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len()
+        && given
+            .iter()
+            .zip(expected)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/*
+ * /metrics names every route and how often it fails, so it is only served to
+ * scrapers holding the token from the config. It is not a user token.
+ */
+#[axum_macros::debug_middleware]
+pub async fn check_metrics_token(
+    State(connections): State<AWSConnectionState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ServerError> {
+    let token = request
+        .headers()
+        .get("authorization")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "));
+
+    match (token, &connections.config.metrics_token) {
+        (Some(token), Some(expected)) if tokens_match(token.as_bytes(), expected.as_bytes()) => {
+            Ok(next.run(request).await)
+        }
+        _ => Err(
+            ServerError::from_code(ErrorCode::Unauthorized).context(&format!(
+                "[Metrics Middleware] Invalid metrics token. Endpoint: {}",
+                request.uri(),
+            )),
+        ),
+    }
+}
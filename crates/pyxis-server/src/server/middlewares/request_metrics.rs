@@ -0,0 +1,68 @@
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use metrics::{counter, histogram};
+
+use crate::{
+    error::ErrorCode,
+    telemetry::{
+        AUTH_FAILURES, DYNAMODB_ERRORS, HTTP_REQUESTS, HTTP_REQUEST_DURATION, SYNC_WRITES,
+        SYNC_WRITE_BYTES,
+    },
+};
+
+// Set on the response of a sync write, so that it is counted under its source
+#[derive(Debug, Clone)]
+pub struct SyncWrite {
+    pub source: String,
+    pub bytes: usize,
+}
+
+/*
+ * Records every request under the route it matched rather than its path, so
+ * that ids in paths don't turn into labels. Errors are told apart by the code
+ * the error response carries.
+ */
+pub async fn track_metrics(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| String::from("unmatched"));
+    let method = request.method().to_string();
+    let start = Instant::now();
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    counter!(HTTP_REQUESTS, "method" => method.clone(), "route" => route.clone(), "status" => status)
+        .increment(1);
+    histogram!(HTTP_REQUEST_DURATION, "method" => method, "route" => route.clone())
+        .record(start.elapsed().as_secs_f64());
+
+    match response.extensions().get::<ErrorCode>() {
+        Some(
+            code @ (ErrorCode::Unauthorized
+            | ErrorCode::InvalidCredentials
+            | ErrorCode::InvalidToken
+            | ErrorCode::TokenExpired),
+        ) => {
+            counter!(AUTH_FAILURES, "route" => route, "code" => code.as_str()).increment(1);
+        }
+        Some(ErrorCode::DatabaseError) => {
+            counter!(DYNAMODB_ERRORS, "route" => route).increment(1);
+        }
+        _ => {}
+    }
+
+    if let Some(write) = response.extensions().get::<SyncWrite>() {
+        counter!(SYNC_WRITES, "source" => write.source.clone()).increment(1);
+        counter!(SYNC_WRITE_BYTES, "source" => write.source.clone()).increment(write.bytes as u64);
+    }
+
+    response
+}
@@ -1,6 +1,5 @@
 use axum::{
     extract::{Request, State},
-    http::StatusCode,
     middleware::Next,
     response::Response,
 };
@@ -10,6 +9,7 @@ use crate::{
         features_repository::{Feature, FeaturesRepository},
         token_repository::Claims,
     },
+    error::{ErrorCode, ServerError},
     server::router::AWSConnectionState,
 };
 
@@ -26,45 +26,39 @@ pub async fn check_sync_feature(
     State(connections): State<AWSConnectionState>,
     request: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
+) -> Result<Response, ServerError> {
     let claims = request.extensions().get::<Claims>();
     println!("Claims: {:?}", claims);
 
-    if claims.is_none() {
-        eprintln!(
-            "[Sync Check Middleware] Missing Claims. Endpoint: {}",
-            request.uri().to_string(),
+    let Some(claims) = claims else {
+        return Err(
+            ServerError::from_code(ErrorCode::Unauthorized).context(&format!(
+                "[Sync Check Middleware] Missing Claims. Endpoint: {}",
+                request.uri(),
+            )),
         );
+    };
 
-        return Err(StatusCode::FORBIDDEN);
-    }
-
-    let claims = claims.unwrap();
-
-    let features_repository = FeaturesRepository::new(connections.dynamo.connection.clone());
-    match features_repository
+    let features_repository =
+        FeaturesRepository::new(connections.dynamo.connection.clone(), &connections.config);
+    let user_features = features_repository
         .get(claims.user.user_id.to_string())
         .await
-    {
-        Ok(user_features) => {
-            if !is_sync_enabled(&user_features) {
-                eprintln!(
-                    "[Sync Check Middleware] Sync not enabled. Endpoint: {}",
-                    request.uri().to_string(),
-                );
+        .map_err(|e| {
+            e.context(&format!(
+                "[Sync Check Middleware] Unable to get features. Endpoint: {}, Error:",
+                request.uri(),
+            ))
+        })?;
 
-                return Err(StatusCode::FORBIDDEN);
-            }
-
-            Ok(next.run(request).await)
-        }
-        Err(e) => {
-            eprintln!(
-                "[Sync Check Middleware] Unable to get features. Endpoint: {}, Error: {}",
-                request.uri().to_string(),
-                e
-            );
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
+    if !is_sync_enabled(&user_features) {
+        return Err(
+            ServerError::from_code(ErrorCode::SyncDisabled).context(&format!(
+                "[Sync Check Middleware] Sync not enabled. Endpoint: {}",
+                request.uri(),
+            )),
+        );
     }
+
+    Ok(next.run(request).await)
 }
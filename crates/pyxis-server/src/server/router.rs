@@ -5,58 +5,109 @@ use super::{
         get_devices::get_devices, sign_in::sign_in, sign_out::sign_out, sign_up::sign_up,
         subscription_get::get_subscription, subscription_modify::modify_subscription,
     },
+    health::{healthz::healthz, metrics_get::metrics_get, readyz::readyz},
     middlewares::auth::check_token,
+    middlewares::metrics_auth::check_metrics_token,
+    middlewares::request_metrics::track_metrics,
     middlewares::sync_check::check_sync_feature,
     sync::{
-        document_list::document_list, document_write::document_write, ping::ping,
-        updates_list::updates_list, updates_write::updates_write,
+        attachment_get::attachment_get, attachment_write::attachment_write,
+        changes_list::changes_list, document_list::document_list, document_write::document_write,
+        ping::ping, updates_list::updates_list, updates_write::updates_write,
     },
 };
-use crate::{dynamo_client::Dynamo, sns_client::SNS};
+use crate::{blob_client::BlobStore, config::Config, dynamo_client::Dynamo, sns_client::SNS};
 use axum::{
+    extract::DefaultBodyLimit,
     middleware,
-    routing::{get, post},
+    routing::{get, post, put},
     Router,
 };
+use metrics_exporter_prometheus::PrometheusHandle;
+
+// Attachments are uploaded as a single request body
+const MAX_ATTACHMENT_SIZE: usize = 25 * 1024 * 1024;
+// Snapshots of long notes go past the default limit, their bytes are sent as json numbers
+const MAX_DOCUMENT_SIZE: usize = 32 * 1024 * 1024;
 
 #[derive(Clone)]
 pub struct AWSConnectionState {
     pub dynamo: Arc<Dynamo>,
     pub sns: Arc<SNS>,
+    pub blobs: Arc<BlobStore>,
+    pub metrics: PrometheusHandle,
+    pub config: Arc<Config>,
 }
 
-pub fn create_route(dynamo: Arc<Dynamo>, sns: Arc<SNS>) -> Router {
+pub fn create_route(
+    dynamo: Arc<Dynamo>,
+    sns: Arc<SNS>,
+    blobs: Arc<BlobStore>,
+    metrics: PrometheusHandle,
+    config: Arc<Config>,
+) -> Router {
+    let state = AWSConnectionState {
+        dynamo,
+        sns,
+        blobs,
+        metrics,
+        config,
+    };
+
     let protected_auth_routes = Router::new()
         .route("/signout", post(sign_out))
         .route("/devices", get(get_devices))
         .route("/features", get(get_subscription))
         .route("/features", post(modify_subscription))
-        .layer(middleware::from_fn(check_token));
+        .layer(middleware::from_fn_with_state(state.clone(), check_token));
 
     let auth_router = Router::new()
         .merge(protected_auth_routes)
         .route("/signup", post(sign_up))
         .route("/signin", post(sign_in));
 
-    let state = AWSConnectionState {
-        dynamo: dynamo.clone(),
-        sns: sns.clone(),
-    };
-
     let protected_sync_router = Router::new()
-        .route("/document/write", post(document_write))
+        .route(
+            "/document/write",
+            post(document_write).layer(DefaultBodyLimit::max(MAX_DOCUMENT_SIZE)),
+        )
         .route("/document/list", get(document_list))
         .route("/update/write", post(updates_write))
         .route("/update/list", get(updates_list))
-        .layer(middleware::from_fn_with_state(state, check_sync_feature))
-        .layer(middleware::from_fn(check_token));
+        .route("/changes", get(changes_list))
+        .route(
+            "/attachment/:hash",
+            put(attachment_write).layer(DefaultBodyLimit::max(MAX_ATTACHMENT_SIZE)),
+        )
+        .route("/attachment/:hash", get(attachment_get))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            check_sync_feature,
+        ))
+        .layer(middleware::from_fn_with_state(state.clone(), check_token));
 
     let sync_router = Router::new()
         .merge(protected_sync_router)
         .route("/ping", get(ping));
 
-    Router::new()
+    let mut router = Router::new()
         .nest("/auth", auth_router)
         .nest("/sync", sync_router)
-        .with_state(AWSConnectionState { dynamo, sns })
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz));
+
+    // Only served with a token to check scrapers against
+    if state.config.metrics_token.is_some() {
+        router = router.route(
+            "/metrics",
+            get(metrics_get).layer(middleware::from_fn_with_state(
+                state.clone(),
+                check_metrics_token,
+            )),
+        );
+    }
+
+    router
+        .layer(middleware::from_fn(track_metrics))
+        .with_state(state)
 }
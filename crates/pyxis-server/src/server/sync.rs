@@ -1,3 +1,6 @@
+pub mod attachment_get;
+pub mod attachment_write;
+pub mod changes_list;
 pub mod document_list;
 pub mod document_write;
 pub mod ping;
@@ -0,0 +1,30 @@
+use crate::server::router::AWSConnectionState;
+use axum::{
+    extract::{Path, State},
+    http::header::CONTENT_TYPE,
+    response::IntoResponse,
+    Extension,
+};
+
+use crate::{database::token_repository::Claims, error::ServerError};
+
+#[axum_macros::debug_handler]
+pub async fn attachment_get(
+    Extension(claims): Extension<Claims>,
+    State(connections): State<AWSConnectionState>,
+    Path(hash): Path<String>,
+) -> Result<impl IntoResponse, ServerError> {
+    let Claims {
+        user,
+        exp: _,
+        iat: _,
+    } = claims;
+
+    let content = connections
+        .blobs
+        .get(&user.user_id.to_string(), &hash)
+        .await
+        .map_err(|e| ServerError::from(e).context("Error while reading blob!"))?;
+
+    Ok(([(CONTENT_TYPE, "application/octet-stream")], content))
+}
@@ -0,0 +1,60 @@
+use crate::server::router::AWSConnectionState;
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    Extension,
+};
+use pyxis_shared::utils::blob_store;
+
+use crate::{
+    database::token_repository::Claims,
+    error::{ErrorCode, ServerError},
+    server::middlewares::request_metrics::SyncWrite,
+};
+
+#[axum_macros::debug_handler]
+pub async fn attachment_write(
+    Extension(claims): Extension<Claims>,
+    State(connections): State<AWSConnectionState>,
+    Path(hash): Path<String>,
+    content: Bytes,
+) -> Result<(Option<Extension<SyncWrite>>, StatusCode), ServerError> {
+    let Claims {
+        user,
+        exp: _,
+        iat: _,
+    } = claims;
+
+    if !blob_store::is_valid_hash(&hash) || blob_store::hash(&content) != hash {
+        return Err(ServerError::new(
+            ErrorCode::BadRequest,
+            "Content does not match its hash",
+        ));
+    }
+
+    let user_id = user.user_id.to_string();
+
+    // Blobs are content-addressed, so a re-upload never changes anything
+    let exists = connections
+        .blobs
+        .exists(&user_id, &hash)
+        .await
+        .map_err(|e| ServerError::from(e).context("Error while checking blob!"))?;
+    if exists {
+        return Ok((None, StatusCode::OK));
+    }
+
+    connections
+        .blobs
+        .put(&user_id, &hash, &content)
+        .await
+        .map_err(|e| ServerError::from(e).context("Error while writing blob!"))?;
+
+    let write = SyncWrite {
+        source: String::from("attachments"),
+        bytes: content.len(),
+    };
+
+    Ok((Some(Extension(write)), StatusCode::CREATED))
+}
@@ -0,0 +1,70 @@
+use axum::{
+    extract::{Query, State},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    database::{change_repository::ChangeRepository, token_repository::Claims},
+    error::{ErrorCode, ServerError},
+    server::router::AWSConnectionState,
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct ChangesListQueries {
+    // Last sequence the device has seen. Starts at 0.
+    #[serde(default)]
+    pub since: i64,
+}
+
+#[axum_macros::debug_handler]
+pub async fn changes_list(
+    Extension(claims): Extension<Claims>,
+    State(connections): State<AWSConnectionState>,
+    Query(request): Query<ChangesListQueries>,
+) -> Result<Json<Value>, ServerError> {
+    let Claims {
+        user,
+        exp: _,
+        iat: _,
+    } = claims;
+
+    if request.since < 0 {
+        return Err(ServerError::new(
+            ErrorCode::BadRequest,
+            "since must not be negative",
+        ));
+    }
+
+    let change_repository =
+        ChangeRepository::new(connections.dynamo.connection.clone(), &connections.config);
+
+    let user_id = user.user_id.to_string();
+    let mut list = change_repository
+        .list_since(&user_id, request.since, &user.device_id.to_string())
+        .await
+        .map_err(|e| e.context("Error while listing changes!"))?;
+
+    for change in list.changes.iter_mut() {
+        if let Some(blob) = change.blob.take() {
+            // A missing blob is the server's fault, not a missing change
+            change.payload = connections
+                .blobs
+                .resolve(&user_id, &blob)
+                .await
+                .map_err(|e| {
+                    ServerError::failure(ErrorCode::StorageError, e).context(&format!(
+                        "Error while reading payload of change {}!",
+                        change.seq
+                    ))
+                })?;
+        }
+    }
+
+    Ok(Json(serde_json::json!({
+        "changes": list.changes,
+        "next_cursor": list.next_cursor,
+        "has_more": list.has_more,
+    })))
+}
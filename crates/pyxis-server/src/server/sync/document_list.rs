@@ -1,13 +1,13 @@
 use axum::{
     extract::{Query, State},
-    http::StatusCode,
     Extension, Json,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::{
-    database::{documents_repository::DocumentRepository, token_repository::Claims},
+    database::{cursor, documents_repository::DocumentRepository, token_repository::Claims},
+    error::{ErrorCode, ServerError},
     server::router::AWSConnectionState,
 };
 
@@ -16,6 +16,9 @@ pub struct DocumentListQueries {
     pub record_id: i64,
     pub is_snapshot: bool,
     pub device_id: String,
+    pub limit: Option<i32>,
+    // next_cursor of the previous page
+    pub cursor: Option<String>,
 }
 
 #[axum_macros::debug_handler]
@@ -23,32 +26,59 @@ pub async fn document_list(
     Extension(claims): Extension<Claims>,
     State(connections): State<AWSConnectionState>,
     Query(request): Query<DocumentListQueries>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ServerError> {
     let Claims {
         user,
         exp: _,
         iat: _,
     } = claims;
 
-    let document_repository = DocumentRepository::new(connections.dynamo.connection.clone());
+    let document_repository =
+        DocumentRepository::new(connections.dynamo.connection.clone(), &connections.config);
 
     let DocumentListQueries {
         record_id,
         is_snapshot,
         device_id,
+        limit,
+        cursor,
     } = request;
 
-    let documents_response = document_repository
-        .list_by_record_id(user.user_id.to_string(), device_id, record_id, is_snapshot)
-        .await;
+    let pk = format!("{}/{}", user.user_id, device_id);
+    let start_key = cursor
+        .as_deref()
+        .map(|cursor| cursor::decode(cursor, &pk))
+        .transpose()?;
 
-    if let Ok(documents) = documents_response {
-        let response = serde_json::json!({
-            "documents": documents
-        });
+    let mut page = document_repository
+        .list_by_record_id(
+            user.user_id.to_string(),
+            device_id,
+            record_id,
+            is_snapshot,
+            cursor::page_size(limit),
+            start_key,
+        )
+        .await
+        .map_err(|e| e.context("Error while listing documents!"))?;
 
-        return Ok(Json(response));
+    // Payloads kept in the blob store are sent inline, like any other
+    let user_id = user.user_id.to_string();
+    for document in page.items.iter_mut() {
+        if let Some(blob) = document.blob.take() {
+            document.payload = connections
+                .blobs
+                .resolve(&user_id, &blob)
+                .await
+                .map_err(|e| {
+                    ServerError::failure(ErrorCode::StorageError, e)
+                        .context(&format!("Error while reading payload of {}!", document.sk))
+                })?;
+        }
     }
 
-    Err(StatusCode::INTERNAL_SERVER_ERROR)
+    Ok(Json(serde_json::json!({
+        "documents": page.items,
+        "next_cursor": page.next_cursor,
+    })))
 }
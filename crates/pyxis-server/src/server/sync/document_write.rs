@@ -1,26 +1,34 @@
 use crate::server::router::AWSConnectionState;
-use axum::{extract::State, http::StatusCode, Extension, Json};
+use axum::{extract::State, Extension, Json};
 use pyxis_shared::payload::DocumentWritePayload;
 use serde_json::Value;
 
 use crate::database::{
+    change_repository::{Change, ChangeRepository, WriteResult},
     documents_repository::{Document, DocumentRepository},
     token_repository::Claims,
 };
+use crate::error::{ErrorCode, ServerError};
+use crate::server::middlewares::request_metrics::SyncWrite;
 
 #[axum_macros::debug_handler]
 pub async fn document_write(
     Extension(claims): Extension<Claims>,
     State(connections): State<AWSConnectionState>,
     Json(document): Json<DocumentWritePayload>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<(Extension<SyncWrite>, Json<Value>), ServerError> {
     let Claims {
         user,
         exp: _,
         iat: _,
     } = claims;
 
-    let document_repository = DocumentRepository::new(connections.dynamo.connection.clone());
+    let document_repository =
+        DocumentRepository::new(connections.dynamo.connection.clone(), &connections.config);
+    let change_repository =
+        ChangeRepository::new(connections.dynamo.connection.clone(), &connections.config);
+
+    document.message()?;
 
     let pk = format!("{}/{}", user.user_id, user.device_id);
     let DocumentWritePayload {
@@ -29,7 +37,38 @@ pub async fn document_write(
         operation,
         source,
         file_uid,
+        idempotency_key,
+        version: _,
     } = document;
+    let write = SyncWrite {
+        source: source.clone(),
+        bytes: payload.len(),
+    };
+
+    // Snapshots of long notes don't fit in an item
+    let (payload, blob) = connections
+        .blobs
+        .offload(&user.user_id.to_string(), payload)
+        .await
+        .map_err(|e| {
+            ServerError::failure(ErrorCode::StorageError, e)
+                .context("Error while storing payload as a blob!")
+        })?;
+    let change = Change {
+        seq: 0,
+        device_id: user.device_id.to_string(),
+        kind: String::from("document"),
+        source: source.clone(),
+        operation: operation.clone(),
+        record_id: Some(record_id),
+        update_id: None,
+        snapshot_id: None,
+        file_uid: file_uid.clone(),
+        payload: payload.clone(),
+        encoding: None,
+        blob: blob.clone(),
+        timestamp: 0,
+    };
 
     let doc = Document {
         pk,
@@ -38,22 +77,29 @@ pub async fn document_write(
         operation,
         source,
         file_uid,
+        idempotency_key,
+        blob,
     };
 
-    let write_response = document_repository.create(doc).await;
+    /*
+     * The document and its change are written together. A document sent again
+     * gets the same answer as the first time.
+     */
+    let result = document_repository
+        .create(doc, &change_repository, &user.user_id.to_string(), change)
+        .await
+        .map_err(|e| e.context("Error while writing documents!"))?;
 
-    if let Err(e) = write_response {
-        println!("Error while writing documents! {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    if result == WriteResult::Conflict {
+        return Err(ServerError::new(
+            ErrorCode::Conflict,
+            format!("Record id {} already holds another document!", record_id),
+        ));
     }
 
-    if let Ok(record_id) = write_response {
-        let response = serde_json::json!({
-            "record_id": record_id
-        });
-
-        return Ok(Json(response));
-    }
+    let response = serde_json::json!({
+        "record_id": record_id
+    });
 
-    Err(StatusCode::INTERNAL_SERVER_ERROR)
+    Ok((Extension(write), Json(response)))
 }
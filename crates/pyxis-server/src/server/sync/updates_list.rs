@@ -1,19 +1,28 @@
 use crate::server::router::AWSConnectionState;
 use axum::{
     extract::{Query, State},
-    http::StatusCode,
     Extension, Json,
 };
+use pyxis_shared::payload::UpdateEncoding;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::database::{token_repository::Claims, updates_repository::UpdateRepository};
+use crate::{
+    database::{cursor, token_repository::Claims, updates_repository::UpdateRepository},
+    error::ServerError,
+};
 
 #[derive(Serialize, Deserialize)]
 pub struct UpdatesListQuery {
     pub snapshot_id: i64,
     pub file_uid: String,
     pub device_id: String,
+    pub limit: Option<i32>,
+    // next_cursor of the previous page
+    pub cursor: Option<String>,
+    // Newest encoding the reader knows. Older readers only know json.
+    #[serde(default)]
+    pub encoding: UpdateEncoding,
 }
 
 #[axum_macros::debug_handler]
@@ -21,32 +30,51 @@ pub async fn updates_list(
     Extension(claims): Extension<Claims>,
     State(connections): State<AWSConnectionState>,
     Query(request): Query<UpdatesListQuery>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ServerError> {
     let Claims {
         user,
         exp: _,
         iat: _,
     } = claims;
 
-    let updates_repository = UpdateRepository::new(connections.dynamo.connection.clone());
+    let updates_repository =
+        UpdateRepository::new(connections.dynamo.connection.clone(), &connections.config);
 
     let UpdatesListQuery {
         snapshot_id,
         file_uid,
         device_id,
+        limit,
+        cursor,
+        encoding,
     } = request;
 
-    let updates_response = updates_repository
-        .get_by_snapshot_id(user.user_id.to_string(), device_id, file_uid, snapshot_id)
-        .await;
+    let pk = format!("{}/{}", user.user_id, device_id);
+    let start_key = cursor
+        .as_deref()
+        .map(|cursor| cursor::decode(cursor, &pk))
+        .transpose()?;
 
-    if let Ok(updates) = updates_response {
-        let response = serde_json::json!({
-            "updates": updates
-        });
+    let mut page = updates_repository
+        .get_by_snapshot_id(
+            user.user_id.to_string(),
+            device_id,
+            file_uid,
+            snapshot_id,
+            cursor::page_size(limit),
+            start_key,
+        )
+        .await
+        .map_err(|e| e.context("Error while listing updates!"))?;
 
-        return Ok(Json(response));
+    for update in page.items.iter_mut() {
+        update
+            .transcode(encoding)
+            .map_err(|e| e.context(&format!("Error while transcoding update {}!", update.sk)))?;
     }
 
-    Err(StatusCode::INTERNAL_SERVER_ERROR)
+    Ok(Json(serde_json::json!({
+        "updates": page.items,
+        "next_cursor": page.next_cursor,
+    })))
 }
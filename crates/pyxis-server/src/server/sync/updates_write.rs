@@ -1,44 +1,93 @@
 use crate::server::router::AWSConnectionState;
 use axum::{extract::State, http::StatusCode, Extension, Json};
-use pyxis_shared::payload::UpdateWritePayload;
+use pyxis_shared::payload::{UpdateEncoding, UpdateWritePayload};
 use uuid::Uuid;
 
 use crate::database::{
+    change_repository::{Change, ChangeRepository, WriteResult},
     token_repository::Claims,
     updates_repository::{Update, UpdateRepository},
 };
+use crate::error::{ErrorCode, ServerError};
+use crate::server::middlewares::request_metrics::SyncWrite;
 
 #[axum_macros::debug_handler]
 pub async fn updates_write(
     Extension(claims): Extension<Claims>,
     State(connections): State<AWSConnectionState>,
     Json(update): Json<UpdateWritePayload>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<(Extension<SyncWrite>, StatusCode), ServerError> {
     let Claims {
         user,
         exp: _,
         iat: _,
     } = claims;
 
-    let update_repository = UpdateRepository::new(connections.dynamo.connection.clone());
+    let update_repository =
+        UpdateRepository::new(connections.dynamo.connection.clone(), &connections.config);
+    let change_repository =
+        ChangeRepository::new(connections.dynamo.connection.clone(), &connections.config);
+
+    update.content()?;
 
     let UpdateWritePayload {
         file_uid,
         snapshot_id,
         payload,
+        idempotency_key,
+        encoding,
+        version: _,
     } = update;
 
+    let write = SyncWrite {
+        source: String::from("updates"),
+        bytes: payload.len(),
+    };
+
     let pk = format!("{}/{}", user.user_id, user.device_id);
-    let sk = format!("{}/{}/{}", snapshot_id, file_uid, Uuid::new_v4());
+    // Updates sent without a key can't be told apart from new ones
+    let key = idempotency_key.unwrap_or_else(|| Uuid::new_v4().to_string());
+    let sk = format!("{}/{}/{}", snapshot_id, file_uid, key);
 
-    let update = Update { pk, sk, payload };
+    let change = Change {
+        seq: 0,
+        device_id: user.device_id.to_string(),
+        kind: String::from("update"),
+        source: String::from("updates"),
+        operation: String::from("insert"),
+        record_id: None,
+        update_id: Some(sk.clone()),
+        snapshot_id: Some(snapshot_id),
+        file_uid: Some(file_uid),
+        payload: payload.clone(),
+        encoding: (encoding != UpdateEncoding::Json).then(|| encoding.as_str().to_owned()),
+        blob: None,
+        timestamp: 0,
+    };
+
+    let update = Update {
+        pk,
+        sk,
+        payload,
+        encoding,
+    };
 
-    let write_response = update_repository.create(update).await;
+    let result = update_repository
+        .create(
+            update,
+            &change_repository,
+            &user.user_id.to_string(),
+            change,
+        )
+        .await
+        .map_err(|e| e.context("Error while writing updates!"))?;
 
-    if let Err(e) = write_response {
-        println!("Error while writing updates! {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    if result == WriteResult::Conflict {
+        return Err(ServerError::new(
+            ErrorCode::Conflict,
+            format!("Update key {} already holds another update!", key),
+        ));
     }
 
-    Ok(StatusCode::OK)
+    Ok((Extension(write), StatusCode::OK))
 }
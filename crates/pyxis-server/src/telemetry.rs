@@ -0,0 +1,47 @@
+use metrics::{describe_counter, describe_histogram, Unit};
+use metrics_exporter_prometheus::{BuildError, Matcher, PrometheusBuilder, PrometheusHandle};
+
+pub const HTTP_REQUESTS: &str = "pyxis_http_requests_total";
+pub const HTTP_REQUEST_DURATION: &str = "pyxis_http_request_duration_seconds";
+pub const AUTH_FAILURES: &str = "pyxis_auth_failures_total";
+pub const SYNC_WRITES: &str = "pyxis_sync_writes_total";
+pub const SYNC_WRITE_BYTES: &str = "pyxis_sync_write_bytes_total";
+pub const DYNAMODB_ERRORS: &str = "pyxis_dynamodb_errors_total";
+
+// Most requests are a single DynamoDB call, bootstrap pages and blob uploads take seconds
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/*
+ * Installs the process wide recorder. The metrics layer on the router records
+ * into it and the returned handle renders it for /metrics.
+ */
+pub fn install() -> Result<PrometheusHandle, BuildError> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(HTTP_REQUEST_DURATION.to_owned()),
+            LATENCY_BUCKETS,
+        )?
+        .install_recorder()?;
+
+    describe_counter!(HTTP_REQUESTS, "Requests served, by route and status");
+    describe_histogram!(
+        HTTP_REQUEST_DURATION,
+        Unit::Seconds,
+        "Time taken to answer a request, by route"
+    );
+    describe_counter!(
+        AUTH_FAILURES,
+        "Requests rejected for a missing, invalid or expired token or wrong credentials"
+    );
+    describe_counter!(SYNC_WRITES, "Records written through sync, by source");
+    describe_counter!(
+        SYNC_WRITE_BYTES,
+        Unit::Bytes,
+        "Payload bytes written through sync, by source"
+    );
+    describe_counter!(DYNAMODB_ERRORS, "Requests failed by a DynamoDB call");
+
+    Ok(handle)
+}
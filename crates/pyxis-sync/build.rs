@@ -4,7 +4,8 @@ use std::env;
 fn main() {
     dotenv().ok();
 
-    let base_url = env::var("APP_BASE_URL").unwrap();
-
-    println!("cargo:rustc-env=APP_BASE_URL={}", base_url);
+    // Only a default, the server can be changed in the network settings at runtime
+    if let Ok(base_url) = env::var("APP_BASE_URL") {
+        println!("cargo:rustc-env=APP_BASE_URL={}", base_url);
+    }
 }
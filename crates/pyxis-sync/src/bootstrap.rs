@@ -0,0 +1,521 @@
+mod apply;
+mod content;
+
+use std::{collections::HashMap, error::Error, str::FromStr};
+
+use pyxis_shared::{
+    entities::{
+        attachments::Attachment, bootstrap::Bootstrap, queue::Source, snapshots::Snapshots,
+        tracker::Tracker,
+    },
+    payload::UpdateEncoding,
+    utils::blob_store,
+};
+use rusqlite::Connection;
+use serde::{de::DeserializeOwned, Deserialize};
+use tauri_plugin_http::reqwest::Client;
+use uuid::Uuid;
+
+use crate::shutdown::Shutdown;
+use apply::apply_document;
+use content::merge_content;
+
+#[derive(Deserialize, Debug)]
+pub struct RemoteDocument {
+    pub sk: i64,
+    pub payload: String,
+    pub operation: String,
+    pub source: String,
+    pub file_uid: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DocumentList {
+    documents: Vec<RemoteDocument>,
+    next_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RemoteUpdate {
+    payload: String,
+    #[serde(default)]
+    encoding: UpdateEncoding,
+}
+
+#[derive(Deserialize)]
+struct UpdateList {
+    updates: Vec<RemoteUpdate>,
+    next_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DeviceList {
+    devices: Vec<String>,
+}
+
+// Records asked for per request when paging through a list
+const PAGE_SIZE: i32 = 500;
+
+// Records are applied one source at a time, each after the sources it points to
+pub const DOCUMENT_SOURCES: [Source; 4] = [
+    Source::Workspace,
+    Source::Directory,
+    Source::File,
+    Source::Attachment,
+];
+
+pub struct Remote<'a> {
+    pub client: &'a Client,
+    pub base_url: &'a str,
+    pub token: &'a str,
+}
+
+impl<'a> Remote<'a> {
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, Box<dyn Error>> {
+        Ok(self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .query(query)
+            .header("authorization", format!("Bearer {}", self.token))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    pub async fn devices(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let list: DeviceList = self.get("/auth/devices", &[]).await?;
+        Ok(list.devices)
+    }
+
+    // Every document a device sent, reading the list page by page
+    pub async fn documents(
+        &self,
+        device_id: &str,
+        is_snapshot: bool,
+    ) -> Result<Vec<RemoteDocument>, Box<dyn Error>> {
+        let mut documents = vec![];
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let mut query = vec![
+                ("record_id", String::from("0")),
+                ("is_snapshot", is_snapshot.to_string()),
+                ("device_id", device_id.to_owned()),
+                ("limit", PAGE_SIZE.to_string()),
+            ];
+            if let Some(cursor) = cursor.take() {
+                query.push(("cursor", cursor));
+            }
+
+            let list: DocumentList = self.get("/sync/document/list", &query).await?;
+            documents.extend(list.documents);
+
+            match list.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        documents.sort_by_key(|document| document.sk);
+        Ok(documents)
+    }
+
+    async fn updates(
+        &self,
+        device_id: &str,
+        file_uid: &str,
+        snapshot_id: i32,
+    ) -> Result<Vec<RemoteUpdate>, Box<dyn Error>> {
+        let mut updates = vec![];
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let mut query = vec![
+                ("snapshot_id", snapshot_id.to_string()),
+                ("file_uid", file_uid.to_owned()),
+                ("device_id", device_id.to_owned()),
+                ("limit", PAGE_SIZE.to_string()),
+                ("encoding", UpdateEncoding::Zstd.as_str().to_owned()),
+            ];
+            if let Some(cursor) = cursor.take() {
+                query.push(("cursor", cursor));
+            }
+
+            let list: UpdateList = self.get("/sync/update/list", &query).await?;
+            updates.extend(list.updates);
+
+            match list.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        Ok(updates)
+    }
+
+    async fn attachment(&self, hash: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(self
+            .client
+            .get(format!("{}/sync/attachment/{}", self.base_url, hash))
+            .header("authorization", format!("Bearer {}", self.token))
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?
+            .to_vec())
+    }
+}
+
+/*
+ * Downloads and applies everything every device of the user has sent, this one
+ * included, so that a new or wiped install ends up with the whole library.
+ * Workspaces, directories, files and attachments come first, in that order, then
+ * the content of every file. The trackers of the other devices are moved past
+ * what was applied so that regular sync does not fetch it again.
+ *
+ * Records that fail to apply hold the progress of their device and the rest is
+ * applied anyway. The bootstrap then fails, and requesting it again retries them.
+ *
+ * Returns false when the worker was asked to stop. The bootstrap stays running
+ * and resumes from its progress on the next start.
+ */
+pub async fn bootstrap(
+    conn: &Connection,
+    docs_conn: &Connection,
+    remote: &Remote<'_>,
+    device_id: Uuid,
+    user_id: Uuid,
+    shutdown: &Shutdown,
+) -> Result<bool, Box<dyn Error>> {
+    Bootstrap::set_status(conn, "running", None)?;
+
+    let devices = remote.devices().await?;
+    let mut documents: Vec<(String, Vec<RemoteDocument>)> = vec![];
+    for device in devices.iter() {
+        documents.push((device.clone(), remote.documents(device, false).await?));
+    }
+
+    let mut failures = 0;
+    for source in DOCUMENT_SOURCES.iter() {
+        if !apply_source(
+            conn,
+            docs_conn,
+            remote,
+            &documents,
+            source,
+            shutdown,
+            &mut failures,
+        )
+        .await?
+        {
+            return Ok(false);
+        }
+    }
+
+    let mut snapshots: Vec<(String, Vec<RemoteDocument>)> = vec![];
+    for device in devices.iter() {
+        let device_snapshots = remote.documents(device, true).await?;
+        if !apply_content(
+            conn,
+            docs_conn,
+            remote,
+            device,
+            &device_snapshots,
+            shutdown,
+            &mut failures,
+        )
+        .await?
+        {
+            return Ok(false);
+        }
+        snapshots.push((device.clone(), device_snapshots));
+    }
+
+    // Progress was held before each of them, so requesting the bootstrap again retries them
+    if failures > 0 {
+        return Err(format!("{} records could not be applied", failures).into());
+    }
+
+    for (device, device_documents) in documents.iter().chain(snapshots.iter()) {
+        let device = Uuid::from_str(device)?;
+        // The tracker of this device counts what it has sent
+        if device != device_id {
+            advance_tracker(conn, device, user_id, device_documents)?;
+        }
+    }
+
+    Bootstrap::set_status(conn, "finished", None)?;
+
+    Ok(true)
+}
+
+async fn apply_source(
+    conn: &Connection,
+    docs_conn: &Connection,
+    remote: &Remote<'_>,
+    documents: &[(String, Vec<RemoteDocument>)],
+    source: &Source,
+    shutdown: &Shutdown,
+    failures: &mut usize,
+) -> Result<bool, Box<dyn Error>> {
+    let source_name = source.to_string();
+    let mut pending: Vec<(&str, &RemoteDocument)> = vec![];
+
+    for (device, device_documents) in documents.iter() {
+        let progress = Bootstrap::get_progress(conn, device, source)?;
+        pending.extend(
+            device_documents
+                .iter()
+                .filter(|document| document.source == source_name && document.sk > progress)
+                .map(|document| (device.as_str(), document)),
+        );
+    }
+
+    /*
+     * A directory may sit in one created by another device, so directories from
+     * every device are applied parents first. Their progress is only saved once
+     * all of them are in.
+     */
+    let in_order = *source != Source::Directory;
+    if !in_order {
+        order_by_depth(&mut pending);
+    }
+
+    // First record of each device that failed. Progress of the device stays before it.
+    let mut failed: HashMap<&str, i64> = HashMap::new();
+    let is_held = |failed: &HashMap<&str, i64>, device: &str, sk: i64| {
+        failed.get(device).is_some_and(|failed_sk| sk >= *failed_sk)
+    };
+
+    for (device, document) in pending.iter() {
+        if shutdown.requested() {
+            return Ok(false);
+        }
+
+        let applied: Result<(), Box<dyn Error>> = if *source == Source::Attachment
+            && document.operation == "insert"
+            && !download_blob(docs_conn, remote, document).await?
+        {
+            Err("The attachment does not match its hash".into())
+        } else {
+            apply_document(docs_conn, document).map_err(|e| e.into())
+        };
+
+        if let Err(e) = applied {
+            eprintln!(
+                "[Bootstrap] Failed to apply {} record {} from {}! {}",
+                source_name, document.sk, device, e
+            );
+            let failed_sk = failed.entry(device).or_insert(document.sk);
+            *failed_sk = (*failed_sk).min(document.sk);
+            *failures += 1;
+            continue;
+        }
+
+        if in_order && !is_held(&failed, device, document.sk) {
+            Bootstrap::save_progress(conn, device, source, document.sk)?;
+        }
+    }
+
+    if !in_order {
+        for (device, document) in pending.iter() {
+            if !is_held(&failed, device, document.sk) {
+                Bootstrap::save_progress(conn, device, source, document.sk)?;
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+// Every change to a directory is applied at the depth it was first seen at
+fn order_by_depth(pending: &mut [(&str, &RemoteDocument)]) {
+    let mut depths: HashMap<String, usize> = HashMap::new();
+
+    for (_, document) in pending.iter() {
+        let record: serde_json::Value = match serde_json::from_str(&document.payload) {
+            Ok(record) => record,
+            Err(_) => continue,
+        };
+
+        if let (Some(uid), Some(path)) = (
+            record.get("uid").and_then(|uid| uid.as_str()),
+            record.get("path").and_then(|path| path.as_str()),
+        ) {
+            depths
+                .entry(uid.to_owned())
+                .or_insert(path.matches('/').count());
+        }
+    }
+
+    pending.sort_by_cached_key(|(device, document)| {
+        let depth = serde_json::from_str::<serde_json::Value>(&document.payload)
+            .ok()
+            .and_then(|record| {
+                record
+                    .get("uid")
+                    .and_then(|uid| uid.as_str())
+                    .and_then(|uid| depths.get(uid).copied())
+            })
+            // Deletes go after everything they could refer to
+            .unwrap_or(usize::MAX);
+
+        (depth, device.to_string(), document.sk)
+    });
+}
+
+// False when the server sent content that does not match the hash. Nothing is stored then.
+async fn download_blob(
+    docs_conn: &Connection,
+    remote: &Remote<'_>,
+    document: &RemoteDocument,
+) -> Result<bool, Box<dyn Error>> {
+    let attachment: Attachment = match serde_json::from_str(&document.payload) {
+        Ok(attachment) => attachment,
+        Err(_) => return Ok(true),
+    };
+
+    if Attachment::get_by_uid(docs_conn, &attachment.uid).is_ok()
+        || blob_store::read(&attachment.hash).is_ok()
+    {
+        return Ok(true);
+    }
+
+    let content = remote.attachment(&attachment.hash).await?;
+    if blob_store::hash(&content) != attachment.hash {
+        return Ok(false);
+    }
+
+    blob_store::write(&content)?;
+    Ok(true)
+}
+
+/*
+ * Only the latest snapshot a device sent for a file matters, along with the
+ * updates sent on top of it. Files are taken in the order their latest snapshot
+ * was sent, which is what progress is kept by.
+ *
+ * Progress stops at the first file that could not be applied in full. The files
+ * after it are still applied and the failure is counted.
+ */
+async fn apply_content(
+    conn: &Connection,
+    docs_conn: &Connection,
+    remote: &Remote<'_>,
+    device: &str,
+    snapshots: &[RemoteDocument],
+    shutdown: &Shutdown,
+    failures: &mut usize,
+) -> Result<bool, Box<dyn Error>> {
+    let progress = Bootstrap::get_progress(conn, device, &Source::Snapshot)?;
+
+    let mut latest: HashMap<&str, &RemoteDocument> = HashMap::new();
+    for document in snapshots.iter() {
+        if let Some(file_uid) = &document.file_uid {
+            latest.insert(file_uid, document);
+        }
+    }
+
+    let mut latest: Vec<(&str, &RemoteDocument)> = latest
+        .into_iter()
+        .filter(|(_, document)| document.sk > progress)
+        .collect();
+    latest.sort_by_key(|(_, document)| document.sk);
+
+    let mut held = false;
+    for (file_uid, document) in latest {
+        if shutdown.requested() {
+            return Ok(false);
+        }
+
+        let snapshot: Snapshots = match serde_json::from_str(&document.payload) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                eprintln!(
+                    "[Bootstrap] Skipping snapshot record {}. Failed to parse! {}",
+                    document.sk, e
+                );
+                if !held {
+                    Bootstrap::save_progress(conn, device, &Source::Snapshot, document.sk)?;
+                }
+                continue;
+            }
+        };
+
+        let mut complete = true;
+        let mut content = vec![snapshot.content];
+        for update in remote
+            .updates(device, file_uid, snapshot.snapshot_id)
+            .await?
+        {
+            match update.encoding.decode_content(&update.payload) {
+                Ok(update) => content.push(update),
+                Err(e) => {
+                    eprintln!(
+                        "[Bootstrap] Failed to decode an update of {} from {}! {}",
+                        file_uid, device, e
+                    );
+                    complete = false;
+                }
+            }
+        }
+
+        if let Err(e) = merge_content(docs_conn, file_uid, &content) {
+            eprintln!(
+                "[Bootstrap] Failed to merge content of {} from {}! {}",
+                file_uid, device, e
+            );
+            complete = false;
+        }
+
+        if !complete {
+            held = true;
+            *failures += 1;
+        } else if !held {
+            Bootstrap::save_progress(conn, device, &Source::Snapshot, document.sk)?;
+        }
+    }
+
+    Ok(true)
+}
+
+fn advance_tracker(
+    conn: &Connection,
+    device_id: Uuid,
+    user_id: Uuid,
+    documents: &[RemoteDocument],
+) -> Result<(), rusqlite::Error> {
+    let last = match documents.iter().max_by_key(|document| document.sk) {
+        Some(last) => last,
+        None => return Ok(()),
+    };
+    let source = match Source::from_str(&last.source) {
+        Ok(source) => source,
+        Err(_) => return Ok(()),
+    };
+
+    let sources = if source == Source::Snapshot {
+        vec![Source::Snapshot]
+    } else {
+        DOCUMENT_SOURCES.to_vec()
+    };
+
+    let tracked = match Tracker::get(conn, sources, device_id, user_id) {
+        Ok(record) => record.record_id,
+        Err(rusqlite::Error::QueryReturnedNoRows) => 0,
+        Err(e) => return Err(e),
+    };
+
+    if last.sk > tracked {
+        Tracker::new(None, device_id, source, last.sk, user_id, None).add(conn)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use pyxis_shared::{
+    entities::{
+        attachments::Attachment,
+        conflicts::{update_remote, Versioned},
+        directories::Directory,
+        files::Files,
+        queue::Source,
+        tombstones::Tombstone,
+        workspaces::Workspace,
+    },
+    payload::{RecordMessage, SyncMessage},
+    utils::blob_store,
+};
+use rusqlite::{Connection, Error};
+
+use super::RemoteDocument;
+
+/*
+ * Applies a document sent by any device the same way the app applies the ones
+ * it receives: rows are written as synced so that they are not sent back, edits
+ * are merged field by field and deletes are settled against edits by time.
+ */
+pub fn apply_document(conn: &Connection, document: &RemoteDocument) -> Result<(), Error> {
+    let message = match SyncMessage::parse(&document.source, &document.operation, &document.payload)
+    {
+        Ok(message) => message,
+        Err(e) => {
+            eprintln!("[Bootstrap] Skipping record {}! {}", document.sk, e);
+            return Ok(());
+        }
+    };
+
+    match message {
+        SyncMessage::Workspace(RecordMessage::Delete(tombstone))
+        | SyncMessage::Directory(RecordMessage::Delete(tombstone))
+        | SyncMessage::File(RecordMessage::Delete(tombstone))
+        | SyncMessage::Attachment(RecordMessage::Delete(tombstone)) => {
+            apply_tombstone(conn, tombstone)
+        }
+        SyncMessage::Workspace(RecordMessage::Insert(workspace)) => {
+            apply_workspace(conn, workspace, true)
+        }
+        SyncMessage::Workspace(RecordMessage::Update(workspace)) => {
+            apply_workspace(conn, workspace, false)
+        }
+        SyncMessage::Directory(RecordMessage::Insert(dir)) => apply_directory(conn, dir, true),
+        SyncMessage::Directory(RecordMessage::Update(dir)) => apply_directory(conn, dir, false),
+        SyncMessage::File(RecordMessage::Insert(file)) => apply_file(conn, file, true),
+        SyncMessage::File(RecordMessage::Update(file)) => apply_file(conn, file, false),
+        // Attachments are immutable
+        SyncMessage::Attachment(RecordMessage::Insert(attachment)) => {
+            insert_attachment(conn, attachment)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn apply_workspace(
+    conn: &Connection,
+    mut workspace: Workspace,
+    inserted: bool,
+) -> Result<(), Error> {
+    workspace.synced = Some(true);
+    workspace.selected = false;
+
+    let duplicate = Workspace::get_by_name(conn, workspace.name.clone()).is_ok();
+    apply_versioned(conn, workspace, inserted, duplicate, Workspace::create)
+}
+
+fn apply_directory(conn: &Connection, mut dir: Directory, inserted: bool) -> Result<(), Error> {
+    dir.synced = Some(true);
+
+    let duplicate =
+        Directory::get_by_path(conn, dir.path.clone(), dir.workspace_uid.clone()).is_ok();
+    apply_versioned(conn, dir, inserted, duplicate, Directory::create)
+}
+
+fn apply_file(conn: &Connection, mut file: Files, inserted: bool) -> Result<(), Error> {
+    file.synced = Some(true);
+
+    let duplicate = Files::get_by_path(conn, file.path.clone(), file.workspace_uid.clone()).is_ok();
+    apply_versioned(conn, file, inserted, duplicate, Files::create)
+}
+
+fn is_admitted(
+    conn: &Connection,
+    source: Source,
+    uid: &str,
+    edited_at: i64,
+) -> Result<bool, Error> {
+    let admitted = Tombstone::admit(conn, &source, uid, edited_at)?;
+    if !admitted {
+        println!(
+            "[Bootstrap] Skipping {} {}. Deleted on this device.",
+            source.to_string(),
+            uid
+        );
+    }
+
+    Ok(admitted)
+}
+
+/*
+ * An insert is skipped when the record, or one at the same place, is already
+ * here. An edit to a record that is gone brings it back, unless it was deleted
+ * after the edit.
+ */
+fn apply_versioned<T: Versioned>(
+    conn: &Connection,
+    record: T,
+    inserted: bool,
+    duplicate: bool,
+    create: fn(&T, &Connection) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let exists = match T::load(conn, record.uid()) {
+        Ok(_) => true,
+        Err(Error::QueryReturnedNoRows) => false,
+        Err(e) => return Err(e),
+    };
+
+    if inserted && (exists || duplicate) {
+        return Ok(());
+    }
+
+    let edited_at = Tombstone::last_edit(record.field_clocks());
+    if !is_admitted(conn, T::SOURCE, record.uid(), edited_at)? {
+        return Ok(());
+    }
+
+    if exists {
+        update_remote(conn, record)?;
+    } else {
+        create(&record, conn)?;
+    }
+
+    Ok(())
+}
+
+// The blob was downloaded before the row is written
+fn insert_attachment(conn: &Connection, mut attachment: Attachment) -> Result<(), Error> {
+    match Attachment::get_by_uid(conn, &attachment.uid) {
+        Ok(_) => return Ok(()),
+        Err(Error::QueryReturnedNoRows) => {}
+        Err(e) => return Err(e),
+    }
+
+    let created_at = Tombstone::millis(&attachment.created_at);
+    if !is_admitted(conn, Source::Attachment, &attachment.uid, created_at)? {
+        return Ok(());
+    }
+
+    attachment.id = None;
+    attachment.synced = Some(true);
+    attachment.create(conn)
+}
+
+fn apply_tombstone(conn: &Connection, tombstone: Tombstone) -> Result<(), Error> {
+    let attachment = match tombstone.source {
+        Source::Attachment => Attachment::get_by_uid(conn, &tombstone.uid).ok(),
+        _ => None,
+    };
+
+    if let (true, Some(attachment)) = (tombstone.apply_remote(conn)?, attachment) {
+        // Blobs are content-addressed and may be shared by several attachments
+        if !Attachment::is_hash_referenced(conn, &attachment.hash)? {
+            if let Err(e) = blob_store::remove(&attachment.hash) {
+                eprintln!("[Bootstrap] Failed to remove blob! {}", e);
+            }
+        }
+    }
+
+    Ok(())
+}
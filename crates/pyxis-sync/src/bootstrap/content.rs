@@ -0,0 +1,75 @@
+use std::error::Error;
+
+use loro::{ExportMode, LoroDoc};
+use pyxis_shared::entities::{files::Files, snapshots::Snapshots, updates::Updates};
+use rusqlite::Connection;
+
+/*
+ * Folds content from another device into the local copy of a file. What the
+ * other device adds is stored as a single local update on top of the current
+ * snapshot, or as the first snapshot when the file has no content here yet.
+ * Returns whether anything changed.
+ */
+pub fn merge_content(
+    conn: &Connection,
+    file_uid: &str,
+    remote: &[Vec<u8>],
+) -> Result<bool, Box<dyn Error>> {
+    match Files::get_by_uid(conn, file_uid) {
+        Ok(_) => {}
+        // Deleted here, or never made it
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(false),
+        Err(e) => return Err(e.into()),
+    }
+
+    let local = match Snapshots::get(file_uid, conn) {
+        Ok(snapshot) => Some(snapshot),
+        Err(rusqlite::Error::QueryReturnedNoRows) => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    let doc = LoroDoc::new();
+    if let Some(snapshot) = &local {
+        if !snapshot.content.is_empty() {
+            doc.import(&snapshot.content)?;
+        }
+
+        let updates = Updates::get(file_uid, snapshot.snapshot_id as i64, conn)?;
+        if !updates.is_empty() {
+            doc.import_batch(&updates)?;
+        }
+    }
+
+    let before = doc.oplog_vv();
+    let remote: Vec<Vec<u8>> = remote
+        .iter()
+        .filter(|content| !content.is_empty())
+        .cloned()
+        .collect();
+    if !remote.is_empty() {
+        doc.import_batch(&remote)?;
+    }
+
+    if doc.oplog_vv() == before {
+        return Ok(false);
+    }
+
+    match local {
+        Some(snapshot) => Updates::new(
+            file_uid.to_owned(),
+            doc.export(ExportMode::updates(&before))?,
+            None,
+            snapshot.snapshot_id as i64,
+        )
+        .insert(conn)?,
+        None => Snapshots::new(
+            file_uid.to_owned(),
+            doc.export(ExportMode::Snapshot)?,
+            None,
+            1,
+        )
+        .update(conn)?,
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,62 @@
+use clap::{Parser, Subcommand};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "pyxis-sync",
+    version,
+    about = "Syncs Pyxis notes with the server"
+)]
+pub struct Cli {
+    /// Exit when this process exits. Set by the app when it runs the worker as a sidecar.
+    #[arg(long)]
+    pub pid: Option<i32>,
+
+    /// Send everything in the queue, then exit. Exits with an error if anything is left.
+    #[arg(long)]
+    pub once: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Show the signed in user, the queue and what was synced so far
+    Status,
+    /// Inspect and repair the queue of changes waiting to be sent
+    Queue {
+        #[command(subcommand)]
+        command: QueueCommand,
+    },
+    /// Manage the records of what was sent and received
+    Tracker {
+        #[command(subcommand)]
+        command: TrackerCommand,
+    },
+    /// Compare the records held here with what the server has for all devices
+    Verify,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum QueueCommand {
+    /// List the latest elements
+    List {
+        /// Only list elements with this status (init, picked or failed)
+        #[arg(long)]
+        status: Option<String>,
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+    },
+    /// Print an element with its payload
+    Show { id: i64 },
+    /// Send an element again
+    Retry { id: i64 },
+    /// Remove an element without sending it
+    Drop { id: i64 },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TrackerCommand {
+    /// Forget what this device has sent, so that the whole queue is uploaded again
+    Reset,
+}
@@ -0,0 +1,185 @@
+use loro::{ExportMode, LoroDoc, LoroError};
+use pyxis_shared::entities::{
+    queue::{ListenerQueue, Source},
+    snapshots::Snapshots,
+    updates::Updates,
+};
+use rusqlite::{Connection, Error};
+
+/*
+ * Enqueues a change, folding it into the pending element for the same record
+ * when there is one that has not been picked up yet. Only the latest state of
+ * a record is sent, so three edits to a title go out as a single update and a
+ * record inserted and deleted before it was sent never goes out at all. File
+ * content only depends on the file, so it never holds a record change back.
+ *
+ * Dropping both halves of an insert and a delete lowers the last outbox id in
+ * the queue. If the sidecar dies before the outbox is trimmed, the delete is
+ * moved again and sent for a record the server never saw, which is harmless.
+ */
+pub fn enqueue(
+    docs_conn: &Connection,
+    config_conn: &Connection,
+    mut entry: ListenerQueue,
+) -> Result<(), Error> {
+    let pending = match pending_entry(config_conn, &entry)? {
+        Some(pending) if pending.status == "init" => pending,
+        _ => return entry.enqueue(config_conn),
+    };
+
+    if entry.source == Source::Update {
+        return match merge_updates(docs_conn, &pending, &entry) {
+            Some(payload) => {
+                entry.id = pending.id;
+                entry.operation = pending.operation;
+                entry.payload = payload;
+                entry.coalesce(config_conn)
+            }
+            None => entry.enqueue(config_conn),
+        };
+    }
+
+    /*
+     * A folded change goes out in the place of the pending one. Anything queued
+     * after it could be something the change now refers to, such as the
+     * directory a file was moved into, so the change waits its turn instead.
+     */
+    if pending.has_records_after(config_conn)? {
+        return entry.enqueue(config_conn);
+    }
+
+    entry.id = pending.id;
+    match (pending.operation.as_str(), entry.operation.as_str()) {
+        ("insert", "delete") => {
+            // The content of a file the server never saw has nowhere to go
+            if entry.source == Source::File {
+                if let Some(file_uid) = &entry.record_uid {
+                    ListenerQueue::remove_file_content(config_conn, file_uid)?;
+                }
+            }
+            return pending.remove(config_conn);
+        }
+        // Still unknown to the server
+        ("insert", _) => entry.operation = pending.operation,
+        // The delete never left, so the server still has the record
+        ("delete", "insert") => entry.operation = String::from("update"),
+        _ => {}
+    }
+
+    entry.coalesce(config_conn)
+}
+
+fn pending_entry(
+    config_conn: &Connection,
+    entry: &ListenerQueue,
+) -> Result<Option<ListenerQueue>, Error> {
+    match (
+        &entry.source,
+        &entry.record_uid,
+        &entry.file_uid,
+        entry.snapshot_id,
+    ) {
+        (Source::Update, _, Some(file_uid), Some(snapshot_id)) => {
+            ListenerQueue::last_for_snapshot(config_conn, file_uid, snapshot_id)
+        }
+        (Source::Update, _, _, _) => Ok(None),
+        (source, Some(record_uid), _, _) => {
+            ListenerQueue::last_for_record(config_conn, source, record_uid)
+        }
+        _ => Ok(None),
+    }
+}
+
+/*
+ * Merges a new Loro update into the pending one for the same file and snapshot.
+ * Both are imported on top of the document as it was before the pending update,
+ * and everything after that version is exported as one blob. Returns None when
+ * the updates cannot be merged, in which case the new one is sent on its own.
+ */
+fn merge_updates(
+    docs_conn: &Connection,
+    pending: &ListenerQueue,
+    entry: &ListenerQueue,
+) -> Option<String> {
+    let (pending_update, update) = match (
+        serde_json::from_str::<Updates>(&pending.payload),
+        serde_json::from_str::<Updates>(&entry.payload),
+    ) {
+        (Ok(pending_update), Ok(update)) => (pending_update, update),
+        _ => return None,
+    };
+
+    let snapshot = match Snapshots::get(&update.file_uid, docs_conn) {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            eprintln!(
+                "[Queue] Failed to read the snapshot to merge updates! {}",
+                e
+            );
+            return None;
+        }
+    };
+
+    // Updates to an older snapshot cannot be placed on top of the current one
+    if snapshot.snapshot_id as i64 != update.snapshot_id {
+        return None;
+    }
+
+    let earlier = match Updates::list_before(
+        docs_conn,
+        &update.file_uid,
+        update.snapshot_id,
+        pending_update.id.unwrap_or(0),
+    ) {
+        Ok(earlier) => earlier,
+        Err(e) => {
+            eprintln!("[Queue] Failed to read earlier updates! {}", e);
+            return None;
+        }
+    };
+
+    let content = match merge_content(
+        &snapshot.content,
+        &earlier,
+        &[pending_update.content, update.content],
+    ) {
+        Ok(Some(content)) => content,
+        Ok(None) => return None,
+        Err(e) => {
+            eprintln!("[Queue] Failed to merge updates! {}", e);
+            return None;
+        }
+    };
+
+    let merged = Updates {
+        id: pending_update.id,
+        content,
+        file_uid: update.file_uid,
+        updated_at: update.updated_at,
+        snapshot_id: update.snapshot_id,
+    };
+
+    serde_json::to_string(&merged).ok()
+}
+
+fn merge_content(
+    snapshot: &[u8],
+    earlier: &[Vec<u8>],
+    updates: &[Vec<u8>],
+) -> Result<Option<Vec<u8>>, LoroError> {
+    let doc = LoroDoc::new();
+    doc.import(snapshot)?;
+
+    if !earlier.is_empty() && doc.import_batch(earlier)?.pending.is_some() {
+        return Ok(None);
+    }
+
+    let before = doc.oplog_vv();
+
+    // Missing dependencies would leave part of the updates out of the export
+    if doc.import_batch(updates)?.pending.is_some() {
+        return Ok(None);
+    }
+
+    Ok(Some(doc.export(ExportMode::updates(&before))?))
+}
@@ -0,0 +1,35 @@
+pub mod queue;
+pub mod status;
+pub mod tracker;
+pub mod verify;
+
+use std::{error::Error, str::FromStr};
+
+use pyxis_shared::entities::config::{ConfigEntry, Configuration};
+use rusqlite::Connection;
+use uuid::Uuid;
+
+pub type CommandResult = Result<bool, Box<dyn Error>>;
+
+pub struct SignedInUser {
+    pub config: Configuration,
+    pub device_id: Uuid,
+    pub user_id: Uuid,
+}
+
+pub fn get_signed_in_user(conn: &Connection) -> Result<SignedInUser, Box<dyn Error>> {
+    let config = match ConfigEntry::get_logged_in_user(conn) {
+        Ok(config) => config,
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Err("Not signed in!".into()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let device_id = config.device_id.ok_or("No device id configured!")?;
+    let user_id = Uuid::from_str(&config.user_id)?;
+
+    Ok(SignedInUser {
+        config,
+        device_id,
+        user_id,
+    })
+}
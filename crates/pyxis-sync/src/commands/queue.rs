@@ -0,0 +1,88 @@
+use pyxis_shared::entities::{queue::ListenerQueue, tracker::Tracker};
+use rusqlite::Connection;
+
+use super::{get_signed_in_user, CommandResult};
+
+fn get_entry(conn: &Connection, id: i64) -> Result<ListenerQueue, Box<dyn std::error::Error>> {
+    match ListenerQueue::get(conn, id) {
+        Ok(entry) => Ok(entry),
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            Err(format!("No element with id {} in the queue!", id).into())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn list(conn: &Connection, status: Option<&str>, limit: i64) -> CommandResult {
+    let entries = ListenerQueue::list(conn, status, limit)?;
+
+    println!(
+        "{:>8}  {:<8} {:<12} {:<8} {:<36} {:>8}",
+        "ID", "STATUS", "SOURCE", "OP", "RECORD", "BYTES"
+    );
+    for entry in entries {
+        println!(
+            "{:>8}  {:<8} {:<12} {:<8} {:<36} {:>8}",
+            entry.id.unwrap_or_default(),
+            entry.status,
+            entry.source.to_string(),
+            entry.operation,
+            entry
+                .record_uid
+                .as_deref()
+                .or(entry.file_uid.as_deref())
+                .unwrap_or("-"),
+            entry.payload.len()
+        );
+    }
+
+    Ok(true)
+}
+
+pub fn show(conn: &Connection, id: i64) -> CommandResult {
+    let entry = get_entry(conn, id)?;
+
+    println!("{}", serde_json::to_string_pretty(&entry)?);
+
+    Ok(true)
+}
+
+/*
+ * An element that was not sent yet goes back to waiting in its place. One that
+ * was already sent is queued again at the end, since the worker only looks at
+ * elements after the last one it sent.
+ */
+pub fn retry(conn: &Connection, id: i64) -> CommandResult {
+    let user = get_signed_in_user(conn)?;
+    let entry = get_entry(conn, id)?;
+    let last_sent = Tracker::get_last_queue_entry_id(conn, user.device_id, user.user_id)
+        .unwrap_or_default()
+        .unwrap_or_default();
+
+    if id > last_sent {
+        entry.reset(conn)?;
+        println!("Element {} will be sent again.", id);
+    } else {
+        let transaction = conn.unchecked_transaction()?;
+        entry.enqueue(&transaction)?;
+        entry.remove(&transaction)?;
+        transaction.commit()?;
+        println!("Element {} was already sent. Queued it again.", id);
+    }
+
+    Ok(true)
+}
+
+pub fn drop(conn: &Connection, id: i64) -> CommandResult {
+    let entry = get_entry(conn, id)?;
+    entry.remove(conn)?;
+
+    println!(
+        "Dropped element {} ({} {}). It will not be sent.",
+        id,
+        entry.operation,
+        entry.source.to_string()
+    );
+
+    Ok(true)
+}
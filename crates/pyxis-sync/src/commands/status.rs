@@ -0,0 +1,58 @@
+use pyxis_shared::entities::{network::NetworkSettings, queue::ListenerQueue, tracker::Tracker};
+use rusqlite::Connection;
+
+use super::{get_signed_in_user, CommandResult};
+
+pub fn status(conn: &Connection) -> CommandResult {
+    let user = get_signed_in_user(conn)?;
+    let settings = NetworkSettings::get(conn)?;
+
+    println!(
+        "User:    {} ({})",
+        user.config.username.as_deref().unwrap_or("-"),
+        user.user_id
+    );
+    println!("Device:  {}", user.device_id);
+    println!(
+        "Server:  {}",
+        settings
+            .server_url
+            .as_deref()
+            .or(option_env!("APP_BASE_URL"))
+            .unwrap_or("-")
+    );
+
+    println!("\nQueue");
+    let counts = ListenerQueue::count_by_status(conn)?;
+    if counts.is_empty() {
+        println!("  empty");
+    }
+    for (status, count) in counts {
+        println!("  {:<10} {}", status, count);
+    }
+
+    let sent = Tracker::get_last_queue_entry_id(conn, user.device_id, user.user_id).ok();
+    println!(
+        "  last sent  {}",
+        sent.flatten()
+            .map_or(String::from("-"), |id| id.to_string())
+    );
+
+    println!("\nTracker");
+    for record in Tracker::list(conn, user.user_id)? {
+        let device = if record.device_id == user.device_id {
+            String::from("this device")
+        } else {
+            record.device_id.to_string()
+        };
+
+        println!(
+            "  {:<36} {:<12} record {}",
+            device,
+            record.source.to_string(),
+            record.record_id
+        );
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,21 @@
+use pyxis_shared::entities::{queue::ListenerQueue, tracker::Tracker};
+use rusqlite::Connection;
+
+use super::{get_signed_in_user, CommandResult};
+
+pub fn reset(conn: &Connection) -> CommandResult {
+    let user = get_signed_in_user(conn)?;
+
+    // Under their old keys the server would answer with the first result and store nothing
+    let transaction = conn.unchecked_transaction()?;
+    let removed = Tracker::reset(&transaction, user.device_id, user.user_id)?;
+    ListenerQueue::renew_idempotency_keys(&transaction)?;
+    transaction.commit()?;
+
+    println!(
+        "Removed {} tracker records. The whole queue is sent again on the next run.",
+        removed
+    );
+
+    Ok(true)
+}
@@ -0,0 +1,105 @@
+use std::collections::{BTreeMap, HashMap};
+
+use pyxis_shared::{
+    database::Database,
+    entities::{network::NetworkSettings, queue::ListenerQueue},
+};
+use rusqlite::Connection;
+
+use super::{get_signed_in_user, CommandResult};
+use crate::{
+    bootstrap::{Remote, DOCUMENT_SOURCES},
+    network::SyncClient,
+};
+
+/*
+ * Replays the documents every device sent and keeps the records that were not
+ * deleted, by uid. That is what this device should hold once it is in sync.
+ */
+async fn server_counts(
+    remote: &Remote<'_>,
+) -> Result<BTreeMap<String, usize>, Box<dyn std::error::Error>> {
+    let mut alive: HashMap<(String, String), bool> = HashMap::new();
+
+    for device in remote.devices().await? {
+        for document in remote.documents(&device, false).await? {
+            let uid = serde_json::from_str::<serde_json::Value>(&document.payload)
+                .ok()
+                .and_then(|payload| payload.get("uid")?.as_str().map(str::to_owned));
+
+            if let Some(uid) = uid {
+                // A delete is final, whatever order the devices sent things in
+                let is_deleted = document.operation == "delete";
+                alive
+                    .entry((document.source, uid))
+                    .and_modify(|alive| *alive &= !is_deleted)
+                    .or_insert(!is_deleted);
+            }
+        }
+    }
+
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for ((source, _), _) in alive.iter().filter(|(_, alive)| **alive) {
+        *counts.entry(source.clone()).or_default() += 1;
+    }
+
+    Ok(counts)
+}
+
+/*
+ * Compares the records of every kind held here with the ones the server holds
+ * for all devices of the user. Changes still in the queue are shown alongside,
+ * since they account for records the server does not have yet.
+ */
+pub async fn verify(conn: &Connection, docs_db: &Database) -> CommandResult {
+    let user = get_signed_in_user(conn)?;
+    let token = user.config.user_token.clone().ok_or("Not signed in!")?;
+    let sync_client = SyncClient::build(NetworkSettings::get(conn)?)?;
+    let remote = Remote {
+        client: &sync_client.client,
+        base_url: &sync_client.base_url,
+        token: &token,
+    };
+
+    let on_server = server_counts(&remote).await?;
+    // Picked elements have gone out, failed ones were refused
+    let unsent: i64 = ListenerQueue::count_by_status(conn)?
+        .into_iter()
+        .filter(|(status, _)| status != "picked")
+        .map(|(_, count)| count)
+        .sum();
+
+    let docs_conn = docs_db.get_connection();
+    let mut matches = true;
+
+    println!("{:<12} {:>8} {:>8}", "", "LOCAL", "SERVER");
+    for source in DOCUMENT_SOURCES.iter() {
+        let source = source.to_string();
+        let local: usize =
+            docs_conn.query_row(&format!("SELECT COUNT(*) FROM {}", source), [], |row| {
+                row.get(0)
+            })?;
+        let server = on_server.get(&source).copied().unwrap_or(0);
+
+        println!(
+            "{:<12} {:>8} {:>8}{}",
+            source,
+            local,
+            server,
+            if local == server { "" } else { "  differs" }
+        );
+        matches &= local == server;
+    }
+
+    if !matches {
+        if unsent > 0 {
+            println!("\n{} changes are still in the queue or failed.", unsent);
+        }
+        println!(
+            "Records missing on the server are sent again after `pyxis-sync tracker reset`. \
+            Records missing here come back with a bootstrap."
+        );
+    }
+
+    Ok(matches)
+}
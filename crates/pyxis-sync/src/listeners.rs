@@ -0,0 +1,79 @@
+mod attachments;
+mod directories;
+mod files;
+mod listener;
+mod snapshots;
+mod updates;
+mod workspaces;
+
+use std::collections::HashMap;
+
+use attachments::AttachmentsListener;
+use directories::DirectoryListener;
+use files::FilesListener;
+use listener::Listener;
+use pyxis_shared::entities::{
+    outbox::OutboxEntry,
+    queue::{ListenerQueue, Source},
+};
+use rusqlite::{Connection, Error};
+use snapshots::SnapshotsListener;
+use updates::UpdatesListener;
+use workspaces::WorkspacesListener;
+
+pub type ListenerMap = HashMap<String, Box<dyn Listener>>;
+
+pub fn create_listeners() -> ListenerMap {
+    let mut listeners: ListenerMap = HashMap::new();
+
+    listeners.insert(Source::Update.to_string(), Box::new(UpdatesListener));
+    listeners.insert(Source::Snapshot.to_string(), Box::new(SnapshotsListener));
+    listeners.insert(Source::Workspace.to_string(), Box::new(WorkspacesListener));
+    listeners.insert(Source::File.to_string(), Box::new(FilesListener));
+    listeners.insert(Source::Directory.to_string(), Box::new(DirectoryListener));
+    listeners.insert(
+        Source::Attachment.to_string(),
+        Box::new(AttachmentsListener),
+    );
+
+    listeners
+}
+
+/*
+ * Changes recorded for the same row since the last move are sent once, as the
+ * row reads now. An insert followed by a delete never reached the server, so
+ * nothing is sent for it.
+ */
+pub fn to_queue_entry(
+    listener: &dyn Listener,
+    connection: &Connection,
+    entries: &[&OutboxEntry],
+) -> Result<Option<ListenerQueue>, Error> {
+    let (first, last) = match (entries.first(), entries.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Ok(None),
+    };
+
+    let result = match (first.operation.as_str(), last.operation.as_str()) {
+        ("insert", "delete") => Ok(None),
+        (_, "delete") => listener.delete(connection, last),
+        ("insert", _) => listener.insert(connection, last),
+        _ => listener.update(connection, last),
+    };
+
+    match result {
+        Ok(Some(mut queue_entry)) => {
+            queue_entry.outbox_id = Some(last.id);
+            queue_entry.record_uid = match queue_entry.source {
+                // A file has a single snapshot
+                Source::Snapshot => queue_entry.file_uid.clone(),
+                _ => last.uid.clone(),
+            };
+            Ok(Some(queue_entry))
+        }
+        Ok(None) => Ok(None),
+        // Snapshots and updates go away with their file without an outbox entry
+        Err(Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
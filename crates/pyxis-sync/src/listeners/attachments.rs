@@ -0,0 +1,31 @@
+use super::listener::Listener;
+use pyxis_shared::entities::{attachments::Attachment, outbox::OutboxEntry, queue::ListenerQueue};
+use pyxis_shared::payload::{RecordMessage, SyncMessage};
+use rusqlite::{Connection, Error};
+
+pub struct AttachmentsListener;
+
+impl Listener for AttachmentsListener {
+    fn insert(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error> {
+        let attachment = Attachment::get(connection, entry.row_id)?;
+
+        self.message_entry(SyncMessage::Attachment(RecordMessage::Insert(attachment)))
+    }
+
+    // Attachments are immutable. A changed file is a new attachment.
+    fn update(&self, _: &Connection, _: &OutboxEntry) -> Result<Option<ListenerQueue>, Error> {
+        Ok(None)
+    }
+
+    fn delete(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error> {
+        self.tombstone_entry(connection, entry)
+    }
+}
@@ -0,0 +1,37 @@
+use rusqlite::{Connection, Error};
+
+use super::listener::Listener;
+use pyxis_shared::entities::{directories::Directory, outbox::OutboxEntry, queue::ListenerQueue};
+use pyxis_shared::payload::{RecordMessage, SyncMessage};
+
+pub struct DirectoryListener;
+
+impl Listener for DirectoryListener {
+    fn insert(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error> {
+        let dir = Directory::get(connection, entry.row_id)?;
+
+        self.message_entry(SyncMessage::Directory(RecordMessage::Insert(dir)))
+    }
+
+    fn update(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error> {
+        let dir = Directory::get(connection, entry.row_id)?;
+
+        self.message_entry(SyncMessage::Directory(RecordMessage::Update(dir)))
+    }
+
+    fn delete(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error> {
+        self.tombstone_entry(connection, entry)
+    }
+}
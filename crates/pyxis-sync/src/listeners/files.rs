@@ -0,0 +1,36 @@
+use super::listener::Listener;
+use pyxis_shared::entities::{files::Files, outbox::OutboxEntry, queue::ListenerQueue};
+use pyxis_shared::payload::{RecordMessage, SyncMessage};
+use rusqlite::{Connection, Error};
+
+pub struct FilesListener;
+
+impl Listener for FilesListener {
+    fn insert(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error> {
+        let file = Files::get(connection, entry.row_id)?;
+
+        self.message_entry(SyncMessage::File(RecordMessage::Insert(file)))
+    }
+
+    fn update(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error> {
+        let file = Files::get(connection, entry.row_id)?;
+
+        self.message_entry(SyncMessage::File(RecordMessage::Update(file)))
+    }
+
+    fn delete(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error> {
+        self.tombstone_entry(connection, entry)
+    }
+}
@@ -0,0 +1,65 @@
+use pyxis_shared::{
+    entities::{
+        outbox::OutboxEntry,
+        queue::{ListenerQueue, Source},
+        tombstones::Tombstone,
+    },
+    payload::{RecordMessage, SyncMessage},
+};
+use rusqlite::{Connection, Error};
+
+pub trait Listener {
+    fn message_entry(&self, message: SyncMessage) -> Result<Option<ListenerQueue>, Error> {
+        let payload = message
+            .to_payload()
+            .map_err(|e| Error::ToSqlConversionFailure(Box::new(e)))?;
+
+        Ok(Some(ListenerQueue::new(
+            None,
+            String::from("init"),
+            message.source().to_string(),
+            message.operation().to_owned(),
+            payload,
+            message.file_uid(),
+            message.snapshot_id(),
+        )))
+    }
+    // Deletes are sent as the tombstone the trigger left behind
+    fn tombstone_entry(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error> {
+        let uid = match &entry.uid {
+            Some(uid) => uid,
+            None => return Ok(None),
+        };
+
+        let tombstone = Tombstone::get(connection, &entry.source, uid)?;
+        let message = match entry.source {
+            Source::Workspace => SyncMessage::Workspace(RecordMessage::Delete(tombstone)),
+            Source::Directory => SyncMessage::Directory(RecordMessage::Delete(tombstone)),
+            Source::File => SyncMessage::File(RecordMessage::Delete(tombstone)),
+            Source::Attachment => SyncMessage::Attachment(RecordMessage::Delete(tombstone)),
+            // Go away with their file
+            Source::Snapshot | Source::Update => return Ok(None),
+        };
+
+        self.message_entry(message)
+    }
+    fn insert(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error>;
+    fn update(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error>;
+    fn delete(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error>;
+}
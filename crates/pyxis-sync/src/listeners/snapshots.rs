@@ -0,0 +1,33 @@
+use super::listener::Listener;
+use pyxis_shared::entities::{outbox::OutboxEntry, queue::ListenerQueue, snapshots::Snapshots};
+use pyxis_shared::payload::{RecordMessage, SyncMessage};
+use rusqlite::{Connection, Error};
+
+pub struct SnapshotsListener;
+
+impl Listener for SnapshotsListener {
+    fn insert(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error> {
+        let snapshot = Snapshots::get_by_id(connection, entry.row_id)?;
+
+        self.message_entry(SyncMessage::Snapshot(RecordMessage::Insert(snapshot)))
+    }
+
+    fn update(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error> {
+        let snapshot = Snapshots::get_by_id(connection, entry.row_id)?;
+
+        self.message_entry(SyncMessage::Snapshot(RecordMessage::Update(snapshot)))
+    }
+
+    // A snapshot only goes away with its file, whose tombstone covers it
+    fn delete(&self, _: &Connection, _: &OutboxEntry) -> Result<Option<ListenerQueue>, Error> {
+        Ok(None)
+    }
+}
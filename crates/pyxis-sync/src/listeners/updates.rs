@@ -0,0 +1,27 @@
+use super::listener::Listener;
+use pyxis_shared::entities::{outbox::OutboxEntry, queue::ListenerQueue, updates::Updates};
+use pyxis_shared::payload::SyncMessage;
+use rusqlite::{Connection, Error};
+
+pub struct UpdatesListener;
+
+impl Listener for UpdatesListener {
+    fn insert(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error> {
+        let update = Updates::get_by_id(entry.row_id, connection)?;
+
+        self.message_entry(SyncMessage::Update(update))
+    }
+
+    fn update(&self, _: &Connection, _: &OutboxEntry) -> Result<Option<ListenerQueue>, Error> {
+        Ok(None)
+    }
+
+    // Updates only go away with their file, whose tombstone covers them
+    fn delete(&self, _: &Connection, _: &OutboxEntry) -> Result<Option<ListenerQueue>, Error> {
+        Ok(None)
+    }
+}
@@ -0,0 +1,36 @@
+use super::listener::Listener;
+use pyxis_shared::entities::{outbox::OutboxEntry, queue::ListenerQueue, workspaces::Workspace};
+use pyxis_shared::payload::{RecordMessage, SyncMessage};
+use rusqlite::{Connection, Error};
+
+pub struct WorkspacesListener;
+
+impl Listener for WorkspacesListener {
+    fn insert(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error> {
+        let workspace = Workspace::get(connection, entry.row_id)?;
+
+        self.message_entry(SyncMessage::Workspace(RecordMessage::Insert(workspace)))
+    }
+
+    fn update(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error> {
+        let workspace = Workspace::get(connection, entry.row_id)?;
+
+        self.message_entry(SyncMessage::Workspace(RecordMessage::Update(workspace)))
+    }
+
+    fn delete(
+        &self,
+        connection: &Connection,
+        entry: &OutboxEntry,
+    ) -> Result<Option<ListenerQueue>, Error> {
+        self.tombstone_entry(connection, entry)
+    }
+}
@@ -0,0 +1,46 @@
+use std::{
+    fs::{self, File, OpenOptions, TryLockError},
+    io::{Error, Write},
+    path::PathBuf,
+};
+
+/*
+ * Keeps a single sync worker per user. The app and a standalone daemon would
+ * otherwise both send the same queue. The lock is released by the operating
+ * system when the process exits, so a crash never leaves it behind.
+ */
+pub struct InstanceLock {
+    _file: File,
+}
+
+impl InstanceLock {
+    fn get_lock_path() -> PathBuf {
+        let mut path = dirs::data_local_dir().expect("Failed to find local data directory");
+        path.push("pyxis");
+
+        fs::create_dir_all(&path).expect("Failed to create app directory");
+        path.push("pyxis-sync.lock");
+        path
+    }
+
+    // Returns None while another worker holds the lock
+    pub fn acquire() -> Result<Option<InstanceLock>, Error> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(InstanceLock::get_lock_path())?;
+
+        match file.try_lock() {
+            Ok(_) => {}
+            Err(TryLockError::WouldBlock) => return Ok(None),
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+
+        // Only informational, the lock itself is what counts
+        file.set_len(0)?;
+        writeln!(file, "{}", std::process::id())?;
+
+        Ok(Some(InstanceLock { _file: file }))
+    }
+}
@@ -1,27 +1,111 @@
+mod bootstrap;
+mod cli;
+mod coalesce;
+mod commands;
+mod listeners;
+mod lock;
+mod network;
+mod outbox;
+mod shutdown;
 mod writer;
 
+use clap::Parser;
+use cli::{Cli, Command, QueueCommand, TrackerCommand};
+use commands::{queue, status::status, tracker, verify::verify, CommandResult};
+use lock::InstanceLock;
 use pyxis_shared::database::Database;
-use rusqlite::Error;
-use sync_worker::sync_worker;
+use rusqlite::Connection;
+use shutdown::Shutdown;
+use std::{process::ExitCode, time::Duration};
+use sync_worker::{parent_exited, sync_worker, WorkerExit, WorkerOptions};
 
 mod sync_worker;
-use std::env;
 
-fn get_pid_arg(args: Vec<String>) -> Option<i32> {
-    for i in 1..args.len() {
-        if args[i] == "--pid" && i + 1 < args.len() {
-            return args[i + 1].parse().ok();
-        }
+// How often a sidecar waiting on another worker checks the lock again
+const LOCK_RETRY_INTERVAL: u64 = 5;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    // Administrative commands work next to a running worker and need no lock
+    if let Some(command) = cli.command {
+        let db = Database::create_connection("pyxis_sync");
+        let connection = db.get_connection();
+
+        return match run_command(&connection, command).await {
+            Ok(true) => ExitCode::SUCCESS,
+            Ok(false) => ExitCode::FAILURE,
+            Err(e) => {
+                eprintln!("{}", e);
+                ExitCode::FAILURE
+            }
+        };
     }
 
-    None
-}
+    let mut shutdown = Shutdown::listen();
+
+    /*
+     * A sidecar started while a daemon is running waits and takes over when the
+     * daemon stops. A second daemon has nothing to wait for.
+     */
+    let _lock = loop {
+        match InstanceLock::acquire() {
+            Ok(Some(lock)) => break lock,
+            Ok(None) if cli.pid.is_some() && !cli.once => {
+                if parent_exited(cli.pid)
+                    || !shutdown
+                        .sleep(Duration::from_secs(LOCK_RETRY_INTERVAL))
+                        .await
+                {
+                    return ExitCode::SUCCESS;
+                }
+            }
+            Ok(None) => {
+                eprintln!("Another sync worker is already running!");
+                return ExitCode::FAILURE;
+            }
+            Err(e) => {
+                eprintln!("Failed to lock the sync worker. Error: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+    };
 
-#[tokio::main]
-async fn main() -> Result<(), Error> {
     let db = Database::create_connection("pyxis_sync");
     let connection = db.get_connection();
-    let pid: Option<i32> = get_pid_arg(env::args().collect());
+    let docs_db = Database::create_connection("pyxis_docs");
+    let docs_connection = docs_db.get_connection();
+    let options = WorkerOptions {
+        pid: cli.pid,
+        once: cli.once,
+    };
 
-    sync_worker(&connection, pid).await
+    match sync_worker(&connection, &docs_connection, &options, &mut shutdown).await {
+        Ok(WorkerExit::Drained) | Ok(WorkerExit::Stopped) => ExitCode::SUCCESS,
+        Ok(WorkerExit::Incomplete) => {
+            eprintln!("Stopped before the queue was drained!");
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprintln!("[Worker] Stopped. Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run_command(conn: &Connection, command: Command) -> CommandResult {
+    match command {
+        Command::Status => status(conn),
+        Command::Queue { command } => match command {
+            QueueCommand::List { status, limit } => queue::list(conn, status.as_deref(), limit),
+            QueueCommand::Show { id } => queue::show(conn, id),
+            QueueCommand::Retry { id } => queue::retry(conn, id),
+            QueueCommand::Drop { id } => queue::drop(conn, id),
+        },
+        Command::Tracker { command } => match command {
+            TrackerCommand::Reset => tracker::reset(conn),
+        },
+        Command::Verify => verify(conn, &Database::create_connection("pyxis_docs")).await,
+    }
 }
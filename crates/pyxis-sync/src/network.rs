@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use pyxis_shared::entities::network::NetworkSettings;
+use tauri_plugin_http::reqwest::{Certificate, Client, Proxy};
+
+// Server the sidecar was built against, used until one is configured
+const DEFAULT_BASE_URL: Option<&str> = option_env!("APP_BASE_URL");
+
+pub struct SyncClient {
+    pub client: Client,
+    pub base_url: String,
+    pub settings: NetworkSettings,
+}
+
+impl SyncClient {
+    pub fn build(settings: NetworkSettings) -> Result<SyncClient, String> {
+        let base_url = match settings.server_url.as_deref().or(DEFAULT_BASE_URL) {
+            Some(url) => url.trim_end_matches('/').to_owned(),
+            None => return Err(String::from("No sync server configured")),
+        };
+
+        let mut builder = Client::builder();
+
+        if let Some(proxy_url) = &settings.proxy_url {
+            builder = builder.proxy(Proxy::all(proxy_url).map_err(|e| e.to_string())?);
+        }
+
+        if let Some(pem) = &settings.ca_certificate {
+            let certificate = Certificate::from_pem(pem.as_bytes()).map_err(|e| e.to_string())?;
+            builder = builder.add_root_certificate(certificate);
+        }
+
+        if let Some(seconds) = settings.connect_timeout_seconds {
+            builder = builder.connect_timeout(Duration::from_secs(seconds));
+        }
+
+        if let Some(seconds) = settings.request_timeout_seconds {
+            builder = builder.timeout(Duration::from_secs(seconds));
+        }
+
+        Ok(SyncClient {
+            client: builder.build().map_err(|e| e.to_string())?,
+            base_url,
+            settings,
+        })
+    }
+}
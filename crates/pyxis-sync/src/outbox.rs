@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use pyxis_shared::entities::{outbox::OutboxEntry, queue::ListenerQueue};
+use rusqlite::{Connection, Error};
+
+use crate::coalesce::enqueue;
+use crate::listeners::{to_queue_entry, ListenerMap};
+
+const BATCH_SIZE: i64 = 500;
+
+/*
+ * Moves changes recorded in the documents database into the listener queue.
+ * The two live in different files, so the move cannot be a single transaction.
+ * Every queue element keeps the id of the outbox entry it was built from and the
+ * outbox is only trimmed after the queue commits. If the sidecar dies in between,
+ * the next run trims what already made it instead of enqueueing it again.
+ */
+pub fn move_outbox(
+    docs_conn: &Connection,
+    config_conn: &Connection,
+    listeners: &ListenerMap,
+) -> Result<usize, Error> {
+    if let Some(moved_id) = ListenerQueue::last_outbox_id(config_conn)? {
+        OutboxEntry::remove_until(docs_conn, moved_id)?;
+    }
+
+    // Read the entries and the rows they point to from the same snapshot
+    let read = docs_conn.unchecked_transaction()?;
+    let entries = OutboxEntry::list(&read, BATCH_SIZE)?;
+    let last_id = match entries.last() {
+        Some(entry) => entry.id,
+        None => return Ok(0),
+    };
+
+    let mut groups: Vec<Vec<&OutboxEntry>> = vec![];
+    let mut group_index: HashMap<(String, i64), usize> = HashMap::new();
+
+    for entry in entries.iter() {
+        let key = (entry.source.to_string(), entry.row_id);
+        match group_index.get(&key) {
+            Some(index) => groups[*index].push(entry),
+            None => {
+                group_index.insert(key, groups.len());
+                groups.push(vec![entry]);
+            }
+        }
+    }
+
+    let mut queue_entries: Vec<ListenerQueue> = vec![];
+    for group in groups.iter() {
+        let listener = match listeners.get(&group[0].source.to_string()) {
+            Some(listener) => listener,
+            None => continue,
+        };
+
+        if let Some(queue_entry) = to_queue_entry(listener.as_ref(), &read, group)? {
+            queue_entries.push(queue_entry);
+        }
+    }
+
+    let write = config_conn.unchecked_transaction()?;
+    for queue_entry in queue_entries {
+        enqueue(&read, &write, queue_entry)?;
+    }
+    write.commit()?;
+    read.commit()?;
+
+    OutboxEntry::remove_until(docs_conn, last_id)?;
+
+    Ok(entries.len())
+}
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::watch,
+};
+
+/*
+ * Set once SIGTERM or SIGINT is received. The worker finishes the element it
+ * is sending and stops at the next check instead of dying mid-request.
+ */
+pub struct Shutdown {
+    receiver: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    pub fn listen() -> Shutdown {
+        let (sender, receiver) = watch::channel(false);
+
+        tokio::spawn(async move {
+            match signal(SignalKind::terminate()) {
+                Ok(mut terminate) => {
+                    tokio::select! {
+                        _ = terminate.recv() => {}
+                        _ = tokio::signal::ctrl_c() => {}
+                    }
+                }
+                Err(e) => {
+                    eprintln!("[Worker] Failed to listen for SIGTERM. Error: {}", e);
+                    let _ = tokio::signal::ctrl_c().await;
+                }
+            }
+
+            println!("Shutting down...");
+            let _ = sender.send(true);
+        });
+
+        Shutdown { receiver }
+    }
+
+    pub fn requested(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    // Returns false when woken up by a shutdown
+    pub async fn sleep(&mut self, duration: Duration) -> bool {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => {}
+            _ = self.receiver.wait_for(|requested| *requested) => {}
+        }
+
+        !self.requested()
+    }
+}
@@ -1,41 +1,90 @@
 use procfs::process::Process;
-use std::{cmp::min, str::FromStr, thread::sleep, time::Duration};
-use tauri_plugin_http::reqwest;
+use std::{cmp::min, str::FromStr, time::Duration};
 
 use pyxis_shared::entities::{
+    bootstrap::Bootstrap,
     config::{ConfigEntry, Features},
+    network::NetworkSettings,
     queue::{ListenerQueue, Source},
     tracker::Tracker,
 };
 use rusqlite::{Connection, Error};
+use tauri_plugin_http::reqwest::StatusCode;
 use uuid::Uuid;
 
+use crate::bootstrap::{bootstrap, Remote};
+use crate::listeners::create_listeners;
+use crate::network::SyncClient;
+use crate::outbox::move_outbox;
+use crate::shutdown::Shutdown;
 use crate::writer::{
-    document_writer::DocumentWriter, sync_writer::SyncWriter, update_writer::UpdateWriter,
+    attachment_writer::AttachmentWriter, document_writer::DocumentWriter, sync_writer::SyncWriter,
+    update_writer::UpdateWriter,
 };
 
 const MAX_SLEEP_DURATION: u64 = 100;
+const POLL_INTERVAL: u64 = 1;
+// Quiet time before a queued change is sent, so that following edits fold into it
+const SETTLE_SECONDS: i64 = 2;
 
-pub async fn sync_worker(conn: &Connection, pid: Option<i32>) -> Result<(), Error> {
-    let client = reqwest::Client::new();
+pub struct WorkerOptions {
+    // Process the worker runs on behalf of, when started as a sidecar
+    pub pid: Option<i32>,
+    // Drain the queue and return instead of polling
+    pub once: bool,
+}
+
+pub enum WorkerExit {
+    Drained,
+    Stopped,
+    // Only in once mode, when the queue could not be drained
+    Incomplete,
+}
+
+pub fn parent_exited(pid: Option<i32>) -> bool {
+    match pid {
+        Some(pid) if Process::new(pid).is_err() => {
+            println!("Main process no longer exists! Process Id: {}", pid);
+            true
+        }
+        _ => false,
+    }
+}
+
+pub async fn sync_worker(
+    conn: &Connection,
+    docs_conn: &Connection,
+    options: &WorkerOptions,
+    shutdown: &mut Shutdown,
+) -> Result<WorkerExit, Error> {
+    let mut sync_client: Option<SyncClient> = None;
+    let listeners = create_listeners();
     let mut sleep_duration = 10;
+    // Nothing will be folded into pending changes once the queue is drained
+    let settle_seconds = if options.once { 0 } else { SETTLE_SECONDS };
 
     loop {
-        if !pid.is_none() && Process::new(pid.unwrap()).is_err() {
-            println!(
-                "Main process no longer exists! Process Id: {}",
-                pid.unwrap()
-            );
-            std::process::exit(0);
+        if parent_exited(options.pid) || shutdown.requested() {
+            return Ok(WorkerExit::Stopped);
         }
 
         sleep_duration = min(sleep_duration, MAX_SLEEP_DURATION);
 
+        // Changes are queued even while signed out or with sync disabled
+        if let Err(e) = move_outbox(docs_conn, conn, &listeners) {
+            eprintln!(
+                "[Outbox] Failed to move changes into the queue. Error: {}",
+                e
+            );
+        }
+
         let (user_token, device_id, user_id, features) = match get_valid_configuration(conn)? {
             Some(config) => config,
             None => {
                 eprintln!("Invalid configuration!");
-                handle_backoff(&mut sleep_duration);
+                if let Some(exit) = handle_backoff(&mut sleep_duration, options, shutdown).await {
+                    return Ok(exit);
+                }
                 continue;
             }
         };
@@ -44,11 +93,64 @@ pub async fn sync_worker(conn: &Connection, pid: Option<i32>) -> Result<(), Erro
             let sync = feature.get("sync");
             if sync.is_none() || !sync.unwrap().0 || sync.unwrap().1 != String::from("enabled") {
                 eprintln!("Sync disabled!");
-                handle_backoff(&mut sleep_duration);
+                if let Some(exit) = handle_backoff(&mut sleep_duration, options, shutdown).await {
+                    return Ok(exit);
+                }
                 continue;
             }
         }
 
+        // Settings are read on every loop so that changes apply without a restart
+        let settings = match NetworkSettings::get(conn) {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("[Network] Failed to get network settings! Error: {}", e);
+                if let Some(exit) = handle_backoff(&mut sleep_duration, options, shutdown).await {
+                    return Ok(exit);
+                }
+                continue;
+            }
+        };
+
+        if !matches!(&sync_client, Some(current) if current.settings == settings) {
+            sync_client = match SyncClient::build(settings) {
+                Ok(client) => Some(client),
+                Err(e) => {
+                    eprintln!("[Network] Failed to build the HTTP client! Error: {}", e);
+                    None
+                }
+            };
+        }
+
+        let (client, base_url) = match &sync_client {
+            Some(sync_client) => (&sync_client.client, sync_client.base_url.as_str()),
+            None => {
+                if let Some(exit) = handle_backoff(&mut sleep_duration, options, shutdown).await {
+                    return Ok(exit);
+                }
+                continue;
+            }
+        };
+
+        // Asked for by the app. Runs before anything else is sent
+        if Bootstrap::is_pending(conn)? {
+            let remote = Remote {
+                client,
+                base_url,
+                token: &user_token,
+            };
+
+            match bootstrap(conn, docs_conn, &remote, device_id, user_id, shutdown).await {
+                Ok(true) => println!("[Bootstrap] Finished"),
+                Ok(false) => {}
+                Err(e) => {
+                    eprintln!("[Bootstrap] Failed! Error: {}", e);
+                    Bootstrap::set_status(conn, "failed", Some(&e.to_string()))?;
+                }
+            }
+            continue;
+        }
+
         let last_written_id: i64 = match Tracker::get_last_queue_entry_id(conn, device_id, user_id)
         {
             Ok(id) => id.or(Some(0)).unwrap(),
@@ -58,14 +160,18 @@ pub async fn sync_worker(conn: &Connection, pid: Option<i32>) -> Result<(), Erro
             }
         };
 
-        let queue_element = match ListenerQueue::dequeue(conn, last_written_id) {
+        let queue_element = match ListenerQueue::dequeue(conn, last_written_id, settle_seconds) {
             Ok(elem) => elem,
+            Err(Error::QueryReturnedNoRows) if options.once => return Ok(WorkerExit::Drained),
             Err(Error::QueryReturnedNoRows) => {
+                shutdown.sleep(Duration::from_secs(POLL_INTERVAL)).await;
                 continue;
             }
             Err(e) => {
                 eprintln!("Failed to dequeue. Error: {}", e);
-                handle_backoff(&mut sleep_duration);
+                if let Some(exit) = handle_backoff(&mut sleep_duration, options, shutdown).await {
+                    return Ok(exit);
+                }
                 continue;
             }
         };
@@ -73,16 +179,27 @@ pub async fn sync_worker(conn: &Connection, pid: Option<i32>) -> Result<(), Erro
         let processing_result = match queue_element.source {
             Source::Update => {
                 (UpdateWriter {})
-                    .write(&client, &queue_element, user_token)
+                    .write(client, base_url, &queue_element, user_token)
                     .await
             }
+            Source::Attachment => {
+                (AttachmentWriter {
+                    document_writer: DocumentWriter {
+                        conn,
+                        device_id,
+                        user_id,
+                    },
+                })
+                .write(client, base_url, &queue_element, user_token)
+                .await
+            }
             _ => {
                 (DocumentWriter {
                     conn,
                     device_id,
                     user_id,
                 })
-                .write(&client, &queue_element, user_token)
+                .write(client, base_url, &queue_element, user_token)
                 .await
             }
         };
@@ -113,10 +230,38 @@ pub async fn sync_worker(conn: &Connection, pid: Option<i32>) -> Result<(), Erro
 
                 Ok(())
             }
+            // The record id was taken and has been skipped. Retry right away with the next one.
+            Err(e)
+                if e.status() == Some(StatusCode::CONFLICT)
+                    && queue_element.source != Source::Update =>
+            {
+                eprintln!("[Post Write] Record id already used: {}", e);
+                queue_element.requeue(conn)?;
+                continue;
+            }
+            /*
+             * Refused for what it holds: the server can't read it, or an update
+             * already went out under its key with a different payload. Sending
+             * it again changes nothing.
+             */
+            Err(e)
+                if e.status() == Some(StatusCode::UNPROCESSABLE_ENTITY)
+                    || e.status() == Some(StatusCode::CONFLICT) =>
+            {
+                eprintln!(
+                    "[Post Write] Element {} was rejected. Skipping it. {}",
+                    queue_element.id.unwrap_or_default(),
+                    e
+                );
+                skip_element(conn, &queue_element, device_id, user_id)?;
+                continue;
+            }
             Err(e) => {
                 eprintln!("[Post Write] Error: {}", e);
                 queue_element.requeue(conn)?;
-                handle_backoff(&mut sleep_duration);
+                if let Some(exit) = handle_backoff(&mut sleep_duration, options, shutdown).await {
+                    return Ok(exit);
+                }
                 continue;
             }
         };
@@ -128,7 +273,9 @@ pub async fn sync_worker(conn: &Connection, pid: Option<i32>) -> Result<(), Erro
             Err(e) => {
                 eprintln!("[Post Processing] Error: {}", e);
                 queue_element.requeue(conn)?;
-                handle_backoff(&mut sleep_duration);
+                if let Some(exit) = handle_backoff(&mut sleep_duration, options, shutdown).await {
+                    return Ok(exit);
+                }
             }
         }
     }
@@ -161,7 +308,53 @@ fn get_valid_configuration(
     }
 }
 
-fn handle_backoff(sleep_duration: &mut u64) {
-    sleep(Duration::from_secs(*sleep_duration));
+/*
+ * The server refused the element itself, so sending it again would fail the same
+ * way and hold back everything queued after it. It is left in the queue marked
+ * failed, for `queue list --status failed` and `queue retry`, and the cursor
+ * moves past it.
+ */
+fn skip_element(
+    conn: &Connection,
+    queue_element: &ListenerQueue,
+    device_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), Error> {
+    let source = queue_element.source.clone();
+    let record_id = match Tracker::get(conn, vec![source.clone()], device_id, user_id) {
+        Ok(record) => record.record_id,
+        Err(Error::QueryReturnedNoRows) => 0,
+        Err(e) => return Err(e),
+    };
+
+    let transaction = conn.unchecked_transaction()?;
+    queue_element.requeue(&transaction)?;
+    Tracker::new(
+        None,
+        device_id,
+        source,
+        record_id,
+        user_id,
+        queue_element.id,
+    )
+    .add(&transaction)?;
+    transaction.commit()
+}
+
+// Returns how the worker should exit instead of retrying, if it should
+async fn handle_backoff(
+    sleep_duration: &mut u64,
+    options: &WorkerOptions,
+    shutdown: &mut Shutdown,
+) -> Option<WorkerExit> {
+    if options.once {
+        return Some(WorkerExit::Incomplete);
+    }
+
+    if !shutdown.sleep(Duration::from_secs(*sleep_duration)).await {
+        return Some(WorkerExit::Stopped);
+    }
     *sleep_duration *= 2;
+
+    None
 }
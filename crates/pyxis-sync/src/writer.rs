@@ -1,3 +1,4 @@
+pub mod attachment_writer;
 pub mod document_writer;
 pub mod sync_writer;
 pub mod update_writer;
@@ -0,0 +1,69 @@
+use pyxis_shared::{
+    entities::{attachments::Attachment, queue::ListenerQueue},
+    utils::blob_store,
+};
+use tauri_plugin_http::reqwest::{Client, Error};
+
+use super::{
+    document_writer::DocumentWriter,
+    sync_writer::{check_status, SyncWriter},
+};
+
+pub struct AttachmentWriter<'a> {
+    pub document_writer: DocumentWriter<'a>,
+}
+
+impl<'a> AttachmentWriter<'a> {
+    async fn upload_blob(
+        &self,
+        client: &Client,
+        base_url: &str,
+        hash: &str,
+        token: &str,
+    ) -> Result<(), Error> {
+        let content = match blob_store::read(hash) {
+            Ok(content) => content,
+            Err(e) => {
+                // Nothing to upload. Other devices will fail to fetch the blob.
+                eprintln!("[Attachment Writer] Failed to read blob {}! {}", hash, e);
+                return Ok(());
+            }
+        };
+
+        let response = client
+            .put(format!("{}/sync/attachment/{}", base_url, hash))
+            .body(content)
+            .header("authorization", format!("Bearer {}", token))
+            .send()
+            .await?;
+        check_status(response).await?;
+
+        Ok(())
+    }
+}
+
+impl<'a> SyncWriter for AttachmentWriter<'a> {
+    async fn write(
+        &self,
+        client: &Client,
+        base_url: &str,
+        queue_element: &ListenerQueue,
+        token: String,
+    ) -> Result<(i64, i64), Error> {
+        /*
+         * The blob has to reach the server before the document so that other
+         * devices never see an attachment they cannot download
+         */
+        if queue_element.operation == "insert" {
+            let attachment: Attachment = serde_json::from_str(&queue_element.payload)
+                .expect("[Attachment Writer] Failed to parse payload!");
+
+            self.upload_blob(client, base_url, &attachment.hash, &token)
+                .await?;
+        }
+
+        self.document_writer
+            .write(client, base_url, queue_element, token)
+            .await
+    }
+}
@@ -1,17 +1,15 @@
-use std::env;
-
 use pyxis_shared::{
     entities::{
         queue::{ListenerQueue, Source},
         tracker::Tracker,
     },
-    payload::DocumentWritePayload,
+    payload::{DocumentWritePayload, SYNC_MESSAGE_VERSION},
 };
 use rusqlite::Connection;
-use tauri_plugin_http::reqwest::{Client, Error};
+use tauri_plugin_http::reqwest::{Client, Error, StatusCode};
 use uuid::Uuid;
 
-use super::sync_writer::SyncWriter;
+use super::sync_writer::{check_status, SyncWriter};
 
 pub struct DocumentWriter<'a> {
     pub device_id: Uuid,
@@ -19,17 +17,54 @@ pub struct DocumentWriter<'a> {
     pub conn: &'a Connection,
 }
 
+impl<'a> DocumentWriter<'a> {
+    /*
+     * The server already holds a different record under this id, typically after
+     * the tracker was reset. The id is marked as used so the element is retried
+     * with the next one. The queue cursor stays where it is.
+     */
+    fn skip_record(&self, source: &Source, record_id: i64) {
+        let skipped = Tracker::get_last_queue_entry_id(self.conn, self.device_id, self.user_id)
+            .or_else(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                e => Err(e),
+            })
+            .and_then(|queue_entry_id| {
+                Tracker::new(
+                    None,
+                    self.device_id,
+                    source.clone(),
+                    record_id,
+                    self.user_id,
+                    queue_entry_id,
+                )
+                .add(self.conn)
+            });
+
+        if let Err(e) = skipped {
+            eprintln!("[Sync] Failed to skip record {}! {}", record_id, e);
+        }
+    }
+}
+
 impl<'a> SyncWriter for DocumentWriter<'a> {
     async fn write(
         &self,
         client: &Client,
+        base_url: &str,
         queue_element: &ListenerQueue,
         token: String,
     ) -> Result<(i64, i64), Error> {
         let sources = if queue_element.source == Source::Snapshot {
             [Source::Snapshot].to_vec()
         } else {
-            [Source::Directory, Source::File, Source::Workspace].to_vec()
+            [
+                Source::Directory,
+                Source::File,
+                Source::Workspace,
+                Source::Attachment,
+            ]
+            .to_vec()
         };
 
         let last_record = match Tracker::get(self.conn, sources, self.device_id, self.user_id) {
@@ -61,10 +96,10 @@ impl<'a> SyncWriter for DocumentWriter<'a> {
             operation: queue_element.operation.clone(),
             source: queue_element.source.to_string(),
             file_uid: queue_element.file_uid.clone(),
+            idempotency_key: queue_element.idempotency_key.clone(),
+            version: SYNC_MESSAGE_VERSION,
         };
 
-        let base_url = env!("APP_BASE_URL");
-
         let response = client
             .post(format!("{}/sync/document/write", base_url))
             .json(&update_payload)
@@ -72,7 +107,11 @@ impl<'a> SyncWriter for DocumentWriter<'a> {
             .send()
             .await?;
 
-        match response.error_for_status() {
+        if response.status() == StatusCode::CONFLICT {
+            self.skip_record(&queue_element.source, last_record.record_id + 1);
+        }
+
+        match check_status(response).await {
             Ok(_) => {
                 return Ok((last_record.record_id + 1, queue_element.id.unwrap()));
             }
@@ -1,12 +1,35 @@
 use pyxis_shared::entities::queue::{ListenerQueue, Source};
 use rusqlite::Connection;
-use tauri_plugin_http::reqwest::{Client, Error};
+use tauri_plugin_http::reqwest::{Client, Error, Response, StatusCode};
 use uuid::Uuid;
 
+/*
+ * Like `error_for_status`, but prints what the server said when it refused the
+ * message itself. Retrying won't help then, so the reason has to be in the log.
+ */
+pub async fn check_status(response: Response) -> Result<Response, Error> {
+    let status = response.status();
+    let result = response.error_for_status_ref().map(|_| ());
+
+    match result {
+        Ok(()) => Ok(response),
+        Err(e) => {
+            if status == StatusCode::UNPROCESSABLE_ENTITY || status == StatusCode::CONFLICT {
+                eprintln!(
+                    "[Sync] Rejected by the server: {}",
+                    response.text().await.unwrap_or_default()
+                );
+            }
+            Err(e)
+        }
+    }
+}
+
 pub trait SyncWriter {
     async fn write(
         &self,
         client: &Client,
+        base_url: &str,
         element: &ListenerQueue,
         token: String,
     ) -> Result<(i64, i64), Error>;
@@ -1,15 +1,14 @@
-use std::env;
-
 use pyxis_shared::{
     entities::{
         queue::{ListenerQueue, Source},
         tracker::Tracker,
+        updates::Updates,
     },
-    payload::UpdateWritePayload,
+    payload::{UpdateEncoding, UpdateWritePayload, SYNC_MESSAGE_VERSION},
 };
 use tauri_plugin_http::reqwest::{Client, Error};
 
-use super::sync_writer::SyncWriter;
+use super::sync_writer::{check_status, SyncWriter};
 use rusqlite::Connection;
 use uuid::Uuid;
 
@@ -19,22 +18,36 @@ impl SyncWriter for UpdateWriter {
     async fn write(
         &self,
         client: &Client,
+        base_url: &str,
         queue_element: &ListenerQueue,
         token: String,
     ) -> Result<(i64, i64), Error> {
+        // Queued as json. Sent as is when it can't be read back.
+        let (encoding, payload) = match serde_json::from_str::<Updates>(&queue_element.payload)
+            .map_err(|e| e.into())
+            .and_then(|update| UpdateEncoding::Zstd.encode(&update))
+        {
+            Ok(encoded) => encoded,
+            Err(e) => {
+                eprintln!("[Updates] Failed to encode update! {}", e);
+                (UpdateEncoding::Json, queue_element.payload.clone())
+            }
+        };
+
         let update_payload = UpdateWritePayload {
             file_uid: queue_element
                 .file_uid
                 .clone()
                 .expect("No file id associated with the update"),
-            payload: queue_element.payload.clone(),
+            payload,
             snapshot_id: queue_element
                 .snapshot_id
                 .expect("No snapshot id associated with the update"),
+            idempotency_key: queue_element.idempotency_key.clone(),
+            encoding,
+            version: SYNC_MESSAGE_VERSION,
         };
 
-        let base_url = env!("APP_BASE_URL");
-
         let response = client
             .post(format!("{}/sync/update/write", base_url))
             .json(&update_payload)
@@ -42,7 +55,7 @@ impl SyncWriter for UpdateWriter {
             .send()
             .await?;
 
-        match response.error_for_status() {
+        match check_status(response).await {
             Ok(_) => {
                 return Ok((0, queue_element.id.unwrap()));
             }
@@ -1,24 +1,40 @@
 use std::{
-    path::PathBuf,
-    sync::{mpsc::channel, Arc, Mutex, MutexGuard},
-    thread,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, TryLockError,
+    },
 };
 
-use rusqlite::{hooks::Action, Connection};
+use rusqlite::{Connection, OpenFlags};
 
+/*
+ * A single writer connection and, when opened as a pool, a few read-only
+ * connections. In WAL mode readers see the last committed state and never wait
+ * on the writer, so listing a big workspace does not queue behind a sync write.
+ * Without readers, reads go through the writer.
+ */
+#[derive(Clone)]
 pub struct Database {
     pub conn: Arc<Mutex<Connection>>,
+    readers: Arc<Vec<Mutex<Connection>>>,
+    next_reader: Arc<AtomicUsize>,
 }
 
-struct HookPayload {
-    action: Action,
-    db_name: String,
-    table_name: String,
-    row_id: i64,
-}
-
+#[derive(Clone)]
 pub struct ConfigDatabase(pub Database);
 
+fn lock(conn: &Mutex<Connection>) -> MutexGuard<'_, Connection> {
+    match conn.lock() {
+        Ok(conn) => conn,
+        Err(e) => {
+            eprintln!("Failed to acquire lock on DB connection. {}", e);
+            println!("Attempting to recover...");
+            e.into_inner()
+        }
+    }
+}
+
 impl Database {
     fn get_db_path(db_name: &str) -> PathBuf {
         let mut path = dirs::data_local_dir().expect("Failed to find local data directory");
@@ -30,7 +46,14 @@ impl Database {
     }
 
     pub fn create_connection(db_name: &str) -> Self {
-        let database = match Connection::open(Database::get_db_path(db_name)) {
+        Database::create_pool(db_name, 0)
+    }
+
+    // The writer is opened first, so the database is already in WAL mode for the readers
+    pub fn create_pool(db_name: &str, readers: usize) -> Self {
+        let path = Database::get_db_path(db_name);
+
+        let database = match Connection::open(&path) {
             Ok(conn) => {
                 conn.execute("PRAGMA journal_mode=WAL;", [])
                     .unwrap_or_default();
@@ -46,6 +69,12 @@ impl Database {
 
                 Database {
                     conn: Arc::new(Mutex::new(conn)),
+                    readers: Arc::new(
+                        (0..readers)
+                            .map(|_| Mutex::new(Database::open_reader(&path)))
+                            .collect(),
+                    ),
+                    next_reader: Arc::new(AtomicUsize::new(0)),
                 }
             }
             Err(e) => {
@@ -56,51 +85,44 @@ impl Database {
         database
     }
 
-    pub fn get_connection(&self) -> MutexGuard<Connection> {
-        match self.conn.lock() {
-            Ok(conn) => conn,
+    fn open_reader(path: &Path) -> Connection {
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX
+            | OpenFlags::SQLITE_OPEN_URI;
+
+        match Connection::open_with_flags(path, flags) {
+            Ok(conn) => {
+                conn.execute("PRAGMA busy_timeout=5000;", [])
+                    .unwrap_or_default();
+
+                conn
+            }
             Err(e) => {
-                eprintln!("Failed to acquire lock on DB connection. {}", e);
-                println!("Attempting to recover...");
-                e.into_inner()
+                panic!("Read connection failed. Error: {}", e)
             }
         }
     }
 
-    pub fn set_update_hook<F>(&self, hook: F, config_db: &ConfigDatabase)
-    where
-        F: Fn(Action, &str, &str, i64, &Connection, &Connection) + Send + Sync + 'static,
-    {
-        let (sender, receiver) = channel::<HookPayload>();
-        let conn = self.conn.clone();
-        let config_conn = config_db.0.conn.clone();
-
-        thread::spawn(move || {
-            while let Ok(update) = receiver.recv() {
-                if let Ok(conn) = conn.lock() {
-                    if let Ok(config_conn) = config_conn.lock() {
-                        hook(
-                            update.action,
-                            &update.db_name,
-                            &update.table_name,
-                            update.row_id,
-                            &conn,
-                            &config_conn,
-                        );
-                    }
-                }
+    // The writer. Anything that changes the database goes through it.
+    pub fn get_connection(&self) -> MutexGuard<Connection> {
+        lock(&self.conn)
+    }
+
+    // A free reader, or the next one in turn when all of them are busy
+    pub fn get_reader(&self) -> MutexGuard<'_, Connection> {
+        if self.readers.is_empty() {
+            return self.get_connection();
+        }
+
+        for reader in self.readers.iter() {
+            match reader.try_lock() {
+                Ok(conn) => return conn,
+                Err(TryLockError::Poisoned(e)) => return e.into_inner(),
+                Err(TryLockError::WouldBlock) => {}
             }
-        });
-
-        let update_hook = move |action, db: &str, table: &str, row_id: i64| {
-            let _ = sender.send(HookPayload {
-                action,
-                db_name: db.to_string(),
-                table_name: table.to_string(),
-                row_id,
-            });
-        };
+        }
 
-        self.get_connection().update_hook(Some(update_hook));
+        let index = self.next_reader.fetch_add(1, Ordering::Relaxed) % self.readers.len();
+        lock(&self.readers[index])
     }
 }
@@ -1,9 +1,17 @@
+pub mod attachments;
+pub mod bootstrap;
 pub mod config;
+pub mod conflicts;
+pub mod daily_notes;
 pub mod devices;
 pub mod directories;
+pub mod file_activity;
 pub mod files;
+pub mod network;
+pub mod outbox;
 pub mod queue;
 pub mod snapshots;
+pub mod tombstones;
 pub mod tracker;
 pub mod updates;
 pub mod workspaces;
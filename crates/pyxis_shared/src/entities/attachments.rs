@@ -0,0 +1,131 @@
+use chrono::Utc;
+use nanoid::nanoid;
+use rusqlite::{Connection, Error, Row};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Attachment {
+    pub id: Option<i64>,
+    pub uid: String,
+    pub file_uid: String,
+    pub name: String,
+    pub mime_type: String,
+    pub hash: String,
+    pub size: i64,
+    pub created_at: String,
+    pub synced: Option<bool>,
+}
+
+const SELECT_ATTACHMENTS: &str = "SELECT \
+    a.id, \
+    a.uid, \
+    f.uid as file_uid, \
+    a.name, \
+    a.mime_type, \
+    a.hash, \
+    a.size, \
+    a.created_at, \
+    a.synced \
+    FROM attachments a \
+    INNER JOIN files f ON a.file_id = f.id";
+
+impl Attachment {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        file_uid: String,
+        name: String,
+        mime_type: String,
+        hash: String,
+        size: i64,
+        uid: Option<String>,
+        created_at: Option<String>,
+        synced: Option<bool>,
+    ) -> Self {
+        Self {
+            id: None,
+            uid: uid.unwrap_or_else(|| nanoid!(10)),
+            file_uid,
+            name,
+            mime_type,
+            hash,
+            size,
+            created_at: created_at.unwrap_or_else(|| Utc::now().to_rfc3339()),
+            synced,
+        }
+    }
+
+    fn from_row(row: &Row) -> Result<Attachment, Error> {
+        Ok(Attachment {
+            id: row.get(0)?,
+            uid: row.get(1)?,
+            file_uid: row.get(2)?,
+            name: row.get(3)?,
+            mime_type: row.get(4)?,
+            hash: row.get(5)?,
+            size: row.get(6)?,
+            created_at: row.get(7)?,
+            synced: row.get(8)?,
+        })
+    }
+
+    pub fn get(conn: &Connection, id: i64) -> Result<Attachment, Error> {
+        let mut stmt = conn.prepare(&format!("{} WHERE a.id = ?1", SELECT_ATTACHMENTS))?;
+
+        stmt.query_row([&id], Attachment::from_row)
+    }
+
+    pub fn get_by_uid(conn: &Connection, uid: &str) -> Result<Attachment, Error> {
+        let mut stmt = conn.prepare(&format!("{} WHERE a.uid = ?1", SELECT_ATTACHMENTS))?;
+
+        stmt.query_row([uid], Attachment::from_row)
+    }
+
+    pub fn list(conn: &Connection, file_uid: &str) -> Result<Vec<Attachment>, Error> {
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE f.uid = ?1 ORDER BY a.created_at ASC",
+            SELECT_ATTACHMENTS
+        ))?;
+
+        let attachments_iter = stmt.query_map([file_uid], Attachment::from_row)?;
+
+        attachments_iter.collect()
+    }
+
+    pub fn create(&self, conn: &Connection) -> Result<(), Error> {
+        let mut files_sql = conn.prepare("SELECT id FROM files WHERE uid = ?1")?;
+        let file_id =
+            files_sql.query_row([&self.file_uid], |row| -> Result<i32, Error> { row.get(0) })?;
+
+        let sql = "INSERT INTO attachments (uid, file_id, name, mime_type, hash, size, created_at, synced) \
+                   VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)";
+
+        conn.execute(
+            sql,
+            (
+                &self.uid,
+                &file_id,
+                &self.name,
+                &self.mime_type,
+                &self.hash,
+                &self.size,
+                &self.created_at,
+                &self.synced,
+            ),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn delete(uid: &str, conn: &Connection) -> Result<(), Error> {
+        conn.execute("DELETE FROM attachments WHERE uid = (?1)", [uid])?;
+
+        Ok(())
+    }
+
+    pub fn is_hash_referenced(conn: &Connection, hash: &str) -> Result<bool, Error> {
+        let mut stmt = conn.prepare("SELECT COUNT(*) FROM attachments WHERE hash = ?1")?;
+        let count: i64 = stmt.query_row([hash], |row| row.get(0))?;
+
+        Ok(count > 0)
+    }
+}
@@ -0,0 +1,156 @@
+use chrono::Utc;
+use rusqlite::{Connection, Error, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use super::queue::Source;
+
+/*
+ * A full download of everything the devices of a user have sent. The app asks
+ * for one and the sync worker runs it. Progress is kept per device and source,
+ * so a bootstrap that was interrupted picks up after the last applied record.
+ *
+ * Status is one of requested, running, finished or failed.
+ */
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Bootstrap {
+    pub status: String,
+    pub requested_at: String,
+    pub finished_at: Option<String>,
+    pub error: Option<String>,
+    pub progress: Vec<BootstrapProgress>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BootstrapProgress {
+    pub device_id: String,
+    pub source: String,
+    // Highest record of the device applied for the source
+    pub record_id: i64,
+}
+
+impl Bootstrap {
+    pub fn get(conn: &Connection) -> Result<Option<Bootstrap>, Error> {
+        let mut stmt = conn.prepare(
+            "SELECT status, requested_at, finished_at, error FROM bootstrap WHERE id = 1",
+        )?;
+
+        let bootstrap = stmt
+            .query_row([], |row| {
+                Ok(Bootstrap {
+                    status: row.get(0)?,
+                    requested_at: row.get(1)?,
+                    finished_at: row.get(2)?,
+                    error: row.get(3)?,
+                    progress: vec![],
+                })
+            })
+            .optional()?;
+
+        match bootstrap {
+            Some(mut bootstrap) => {
+                bootstrap.progress = Bootstrap::list_progress(conn)?;
+                Ok(Some(bootstrap))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /*
+     * A bootstrap that failed or was interrupted resumes from its progress. Once
+     * one has finished, asking again starts over.
+     */
+    pub fn request(conn: &Connection) -> Result<(), Error> {
+        let transaction = conn.unchecked_transaction()?;
+
+        let status: Option<String> = transaction
+            .query_row("SELECT status FROM bootstrap WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+
+        if !matches!(status.as_deref(), Some("running") | Some("failed")) {
+            transaction.execute("DELETE FROM bootstrap_progress", ())?;
+        }
+
+        transaction.execute(
+            "INSERT INTO bootstrap (id, status, requested_at) VALUES (1, 'requested', ?1) \
+            ON CONFLICT(id) DO UPDATE SET status='requested', requested_at=?1, finished_at=NULL, error=NULL",
+            [&Utc::now().to_rfc3339()],
+        )?;
+
+        transaction.commit()
+    }
+
+    // Whether the worker has a bootstrap to run or resume
+    pub fn is_pending(conn: &Connection) -> Result<bool, Error> {
+        let status: Option<String> = conn
+            .query_row("SELECT status FROM bootstrap WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+
+        Ok(matches!(
+            status.as_deref(),
+            Some("requested") | Some("running")
+        ))
+    }
+
+    pub fn set_status(conn: &Connection, status: &str, error: Option<&str>) -> Result<(), Error> {
+        let finished_at = match status {
+            "finished" | "failed" => Some(Utc::now().to_rfc3339()),
+            _ => None,
+        };
+
+        conn.execute(
+            "UPDATE bootstrap SET status=?1, error=?2, finished_at=?3 WHERE id = 1",
+            (status, error, finished_at),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn get_progress(conn: &Connection, device_id: &str, source: &Source) -> Result<i64, Error> {
+        let record_id: Option<i64> = conn
+            .query_row(
+                "SELECT record_id FROM bootstrap_progress WHERE device_id=?1 AND source=?2",
+                (device_id, source.to_string()),
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(record_id.unwrap_or(0))
+    }
+
+    pub fn save_progress(
+        conn: &Connection,
+        device_id: &str,
+        source: &Source,
+        record_id: i64,
+    ) -> Result<(), Error> {
+        conn.execute(
+            "INSERT INTO bootstrap_progress (device_id, source, record_id) VALUES (?1, ?2, ?3) \
+            ON CONFLICT(device_id, source) DO UPDATE SET record_id=MAX(record_id, ?3)",
+            (device_id, source.to_string(), record_id),
+        )?;
+
+        Ok(())
+    }
+
+    fn list_progress(conn: &Connection) -> Result<Vec<BootstrapProgress>, Error> {
+        let mut stmt = conn.prepare(
+            "SELECT device_id, source, record_id FROM bootstrap_progress ORDER BY device_id, source",
+        )?;
+
+        let progress = stmt
+            .query_map([], |row: &Row| {
+                Ok(BootstrapProgress {
+                    device_id: row.get(0)?,
+                    source: row.get(1)?,
+                    record_id: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(progress)
+    }
+}
@@ -4,7 +4,7 @@ use serde_with::serde_as;
 use std::collections::HashMap;
 use uuid::Uuid;
 
-use crate::utils::get_machine_id::get_machine_id;
+use crate::{error::corrupt_column, utils::get_machine_id::get_machine_id};
 
 pub type Features = HashMap<String, (bool, String)>;
 
@@ -49,10 +49,7 @@ impl ConfigEntry {
         let mut stmt = conn.prepare("SELECT config FROM configuration WHERE json_extract(config, '$.user_token') IS NOT NULL")?;
         let config = stmt.query_row([], |row| {
             let json_str: String = row.get(0)?;
-            let config: Configuration = serde_json::from_str(&json_str)
-                .expect("[Configuration] Failed to cast JSON to struct!");
-
-            Ok(config)
+            serde_json::from_str::<Configuration>(&json_str).map_err(|e| corrupt_column(0, e))
         });
 
         config
@@ -62,10 +59,7 @@ impl ConfigEntry {
         let mut stmt = conn.prepare("SELECT config FROM configuration WHERE user_id=?1")?;
         let config = stmt.query_row([&user_id], |row| {
             let json_str: String = row.get(0)?;
-            let config: Configuration = serde_json::from_str(&json_str)
-                .expect("[Configuration] Failed to cast JSON to struct!");
-
-            Ok(config)
+            serde_json::from_str::<Configuration>(&json_str).map_err(|e| corrupt_column(0, e))
         });
 
         config
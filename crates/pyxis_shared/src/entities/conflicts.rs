@@ -0,0 +1,450 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use chrono::Utc;
+use rusqlite::{types::FromSqlError, Connection, Error, Row};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{from_str, from_value, to_string, to_value, Map, Value};
+
+use super::{directories::Directory, files::Files, queue::Source, workspaces::Workspace};
+use crate::{error::corrupt_column, utils::hlc::Hlc};
+
+/*
+ * Metadata rows are merged field by field. Every tracked field carries the clock
+ * of its last edit, the clock it replaced, and the latest edit it has seen from
+ * each device. An edit is sequential when the other side has seen it, however
+ * many edits were made on top of it since, and concurrent when neither side
+ * has seen the other's.
+ */
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FieldClock {
+    pub clock: Hlc,
+    pub prev: Option<Hlc>,
+    // Keyed by node. Missing on clocks written before it was tracked.
+    #[serde(default)]
+    pub seen: BTreeMap<String, Hlc>,
+}
+
+impl FieldClock {
+    fn has_seen(&self, clock: &Hlc) -> bool {
+        self.clock == *clock
+            || self.prev.as_ref() == Some(clock)
+            || self.seen.get(&clock.node).is_some_and(|seen| seen >= clock)
+    }
+
+    // Keeps this edit and everything either side has seen
+    fn merge(&self, other: &FieldClock) -> FieldClock {
+        let mut merged = self.clone();
+        for clock in other.seen.values().chain([&other.clock, &self.clock]) {
+            see(&mut merged.seen, clock);
+        }
+
+        merged
+    }
+}
+
+fn see(seen: &mut BTreeMap<String, Hlc>, clock: &Hlc) {
+    if seen.get(&clock.node).is_none_or(|last| last < clock) {
+        seen.insert(clock.node.clone(), clock.clone());
+    }
+}
+
+pub type FieldClocks = BTreeMap<String, FieldClock>;
+
+pub fn field_clocks_from_row(row: &Row, idx: usize) -> Result<FieldClocks, Error> {
+    let clocks: Option<String> = row.get(idx)?;
+
+    match clocks {
+        Some(c) => from_str(&c).map_err(|e| corrupt_column(idx, e)),
+        None => Ok(FieldClocks::default()),
+    }
+}
+
+pub fn field_clocks_to_sql(clocks: &FieldClocks) -> String {
+    to_string(clocks).expect("[Conflicts] Unable to serialize field clocks")
+}
+
+pub trait Versioned: Serialize + DeserializeOwned {
+    const SOURCE: Source;
+    const FIELDS: &'static [&'static str];
+
+    fn uid(&self) -> &str;
+    fn field_clocks(&self) -> &FieldClocks;
+    fn load(conn: &Connection, uid: &str) -> Result<Self, Error>;
+    fn save(&self, conn: &Connection) -> Result<(), Error>;
+}
+
+fn to_sql_error(error: impl std::error::Error + Send + Sync + 'static) -> Error {
+    Error::ToSqlConversionFailure(Box::new(error))
+}
+
+fn to_map<T: Serialize>(entity: &T) -> Result<Map<String, Value>, Error> {
+    match to_value(entity).map_err(to_sql_error)? {
+        Value::Object(map) => Ok(map),
+        _ => Err(Error::ToSqlConversionFailure(
+            "[Conflicts] Entities serialize to objects".into(),
+        )),
+    }
+}
+
+fn from_map<T: DeserializeOwned>(map: Map<String, Value>) -> Result<T, serde_json::Error> {
+    from_value(Value::Object(map))
+}
+
+fn observe_all(clocks: &FieldClocks) {
+    for field_clock in clocks.values() {
+        Hlc::observe(&field_clock.clock);
+    }
+}
+
+/*
+ * Stamps every tracked field that differs from the stored row with a fresh
+ * clock. Untouched fields keep the clock they already had.
+ */
+pub fn update_local<T: Versioned>(conn: &Connection, updated: T) -> Result<T, Error> {
+    let existing = T::load(conn, updated.uid())?;
+    let existing_map = to_map(&existing)?;
+    let mut updated_map = to_map(&updated)?;
+
+    observe_all(existing.field_clocks());
+    let mut clocks = existing.field_clocks().clone();
+
+    for field in T::FIELDS {
+        if existing_map.get(*field) != updated_map.get(*field) {
+            let clock = Hlc::now();
+            let prev = clocks.get(*field).map(|c| c.clock.clone());
+            let mut seen = clocks
+                .get(*field)
+                .map(|c| c.seen.clone())
+                .unwrap_or_default();
+            for based_on in prev.iter().chain([&clock]) {
+                see(&mut seen, based_on);
+            }
+
+            clocks.insert(field.to_string(), FieldClock { clock, prev, seen });
+        }
+    }
+
+    updated_map.insert(
+        String::from("field_clocks"),
+        to_value(&clocks).map_err(to_sql_error)?,
+    );
+
+    let updated: T = from_map(updated_map).map_err(to_sql_error)?;
+    updated.save(conn)?;
+
+    Ok(updated)
+}
+
+/*
+ * Applies a row received from another device. For each tracked field the edit
+ * with the larger clock wins. When neither side had seen the other's edit, the
+ * losing value is kept in the conflicts table so that it can be restored.
+ */
+pub fn update_remote<T: Versioned>(conn: &Connection, incoming: T) -> Result<T, Error> {
+    let existing = T::load(conn, incoming.uid())?;
+    let mut merged_map = to_map(&existing)?;
+    let incoming_map = to_map(&incoming)?;
+
+    observe_all(incoming.field_clocks());
+    let mut clocks = existing.field_clocks().clone();
+
+    for field in T::FIELDS {
+        let local_value = merged_map.get(*field).cloned().unwrap_or(Value::Null);
+        let remote_value = incoming_map.get(*field).cloned().unwrap_or(Value::Null);
+
+        if local_value == remote_value {
+            if let Some(remote) = incoming.field_clocks().get(*field) {
+                let merged = match clocks.get(*field) {
+                    Some(local) if local.clock >= remote.clock => local.merge(remote),
+                    Some(local) => remote.merge(local),
+                    None => remote.clone(),
+                };
+                clocks.insert(field.to_string(), merged);
+            }
+            continue;
+        }
+
+        let remote_wins = match (clocks.get(*field), incoming.field_clocks().get(*field)) {
+            // Neither side tracks the field yet (older clients). Keep the previous behaviour.
+            (None, None) => true,
+            (None, Some(_)) => true,
+            (Some(_), None) => false,
+            (Some(local), Some(remote)) => {
+                let concurrent = !remote.has_seen(&local.clock) && !local.has_seen(&remote.clock);
+
+                let remote_wins = remote.clock > local.clock;
+
+                if concurrent {
+                    Conflict::new(
+                        T::SOURCE,
+                        incoming.uid().to_owned(),
+                        field.to_string(),
+                        local_value.clone(),
+                        remote_value.clone(),
+                        local.clock.clone(),
+                        remote.clock.clone(),
+                        if remote_wins {
+                            Resolution::Remote
+                        } else {
+                            Resolution::Local
+                        },
+                    )
+                    .create(conn)?;
+                }
+
+                remote_wins
+            }
+        };
+
+        let merged = match (clocks.get(*field), incoming.field_clocks().get(*field)) {
+            (Some(local), Some(remote)) if remote_wins => Some(remote.merge(local)),
+            (Some(local), Some(remote)) => Some(local.merge(remote)),
+            (None, Some(remote)) => Some(remote.clone()),
+            _ => None,
+        };
+        if let Some(merged) = merged {
+            clocks.insert(field.to_string(), merged);
+        }
+
+        if remote_wins {
+            merged_map.insert(field.to_string(), remote_value);
+        }
+    }
+
+    merged_map.insert(
+        String::from("field_clocks"),
+        to_value(&clocks).map_err(to_sql_error)?,
+    );
+    if let Some(synced) = incoming_map.get("synced") {
+        merged_map.insert(String::from("synced"), synced.clone());
+    }
+    if let (Some(Value::String(local)), Some(Value::String(remote))) =
+        (merged_map.get("updated_at"), incoming_map.get("updated_at"))
+    {
+        if remote > local {
+            merged_map.insert(String::from("updated_at"), Value::String(remote.clone()));
+        }
+    }
+
+    let merged: T = from_map(merged_map).map_err(to_sql_error)?;
+    merged.save(conn)?;
+
+    Ok(merged)
+}
+
+// `column` is the column of the conflict the value was read from
+fn apply_value<T: Versioned>(
+    conn: &Connection,
+    uid: &str,
+    field: &str,
+    value: Value,
+    column: usize,
+) -> Result<(), Error> {
+    let mut map = to_map(&T::load(conn, uid)?)?;
+    map.insert(field.to_owned(), value);
+    map.insert(
+        String::from("updated_at"),
+        Value::String(Utc::now().to_rfc3339()),
+    );
+    // Resolving is a local edit and has to be sent to the other devices
+    map.insert(String::from("synced"), Value::Null);
+
+    let updated = from_map::<T>(map).map_err(|e| corrupt_column(column, e))?;
+    update_local(conn, updated)?;
+
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    Local,
+    Remote,
+}
+
+impl Resolution {
+    fn to_sql(self) -> &'static str {
+        match self {
+            Resolution::Local => "local",
+            Resolution::Remote => "remote",
+        }
+    }
+
+    fn from_sql(value: &str) -> Result<Resolution, FromSqlError> {
+        match value {
+            "local" => Ok(Resolution::Local),
+            "remote" => Ok(Resolution::Remote),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Conflict {
+    pub id: Option<i64>,
+    pub source: Source,
+    pub record_uid: String,
+    pub field: String,
+    pub local_value: Value,
+    pub remote_value: Value,
+    pub local_clock: Hlc,
+    pub remote_clock: Hlc,
+    // The side that was applied when the conflict was detected
+    pub winner: Resolution,
+    pub resolution: Option<Resolution>,
+    pub created_at: String,
+    pub resolved_at: Option<String>,
+}
+
+const SELECT_CONFLICTS: &str = "SELECT \
+    id, \
+    source, \
+    record_uid, \
+    field, \
+    local_value, \
+    remote_value, \
+    local_clock, \
+    remote_clock, \
+    winner, \
+    resolution, \
+    created_at, \
+    resolved_at \
+    FROM conflicts";
+
+impl Conflict {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        source: Source,
+        record_uid: String,
+        field: String,
+        local_value: Value,
+        remote_value: Value,
+        local_clock: Hlc,
+        remote_clock: Hlc,
+        winner: Resolution,
+    ) -> Self {
+        Self {
+            id: None,
+            source,
+            record_uid,
+            field,
+            local_value,
+            remote_value,
+            local_clock,
+            remote_clock,
+            winner,
+            resolution: None,
+            created_at: Utc::now().to_rfc3339(),
+            resolved_at: None,
+        }
+    }
+
+    fn from_row(row: &Row) -> Result<Conflict, Error> {
+        let source: String = row.get(1)?;
+        let local_value: String = row.get(4)?;
+        let remote_value: String = row.get(5)?;
+        let local_clock: String = row.get(6)?;
+        let remote_clock: String = row.get(7)?;
+        let winner: String = row.get(8)?;
+        let resolution: Option<String> = row.get(9)?;
+
+        Ok(Conflict {
+            id: row.get(0)?,
+            source: Source::from_str(&source)
+                .map_err(|_| corrupt_column(1, format!("Unknown source {}", source)))?,
+            record_uid: row.get(2)?,
+            field: row.get(3)?,
+            local_value: from_str(&local_value).map_err(|e| corrupt_column(4, e))?,
+            remote_value: from_str(&remote_value).map_err(|e| corrupt_column(5, e))?,
+            local_clock: Hlc::from_str(&local_clock).map_err(|e| corrupt_column(6, e))?,
+            remote_clock: Hlc::from_str(&remote_clock).map_err(|e| corrupt_column(7, e))?,
+            winner: Resolution::from_sql(&winner).map_err(|e| corrupt_column(8, e))?,
+            resolution: resolution
+                .map(|r| Resolution::from_sql(&r))
+                .transpose()
+                .map_err(|e| corrupt_column(9, e))?,
+            created_at: row.get(10)?,
+            resolved_at: row.get(11)?,
+        })
+    }
+
+    pub fn create(&self, conn: &Connection) -> Result<(), Error> {
+        let sql = "INSERT INTO conflicts \
+                   (source, record_uid, field, local_value, remote_value, local_clock, remote_clock, winner, created_at) \
+                   VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)";
+
+        conn.execute(
+            sql,
+            (
+                &self.source.to_string(),
+                &self.record_uid,
+                &self.field,
+                &self.local_value.to_string(),
+                &self.remote_value.to_string(),
+                &self.local_clock.to_string(),
+                &self.remote_clock.to_string(),
+                self.winner.to_sql(),
+                &self.created_at,
+            ),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn get(conn: &Connection, id: i64) -> Result<Conflict, Error> {
+        let mut stmt = conn.prepare(&format!("{} WHERE id = ?1", SELECT_CONFLICTS))?;
+
+        stmt.query_row([&id], Conflict::from_row)
+    }
+
+    pub fn list(conn: &Connection, include_resolved: bool) -> Result<Vec<Conflict>, Error> {
+        let mut stmt = conn.prepare(&format!(
+            "{} {} ORDER BY created_at DESC",
+            SELECT_CONFLICTS,
+            if include_resolved {
+                ""
+            } else {
+                "WHERE resolved_at IS NULL"
+            }
+        ))?;
+
+        let conflicts_iter = stmt.query_map([], Conflict::from_row)?;
+
+        conflicts_iter.collect()
+    }
+
+    /*
+     * Keeps the chosen side. Picking the side that lost writes it back as a new
+     * local edit, which then syncs to the other devices like any other change.
+     */
+    pub fn resolve(conn: &Connection, id: i64, resolution: Resolution) -> Result<(), Error> {
+        let conflict = Conflict::get(conn, id)?;
+
+        if conflict.resolved_at.is_some() {
+            return Ok(());
+        }
+
+        if resolution != conflict.winner {
+            let (value, column) = match resolution {
+                Resolution::Local => (conflict.local_value.clone(), 4),
+                Resolution::Remote => (conflict.remote_value.clone(), 5),
+            };
+
+            let uid = &conflict.record_uid;
+            let field = &conflict.field;
+            match conflict.source {
+                Source::File => apply_value::<Files>(conn, uid, field, value, column)?,
+                Source::Directory => apply_value::<Directory>(conn, uid, field, value, column)?,
+                Source::Workspace => apply_value::<Workspace>(conn, uid, field, value, column)?,
+                _ => return Err(Error::InvalidQuery),
+            }
+        }
+
+        conn.execute(
+            "UPDATE conflicts SET resolution = (?1), resolved_at = (?2) WHERE id = (?3)",
+            (resolution.to_sql(), &Utc::now().to_rfc3339(), &id),
+        )?;
+
+        Ok(())
+    }
+}
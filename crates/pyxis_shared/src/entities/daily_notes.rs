@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use chrono::{
+    format::{Item, StrftimeItems},
+    NaiveDate,
+};
+use rusqlite::{Connection, Error};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+use super::{
+    directories::Directory,
+    files::Files,
+    workspaces::{Workspace, WorkspaceSettings},
+};
+
+const DEFAULT_TITLE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_DIR_NAME: &str = "Daily Notes";
+// Longest range listed at once, one entry is built per day
+const MAX_RANGE_DAYS: i64 = 366;
+
+#[derive(Serialize, Deserialize)]
+pub struct DailyNote {
+    pub date: String,
+    pub file: Files,
+}
+
+impl DailyNote {
+    /*
+     * Uids are derived from the workspace and the date so that two devices opening
+     * the same day offline end up with the same note instead of duplicates
+     */
+    fn deterministic_uid(workspace_uid: &str, key: &str) -> String {
+        let name = format!("{}/daily/{}", workspace_uid, key);
+
+        Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes())
+            .simple()
+            .to_string()
+    }
+
+    fn note_uid(workspace_uid: &str, date: &NaiveDate) -> String {
+        DailyNote::deterministic_uid(workspace_uid, &date.to_string())
+    }
+
+    fn dir_uid(workspace_uid: &str) -> String {
+        DailyNote::deterministic_uid(workspace_uid, "directory")
+    }
+
+    // Titles become the last segment of the path, so a '/' would nest the note
+    pub fn validate_format(format: &str) -> Result<(), AppError> {
+        let sample = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap_or_default();
+
+        if format.contains('/')
+            || (!StrftimeItems::new(format).any(|item| item == Item::Error)
+                && sample.format(format).to_string().contains('/'))
+        {
+            return Err(AppError::InvalidInput(format!(
+                "Daily note title format '{}' must not contain '/'!",
+                format
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn title(settings: &WorkspaceSettings, date: &NaiveDate) -> Result<String, AppError> {
+        if let Some(format) = &settings.daily_note_format {
+            DailyNote::validate_format(format)?;
+        }
+
+        let format = match &settings.daily_note_format {
+            Some(format) if !StrftimeItems::new(format).any(|item| item == Item::Error) => format,
+            Some(format) => {
+                eprintln!(
+                    "[Daily Notes] Invalid title format '{}'. Using default.",
+                    format
+                );
+                DEFAULT_TITLE_FORMAT
+            }
+            None => DEFAULT_TITLE_FORMAT,
+        };
+
+        Ok(date.format(format).to_string())
+    }
+
+    fn find_dir(
+        conn: &Connection,
+        workspace_uid: &str,
+        settings: &WorkspaceSettings,
+    ) -> Result<Option<Directory>, Error> {
+        if let Some(dir_uid) = &settings.daily_notes_dir_uid {
+            match Directory::get_by_uid(conn, dir_uid) {
+                Ok(dir) => return Ok(Some(dir)),
+                Err(Error::QueryReturnedNoRows) => {
+                    eprintln!(
+                        "[Daily Notes] Configured directory {} not found. Using default.",
+                        dir_uid
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        match Directory::get_by_uid(conn, &DailyNote::dir_uid(workspace_uid)) {
+            Ok(dir) => return Ok(Some(dir)),
+            Err(Error::QueryReturnedNoRows) => {}
+            Err(e) => return Err(e),
+        }
+
+        match Directory::get_by_path(
+            conn,
+            format!("/{}", DEFAULT_DIR_NAME),
+            workspace_uid.to_owned(),
+        ) {
+            Ok(id) => Ok(Some(Directory::get(conn, id)?)),
+            Err(Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn open(
+        conn: &Connection,
+        workspace_uid: &str,
+        date: NaiveDate,
+    ) -> Result<Files, AppError> {
+        let settings = Workspace::get_settings(conn, workspace_uid.to_owned())?;
+
+        let dir = match DailyNote::find_dir(conn, workspace_uid, &settings)? {
+            Some(dir) => dir,
+            None => {
+                let dir = Directory::new(
+                    String::from(DEFAULT_DIR_NAME),
+                    workspace_uid.to_owned(),
+                    format!("/{}", DEFAULT_DIR_NAME),
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(DailyNote::dir_uid(workspace_uid)),
+                    None,
+                );
+                dir.create(conn)?;
+                dir
+            }
+        };
+
+        let uid = DailyNote::note_uid(workspace_uid, &date);
+        match Files::get_by_uid(conn, &uid) {
+            Ok(file) => return Ok(file),
+            Err(Error::QueryReturnedNoRows) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let title = DailyNote::title(&settings, &date)?;
+        let path = format!("{}/{}", dir.path, title);
+
+        // A note with the same title may have been created by hand
+        match Files::get_by_path(conn, path.clone(), workspace_uid.to_owned()) {
+            Ok(id) => return Ok(Files::get(conn, id)?),
+            Err(Error::QueryReturnedNoRows) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let file = Files::new(
+            Some(dir.uid),
+            path,
+            title,
+            vec![],
+            vec![],
+            workspace_uid.to_owned(),
+            None,
+            None,
+            None,
+            Some(uid),
+            None,
+        );
+        file.create(conn)?;
+
+        Ok(file)
+    }
+
+    pub fn list(
+        conn: &Connection,
+        workspace_uid: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<DailyNote>, AppError> {
+        if (to - from).num_days() > MAX_RANGE_DAYS {
+            return Err(AppError::InvalidInput(format!(
+                "Daily notes can be listed for at most {} days at once!",
+                MAX_RANGE_DAYS
+            )));
+        }
+
+        let settings = Workspace::get_settings(conn, workspace_uid.to_owned())?;
+
+        let dir = match DailyNote::find_dir(conn, workspace_uid, &settings)? {
+            Some(dir) => dir,
+            None => return Ok(vec![]),
+        };
+
+        let mut dates_by_uid: HashMap<String, NaiveDate> = HashMap::new();
+        let mut dates_by_title: HashMap<String, NaiveDate> = HashMap::new();
+
+        for date in from.iter_days().take_while(|date| date <= &to) {
+            dates_by_uid.insert(DailyNote::note_uid(workspace_uid, &date), date);
+            dates_by_title.insert(DailyNote::title(&settings, &date)?, date);
+        }
+
+        let mut notes: Vec<DailyNote> = Files::list(conn, workspace_uid.to_owned(), Some(dir.uid))?
+            .into_iter()
+            .filter_map(|file| {
+                dates_by_uid
+                    .get(&file.uid)
+                    .or_else(|| dates_by_title.get(&file.title))
+                    .map(|date| DailyNote {
+                        date: date.to_string(),
+                        file,
+                    })
+            })
+            .collect();
+
+        notes.sort_by(|a, b| a.date.cmp(&b.date));
+
+        Ok(notes)
+    }
+}
@@ -18,11 +18,7 @@ impl Device {
         let mut stmt = conn.prepare("SELECT device_id FROM devices")?;
 
         let device_iter = stmt.query_map([], |row| -> Result<String, Error> { Ok(row.get(0)?) })?;
-        let devices: Vec<String> = device_iter
-            .map(|result| result.expect("[Devices] Error while mapping rows"))
-            .collect();
-
-        Ok(devices)
+        device_iter.collect()
     }
 
     pub fn add(&self, conn: &Connection) -> Result<(), Error> {
@@ -2,6 +2,11 @@ use chrono::Utc;
 use nanoid::nanoid;
 use rusqlite::{Connection, Error, Result, Row};
 
+use super::{
+    conflicts::{field_clocks_from_row, field_clocks_to_sql, FieldClocks, Versioned},
+    queue::Source,
+};
+
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct Directory {
     pub id: Option<i32>,
@@ -13,8 +18,24 @@ pub struct Directory {
     pub path: String,
     pub parent_uid: Option<String>,
     pub synced: Option<bool>,
+    #[serde(default)]
+    pub field_clocks: FieldClocks,
 }
 
+const SELECT_DIRECTORIES: &str = "SELECT \
+    d.id, \
+    d.uid, \
+    d.name, \
+    w.uid as workspace_uid, \
+    d.path, \
+    d.parent_uid, \
+    d.created_at, \
+    d.updated_at, \
+    d.synced, \
+    d.field_clocks \
+    FROM directories d \
+    INNER JOIN workspaces w ON d.workspace_id = w.id";
+
 impl Directory {
     pub fn new(
         name: String,
@@ -39,39 +60,35 @@ impl Directory {
             created_at: created_at.or(Some(String::from(&current_time))).unwrap(),
             updated_at: updated_at.or(Some(String::from(&current_time))).unwrap(),
             synced,
+            field_clocks: FieldClocks::new(),
         }
     }
 
+    fn from_row(row: &Row) -> Result<Directory, Error> {
+        Ok(Directory {
+            id: row.get(0)?,
+            uid: row.get(1)?,
+            name: row.get(2)?,
+            workspace_uid: row.get(3)?,
+            path: row.get(4)?,
+            parent_uid: row.get(5)?,
+            created_at: row.get(6)?,
+            updated_at: row.get(7)?,
+            synced: row.get(8)?,
+            field_clocks: field_clocks_from_row(row, 9)?,
+        })
+    }
+
     pub fn get(conn: &Connection, id: i64) -> Result<Directory, Error> {
-        let mut sql = conn.prepare(
-            "SELECT \
-                d.id, \
-                d.uid, \
-                d.name, \
-                w.uid as workspace_uid, \
-                d.path, \
-                d.parent_uid, \
-                d.created_at, \
-                d.updated_at, \
-                d.synced \
-                FROM directories d \
-                INNER JOIN workspaces w ON d.workspace_id = w.id \
-                WHERE d.id = ?1",
-        )?;
+        let mut sql = conn.prepare(&format!("{} WHERE d.id = ?1", SELECT_DIRECTORIES))?;
 
-        sql.query_row(&[&id], |row| -> Result<Directory, Error> {
-            Ok(Directory {
-                id: row.get(0)?,
-                uid: row.get(1)?,
-                name: row.get(2)?,
-                workspace_uid: row.get(3)?,
-                path: row.get(4)?,
-                parent_uid: row.get(5)?,
-                created_at: row.get(6)?,
-                updated_at: row.get(7)?,
-                synced: row.get(8)?,
-            })
-        })
+        sql.query_row([&id], Directory::from_row)
+    }
+
+    pub fn get_by_uid(conn: &Connection, uid: &str) -> Result<Directory, Error> {
+        let mut sql = conn.prepare(&format!("{} WHERE d.uid = ?1", SELECT_DIRECTORIES))?;
+
+        sql.query_row([uid], Directory::from_row)
     }
 
     pub fn create(&self, conn: &Connection) -> Result<(), Error> {
@@ -82,8 +99,8 @@ impl Directory {
             })?
         };
 
-        let sql = "INSERT INTO directories (name, uid, workspace_id, path, parent_uid, created_at, updated_at, synced) \
-                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)";
+        let sql = "INSERT INTO directories (name, uid, workspace_id, path, parent_uid, created_at, updated_at, synced, field_clocks) \
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)";
 
         conn.execute(
             sql,
@@ -96,6 +113,7 @@ impl Directory {
                 &self.created_at,
                 &self.updated_at,
                 &self.synced,
+                &field_clocks_to_sql(&self.field_clocks),
             ),
         )?;
 
@@ -108,64 +126,24 @@ impl Directory {
         parent_uid: Option<String>,
     ) -> Result<Vec<Self>, Error> {
         let mut stmt = match parent_uid {
-            Some(_) => conn.prepare(
-                "SELECT \
-                    d.id, \
-                    d.uid, \
-                    d.name, \
-                    w.uid as workspace_uid, \
-                    d.path, \
-                    d.parent_uid, \
-                    d.created_at, \
-                    d.updated_at, \
-                    d.synced \
-                    FROM directories d \
-                    INNER JOIN workspaces w ON d.workspace_id = w.id \
-                    WHERE w.uid = ?1 \
-                    AND d.parent_uid = ?2",
-            )?,
-            None => conn.prepare(
-                "SELECT \
-                    d.id, \
-                    d.uid, \
-                    d.name, \
-                    w.uid as workspace_uid, \
-                    d.path, \
-                    d.parent_uid, \
-                    d.created_at, \
-                    d.updated_at, \
-                    d.synced \
-                    FROM directories d \
-                    INNER JOIN workspaces w ON d.workspace_id = w.id \
-                    WHERE w.uid = ?1 \
-                    AND d.parent_uid IS NULL",
-            )?,
+            Some(_) => conn.prepare(&format!(
+                "{} WHERE w.uid = ?1 AND d.parent_uid = ?2",
+                SELECT_DIRECTORIES
+            ))?,
+            None => conn.prepare(&format!(
+                "{} WHERE w.uid = ?1 AND d.parent_uid IS NULL",
+                SELECT_DIRECTORIES
+            ))?,
         };
 
-        let handler = |row: &Row| -> Result<Directory> {
-            Ok(Directory {
-                id: row.get(0)?,
-                uid: row.get(1)?,
-                name: row.get(2)?,
-                workspace_uid: row.get(3)?,
-                path: row.get(4)?,
-                parent_uid: row.get(5)?,
-                created_at: row.get(6)?,
-                updated_at: row.get(7)?,
-                synced: row.get(8)?,
-            })
-        };
+        let handler = Directory::from_row;
 
         let dir_iter = match parent_uid {
             Some(p_id) => stmt.query_map(&[&workspace_uid, &p_id], handler)?,
             None => stmt.query_map(&[&workspace_uid], handler)?,
         };
 
-        let directories: Vec<Directory> = dir_iter
-            .map(|result| result.expect("[Directories] Error while mapping rows"))
-            .collect();
-
-        Ok(directories)
+        dir_iter.collect()
     }
 
     pub fn update(&self, conn: &Connection) -> Result<(), Error> {
@@ -177,8 +155,8 @@ impl Directory {
             })?;
 
         let sql =
-            "UPDATE directories SET name=(?1), workspace_id=(?2), path=(?3), parent_uid=(?4), updated_at=(?5), synced=(?6) \
-             WHERE uid = (?7)";
+            "UPDATE directories SET name=(?1), workspace_id=(?2), path=(?3), parent_uid=(?4), updated_at=(?5), synced=(?6), field_clocks=(?7) \
+             WHERE uid = (?8)";
 
         conn.execute(
             sql,
@@ -189,6 +167,7 @@ impl Directory {
                 &self.parent_uid,
                 &self.updated_at,
                 &self.synced,
+                &field_clocks_to_sql(&self.field_clocks),
                 &self.uid,
             ),
         )?;
@@ -222,3 +201,24 @@ impl Directory {
         })
     }
 }
+
+impl Versioned for Directory {
+    const SOURCE: Source = Source::Directory;
+    const FIELDS: &'static [&'static str] = &["name", "path", "parent_uid"];
+
+    fn uid(&self) -> &str {
+        &self.uid
+    }
+
+    fn field_clocks(&self) -> &FieldClocks {
+        &self.field_clocks
+    }
+
+    fn load(conn: &Connection, uid: &str) -> Result<Self, Error> {
+        Directory::get_by_uid(conn, uid)
+    }
+
+    fn save(&self, conn: &Connection) -> Result<(), Error> {
+        self.update(conn)
+    }
+}
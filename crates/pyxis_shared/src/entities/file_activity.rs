@@ -0,0 +1,66 @@
+use chrono::Utc;
+use rusqlite::{Connection, Error};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Activity {
+    Open,
+    Edit,
+}
+
+/*
+ * Open and edit history is device-local. The table has no listener, so
+ * nothing recorded here is ever enqueued for sync
+ */
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FileActivity {
+    pub file_uid: String,
+    pub opened_at: Option<String>,
+    pub edited_at: Option<String>,
+    pub open_count: i64,
+    pub edit_count: i64,
+}
+
+impl FileActivity {
+    pub fn record(conn: &Connection, file_uid: &str, activity: Activity) -> Result<(), Error> {
+        let mut files_sql = conn.prepare("SELECT id FROM files WHERE uid = ?1")?;
+        let file_id =
+            files_sql.query_row([file_uid], |row| -> Result<i32, Error> { row.get(0) })?;
+
+        let sql = match activity {
+            Activity::Open => {
+                "INSERT INTO file_activity (file_id, opened_at, open_count) VALUES (?1, ?2, 1) \
+                 ON CONFLICT(file_id) \
+                 DO UPDATE SET opened_at=?2, open_count=open_count+1"
+            }
+            Activity::Edit => {
+                "INSERT INTO file_activity (file_id, edited_at, edit_count) VALUES (?1, ?2, 1) \
+                 ON CONFLICT(file_id) \
+                 DO UPDATE SET edited_at=?2, edit_count=edit_count+1"
+            }
+        };
+
+        conn.execute(sql, (&file_id, &Utc::now().to_rfc3339()))?;
+
+        Ok(())
+    }
+
+    pub fn get(conn: &Connection, file_uid: &str) -> Result<FileActivity, Error> {
+        let mut sql = conn.prepare(
+            "SELECT f.uid, a.opened_at, a.edited_at, a.open_count, a.edit_count \
+             FROM file_activity a INNER JOIN files f ON f.id = a.file_id \
+             WHERE f.uid = ?1",
+        )?;
+
+        sql.query_row([file_uid], |row| -> Result<FileActivity, Error> {
+            Ok(FileActivity {
+                file_uid: row.get(0)?,
+                opened_at: row.get(1)?,
+                edited_at: row.get(2)?,
+                open_count: row.get(3)?,
+                edit_count: row.get(4)?,
+            })
+        })
+    }
+}
@@ -3,6 +3,12 @@ use nanoid::nanoid;
 use rusqlite::{Connection, Error, Result, Row};
 use serde_json::{from_str, to_string};
 
+use super::{
+    conflicts::{field_clocks_from_row, field_clocks_to_sql, update_local, FieldClocks, Versioned},
+    queue::Source,
+};
+use crate::error::corrupt_column;
+
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct Link {
     title: String,
@@ -22,8 +28,30 @@ pub struct Files {
     pub workspace_uid: String,
     pub links: Vec<Link>,
     pub synced: Option<bool>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub field_clocks: FieldClocks,
 }
 
+const SELECT_FILES: &str = "SELECT \
+    f.id, \
+    f.uid, \
+    w.uid as workspace_uid, \
+    f.path, \
+    f.title, \
+    f.created_at, \
+    f.updated_at, \
+    f.links, \
+    f.tags, \
+    d.uid as dir_uid, \
+    f.synced, \
+    f.pinned, \
+    f.field_clocks \
+    FROM files f \
+    INNER JOIN workspaces w ON f.workspace_id = w.id \
+    LEFT JOIN directories d ON f.dir_id = d.id";
+
 pub fn val_or_else<'a, T>(val: &'a Option<T>, value: &'a str, else_value: &'a str) -> &'a str {
     if let Some(_) = val {
         value
@@ -60,50 +88,45 @@ impl Files {
             links,
             tags,
             synced,
+            pinned: false,
+            field_clocks: FieldClocks::new(),
         }
     }
 
+    fn from_row(row: &Row) -> Result<Files, Error> {
+        let links: String = row.get(7)?;
+        let links: Vec<Link> = from_str(&links).map_err(|e| corrupt_column(7, e))?;
+
+        let tags: String = row.get(8)?;
+        let tags: Vec<String> = from_str(&tags).map_err(|e| corrupt_column(8, e))?;
+
+        Ok(Files {
+            id: row.get(0)?,
+            uid: row.get(1)?,
+            workspace_uid: row.get(2)?,
+            path: row.get(3)?,
+            title: row.get(4)?,
+            created_at: row.get(5)?,
+            updated_at: row.get(6)?,
+            dir_uid: row.get(9)?,
+            links,
+            tags,
+            synced: row.get(10)?,
+            pinned: row.get(11)?,
+            field_clocks: field_clocks_from_row(row, 12)?,
+        })
+    }
+
     pub fn get(conn: &Connection, id: i64) -> Result<Files, Error> {
-        let mut stmt = conn.prepare(&format!(
-            "SELECT \
-                f.id, \
-                f.uid, \
-                w.uid as workspace_uid, \
-                f.path, \
-                f.title, \
-                f.created_at, \
-                f.updated_at, \
-                f.links, \
-                f.tags, \
-                d.uid as dir_uid, \
-                f.synced \
-                FROM files f \
-                INNER JOIN workspaces w ON f.workspace_id = w.id \
-                LEFT JOIN directories d ON f.dir_id = d.id \
-                WHERE f.id = ?1"
-        ))?;
+        let mut stmt = conn.prepare(&format!("{} WHERE f.id = ?1", SELECT_FILES))?;
 
-        stmt.query_row(&[&id], |row| -> Result<Files, Error> {
-            let links: String = row.get(7)?;
-            let links: Vec<Link> = from_str(&links).expect("[Files] Unable to get links");
+        stmt.query_row([&id], Files::from_row)
+    }
 
-            let tags: String = row.get(8)?;
-            let tags: Vec<String> = from_str(&tags).expect("[Files] Unable to get tags");
+    pub fn get_by_uid(conn: &Connection, uid: &str) -> Result<Files, Error> {
+        let mut stmt = conn.prepare(&format!("{} WHERE f.uid = ?1", SELECT_FILES))?;
 
-            Ok(Files {
-                id: row.get(0)?,
-                uid: row.get(1)?,
-                workspace_uid: row.get(2)?,
-                path: row.get(3)?,
-                title: row.get(4)?,
-                created_at: row.get(5)?,
-                updated_at: row.get(6)?,
-                dir_uid: row.get(9)?,
-                links,
-                tags,
-                synced: row.get(10)?,
-            })
-        })
+        stmt.query_row([uid], Files::from_row)
     }
 
     pub fn create(&self, conn: &Connection) -> Result<(), Error> {
@@ -121,7 +144,7 @@ impl Files {
             })?
         };
 
-        let sql = "INSERT INTO files (uid, dir_id, workspace_id, path, title, created_at, updated_at, links, tags, synced) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)";
+        let sql = "INSERT INTO files (uid, dir_id, workspace_id, path, title, created_at, updated_at, links, tags, synced, pinned, field_clocks) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)";
 
         conn.execute(
             sql,
@@ -136,6 +159,8 @@ impl Files {
                 &to_string(&self.tags).expect("[Files] Unable to convert tags to JSON"),
                 &to_string(&self.links).expect("[Files] Unable to convert links to JSON"),
                 &self.synced,
+                &self.pinned,
+                &field_clocks_to_sql(&self.field_clocks),
             ),
         )?;
 
@@ -158,7 +183,9 @@ impl Files {
                 f.updated_at, \
                 f.links, \
                 f.tags, \
-                f.synced \
+                f.synced, \
+                f.pinned, \
+                f.field_clocks \
                 {} \
                 FROM files f \
                 INNER JOIN workspaces w ON f.workspace_id = w.id \
@@ -172,10 +199,10 @@ impl Files {
 
         let handler = |row: &Row| -> Result<Files> {
             let links: String = row.get(7)?;
-            let links: Vec<Link> = from_str(&links).expect("[Files] Unable to get links");
+            let links: Vec<Link> = from_str(&links).map_err(|e| corrupt_column(7, e))?;
 
             let tags: String = row.get(8)?;
-            let tags: Vec<String> = from_str(&tags).expect("[Files] Unable to get tags");
+            let tags: Vec<String> = from_str(&tags).map_err(|e| corrupt_column(8, e))?;
 
             Ok(Files {
                 id: row.get(0)?,
@@ -188,11 +215,13 @@ impl Files {
                 links,
                 tags,
                 dir_uid: if let Some(_) = &dir_uid {
-                    Some(row.get(10)?)
+                    Some(row.get(12)?)
                 } else {
                     None
                 },
                 synced: row.get(9)?,
+                pinned: row.get(10)?,
+                field_clocks: field_clocks_from_row(row, 11)?,
             })
         };
 
@@ -201,11 +230,7 @@ impl Files {
             None => stmt.query_map(&[&workspace_uid], handler)?,
         };
 
-        let files: Vec<Files> = files_iter
-            .map(|result| result.expect("[Files] Error while mapping rows"))
-            .collect();
-
-        Ok(files)
+        files_iter.collect()
     }
 
     pub fn update(&self, conn: &Connection) -> Result<(), Error> {
@@ -218,7 +243,7 @@ impl Files {
         };
 
         let sql =
-            "UPDATE files SET dir_id=(?1), title=(?2), path=(?3), links=(?4), tags=(?5), updated_at=(?6), synced=(?7) WHERE uid = (?8)";
+            "UPDATE files SET dir_id=(?1), title=(?2), path=(?3), links=(?4), tags=(?5), updated_at=(?6), synced=(?7), pinned=(?8), field_clocks=(?9) WHERE uid = (?10)";
 
         conn.execute(
             sql,
@@ -230,6 +255,8 @@ impl Files {
                 &to_string(&self.tags).expect("[Files] Unable to serialize tags"),
                 &self.updated_at,
                 &self.synced,
+                &self.pinned,
+                &field_clocks_to_sql(&self.field_clocks),
                 &self.uid,
             ),
         )?;
@@ -244,7 +271,7 @@ impl Files {
     ) -> Result<i64, Error> {
         let mut stmt = conn.prepare(&format!(
             "SELECT \
-                f.id, \
+                f.id \
                 FROM files f \
                 INNER JOIN workspaces w ON f.workspace_id = w.id \
                 WHERE f.path = ?1 \
@@ -256,6 +283,46 @@ impl Files {
         })
     }
 
+    pub fn set_pinned(
+        conn: &Connection,
+        uid: String,
+        pinned: bool,
+        synced: Option<bool>,
+    ) -> Result<(), Error> {
+        let mut file = Files::get_by_uid(conn, &uid)?;
+        file.pinned = pinned;
+        file.updated_at = Utc::now().to_rfc3339();
+        file.synced = synced;
+
+        update_local(conn, file)?;
+
+        Ok(())
+    }
+
+    pub fn list_pinned(conn: &Connection, workspace_uid: String) -> Result<Vec<Self>, Error> {
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE w.uid = ?1 AND f.pinned = 1 ORDER BY f.title ASC",
+            SELECT_FILES
+        ))?;
+
+        let files_iter = stmt.query_map([&workspace_uid], Files::from_row)?;
+
+        files_iter.collect()
+    }
+
+    pub fn list_recent(conn: &Connection, limit: i64) -> Result<Vec<Self>, Error> {
+        let mut stmt = conn.prepare(&format!(
+            "{} INNER JOIN file_activity a ON a.file_id = f.id \
+             ORDER BY MAX(COALESCE(a.opened_at, ''), COALESCE(a.edited_at, '')) DESC \
+             LIMIT ?1",
+            SELECT_FILES
+        ))?;
+
+        let files_iter = stmt.query_map([&limit], Files::from_row)?;
+
+        files_iter.collect()
+    }
+
     pub fn delete(uid: String, conn: &Connection) -> Result<(), Error> {
         let sql = "DELETE FROM files WHERE uid = (?1)";
         conn.execute(sql, (uid,))?;
@@ -263,3 +330,25 @@ impl Files {
         Ok(())
     }
 }
+
+impl Versioned for Files {
+    const SOURCE: Source = Source::File;
+    const FIELDS: &'static [&'static str] =
+        &["title", "path", "dir_uid", "tags", "links", "pinned"];
+
+    fn uid(&self) -> &str {
+        &self.uid
+    }
+
+    fn field_clocks(&self) -> &FieldClocks {
+        &self.field_clocks
+    }
+
+    fn load(conn: &Connection, uid: &str) -> Result<Self, Error> {
+        Files::get_by_uid(conn, uid)
+    }
+
+    fn save(&self, conn: &Connection) -> Result<(), Error> {
+        self.update(conn)
+    }
+}
@@ -0,0 +1,45 @@
+use rusqlite::{Connection, Error, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::error::corrupt_column;
+
+/*
+ * How the sync sidecar reaches the server. Stored as a single row so that a
+ * shipped build can be pointed at a self-hosted server without a rebuild.
+ * Unset fields fall back to what the sidecar was built with.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct NetworkSettings {
+    pub server_url: Option<String>,
+    pub proxy_url: Option<String>,
+    // PEM encoded certificate trusted in addition to the system roots
+    pub ca_certificate: Option<String>,
+    pub connect_timeout_seconds: Option<u64>,
+    pub request_timeout_seconds: Option<u64>,
+}
+
+impl NetworkSettings {
+    pub fn get(conn: &Connection) -> Result<NetworkSettings, Error> {
+        let mut stmt = conn.prepare("SELECT settings FROM network_settings WHERE id = 1")?;
+        let settings = stmt
+            .query_row([], |row| {
+                let json_str: String = row.get(0)?;
+                serde_json::from_str::<NetworkSettings>(&json_str).map_err(|e| corrupt_column(0, e))
+            })
+            .optional()?;
+
+        Ok(settings.unwrap_or_default())
+    }
+
+    pub fn save(&self, conn: &Connection) -> Result<(), Error> {
+        let json_payload =
+            serde_json::to_string(self).expect("[Network] Failed to serialize to json!");
+
+        conn.execute(
+            "INSERT INTO network_settings (id, settings) VALUES (1, ?1) ON CONFLICT(id) DO UPDATE SET settings=?1",
+            [&json_payload],
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use std::str::FromStr;
+
+use rusqlite::{Connection, Error, Row};
+use serde::{Deserialize, Serialize};
+
+use super::queue::Source;
+use crate::error::corrupt_column;
+
+/*
+ * A change recorded by the triggers on the documents database. The row is written
+ * in the same transaction as the change itself, so a crash can never separate
+ * the two. The sidecar later moves these entries into the listener queue.
+ */
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OutboxEntry {
+    pub id: i64,
+    pub source: Source,
+    pub operation: String,
+    pub row_id: i64,
+    pub uid: Option<String>,
+    pub created_at: String,
+}
+
+/*
+ * (table, has a uid column, order). Tables are listed in the order other devices
+ * need them: a record is captured after the records it points to.
+ */
+const REPUSHED: &[(&str, bool, &str)] = &[
+    ("workspaces", true, "id"),
+    // Parents first, whatever order the directories were created in
+    (
+        "directories",
+        true,
+        "LENGTH(path) - LENGTH(REPLACE(path, '/', '')), id",
+    ),
+    ("files", true, "id"),
+    ("attachments", true, "id"),
+    ("snapshots", false, "id"),
+    ("updates", false, "id"),
+];
+
+impl OutboxEntry {
+    fn from_row(row: &Row) -> Result<OutboxEntry, Error> {
+        let source: String = row.get(1)?;
+
+        Ok(OutboxEntry {
+            id: row.get(0)?,
+            source: Source::from_str(&source)
+                .map_err(|_| corrupt_column(1, format!("Unknown source {}", source)))?,
+            operation: row.get(2)?,
+            row_id: row.get(3)?,
+            uid: row.get(4)?,
+            created_at: row.get(5)?,
+        })
+    }
+
+    pub fn list(conn: &Connection, limit: i64) -> Result<Vec<OutboxEntry>, Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, source, operation, row_id, uid, created_at \
+            FROM outbox \
+            ORDER BY id ASC \
+            LIMIT ?1",
+        )?;
+
+        let entries = stmt
+            .query_map([&limit], OutboxEntry::from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(entries)
+    }
+
+    pub fn remove_until(conn: &Connection, id: i64) -> Result<usize, Error> {
+        conn.execute("DELETE FROM outbox WHERE id <= ?1", [&id])
+    }
+
+    /*
+     * Records every row as inserted again, including rows that came from other
+     * devices, so that the sidecar sends all of it. Deletes are not repeated.
+     */
+    pub fn capture_all(conn: &Connection) -> Result<usize, Error> {
+        let transaction = conn.unchecked_transaction()?;
+        let mut captured = 0;
+
+        for (table, has_uid, order) in REPUSHED {
+            captured += transaction.execute(
+                &format!(
+                    "INSERT INTO outbox (source, operation, row_id, uid, created_at) \
+                    SELECT '{table}', 'insert', id, {}, strftime('%Y-%m-%dT%H:%M:%fZ', 'now') \
+                    FROM {table} ORDER BY {order}",
+                    if *has_uid { "uid" } else { "NULL" },
+                ),
+                (),
+            )?;
+        }
+
+        transaction.commit()?;
+
+        Ok(captured)
+    }
+}
@@ -1,7 +1,10 @@
 use std::str::FromStr;
 
-use rusqlite::{Connection, Error};
+use rusqlite::{Connection, Error, OptionalExtension, Row};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::corrupt_column;
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum Source {
@@ -10,6 +13,7 @@ pub enum Source {
     File,
     Snapshot,
     Update,
+    Attachment,
 }
 
 impl FromStr for Source {
@@ -22,6 +26,7 @@ impl FromStr for Source {
             "files" => Ok(Source::File),
             "snapshots" => Ok(Source::Snapshot),
             "updates" => Ok(Source::Update),
+            "attachments" => Ok(Source::Attachment),
             _ => Err(()),
         }
     }
@@ -35,6 +40,7 @@ impl ToString for Source {
             Source::Directory => String::from("directories"),
             Source::Snapshot => String::from("snapshots"),
             Source::Update => String::from("updates"),
+            Source::Attachment => String::from("attachments"),
         }
     }
 }
@@ -48,8 +54,29 @@ pub struct ListenerQueue {
     pub payload: String,
     pub file_uid: Option<String>,
     pub snapshot_id: Option<i64>,
+    // Last outbox entry this element was built from
+    pub outbox_id: Option<i64>,
+    // Uid of the record the element carries, used to coalesce pending changes
+    pub record_uid: Option<String>,
+    // Generated when the element is queued. Lets the server tell a retry from a new write.
+    pub idempotency_key: Option<String>,
 }
 
+const SELECT_QUEUE: &str = "SELECT \
+    id, \
+    status, \
+    source, \
+    operation, \
+    payload, \
+    file_uid, \
+    snapshot_id, \
+    outbox_id, \
+    record_uid, \
+    idempotency_key \
+    FROM listener_queue";
+
+const NOW: &str = "strftime('%Y-%m-%dT%H:%M:%fZ', 'now')";
+
 impl ListenerQueue {
     pub fn new(
         id: Option<i64>,
@@ -68,15 +95,38 @@ impl ListenerQueue {
             payload,
             file_uid,
             snapshot_id,
+            outbox_id: None,
+            record_uid: None,
+            idempotency_key: None,
         }
     }
 
+    fn from_row(row: &Row) -> Result<ListenerQueue, Error> {
+        let source_str: String = row.get(2)?;
+
+        Ok(ListenerQueue {
+            id: row.get(0)?,
+            status: row.get(1)?,
+            source: Source::from_str(&source_str)
+                .map_err(|_| corrupt_column(2, format!("Unknown source {}", source_str)))?,
+            operation: row.get(3)?,
+            payload: row.get(4)?,
+            file_uid: row.get(5)?,
+            snapshot_id: row.get(6)?,
+            outbox_id: row.get(7)?,
+            record_uid: row.get(8)?,
+            idempotency_key: row.get(9)?,
+        })
+    }
+
     pub fn enqueue(&self, conn: &Connection) -> Result<(), Error> {
-        let insert_sql =
-            "INSERT INTO listener_queue (status, source, operation, payload, file_uid, snapshot_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)";
+        let insert_sql = format!(
+            "INSERT INTO listener_queue (status, source, operation, payload, file_uid, snapshot_id, outbox_id, record_uid, idempotency_key, queued_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, {})",
+            NOW
+        );
 
         conn.execute(
-            insert_sql,
+            &insert_sql,
             (
                 "init",
                 &self.source.to_string(),
@@ -84,37 +134,121 @@ impl ListenerQueue {
                 &self.payload,
                 &self.file_uid,
                 &self.snapshot_id,
+                &self.outbox_id,
+                &self.record_uid,
+                &Uuid::new_v4().to_string(),
+            ),
+        )?;
+
+        Ok(())
+    }
+
+    // Folds a newer change to the same record into this element while it is still unsent
+    pub fn coalesce(&self, conn: &Connection) -> Result<(), Error> {
+        // The element may have been sent before a reset, so the new payload takes a new key
+        conn.execute(
+            &format!(
+                "UPDATE listener_queue SET operation=(?1), payload=(?2), outbox_id=(?3), idempotency_key=(?4), queued_at={} WHERE id=(?5) AND status='init'",
+                NOW
+            ),
+            (
+                &self.operation,
+                &self.payload,
+                &self.outbox_id,
+                &Uuid::new_v4().to_string(),
+                &self.id,
             ),
         )?;
 
         Ok(())
     }
 
-    pub fn dequeue(conn: &Connection, id: i64) -> Result<ListenerQueue, Error> {
+    pub fn last_for_record(
+        conn: &Connection,
+        source: &Source,
+        record_uid: &str,
+    ) -> Result<Option<ListenerQueue>, Error> {
+        let mut sql = conn.prepare(&format!(
+            "{} WHERE source = ?1 AND record_uid = ?2 ORDER BY id DESC LIMIT 1",
+            SELECT_QUEUE
+        ))?;
+
+        sql.query_row((&source.to_string(), record_uid), ListenerQueue::from_row)
+            .optional()
+    }
+
+    // Whether a record change other than file content was queued after this element
+    pub fn has_records_after(&self, conn: &Connection) -> Result<bool, Error> {
+        conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM listener_queue WHERE id > ?1 AND source NOT IN (?2, ?3))",
+            (
+                &self.id,
+                &Source::Snapshot.to_string(),
+                &Source::Update.to_string(),
+            ),
+            |row| row.get(0),
+        )
+    }
+
+    // Drops the unsent snapshots and updates of a file
+    pub fn remove_file_content(conn: &Connection, file_uid: &str) -> Result<usize, Error> {
+        conn.execute(
+            "DELETE FROM listener_queue WHERE file_uid = ?1 AND source IN (?2, ?3) AND status = 'init'",
+            (
+                file_uid,
+                &Source::Snapshot.to_string(),
+                &Source::Update.to_string(),
+            ),
+        )
+    }
+
+    pub fn last_for_snapshot(
+        conn: &Connection,
+        file_uid: &str,
+        snapshot_id: i64,
+    ) -> Result<Option<ListenerQueue>, Error> {
+        let mut sql = conn.prepare(&format!(
+            "{} WHERE source = ?1 AND file_uid = ?2 AND snapshot_id = ?3 ORDER BY id DESC LIMIT 1",
+            SELECT_QUEUE
+        ))?;
+
+        sql.query_row(
+            (&Source::Update.to_string(), file_uid, &snapshot_id),
+            ListenerQueue::from_row,
+        )
+        .optional()
+    }
+
+    /*
+     * Elements stay in the queue until no change has been folded into them for
+     * `settle_seconds`. The queue is sent in order, so a newer element never
+     * overtakes an unsettled one.
+     */
+    pub fn dequeue(
+        conn: &Connection,
+        id: i64,
+        settle_seconds: i64,
+    ) -> Result<ListenerQueue, Error> {
         let mut sql = conn.prepare(
-            "SELECT id, status, source, operation, payload, file_uid, snapshot_id \
+            "SELECT \
+                id, status, source, operation, payload, file_uid, snapshot_id, outbox_id, record_uid, idempotency_key, \
+                queued_at IS NULL OR queued_at <= strftime('%Y-%m-%dT%H:%M:%fZ', 'now', ?2) \
             FROM listener_queue \
             WHERE id>?1 \
             ORDER BY ROWID ASC \
             LIMIT 1",
         )?;
 
-        let entry = sql.query_row([&id], |row| -> Result<ListenerQueue, Error> {
-            let source_str: String = row.get(2)?;
-            let file_uid: Option<String> = row.get(5)?;
-            let snapshot_id: Option<i64> = row.get(6)?;
-
-            Ok(ListenerQueue {
-                id: row.get(0)?,
-                status: row.get(1)?,
-                source: Source::from_str(&source_str)
-                    .expect("Failed to convert source from string!"),
-                operation: row.get(3)?,
-                payload: row.get(4)?,
-                file_uid,
-                snapshot_id,
-            })
-        })?;
+        let (entry, settled) = sql.query_row(
+            (&id, &format!("-{} seconds", settle_seconds)),
+            |row| -> Result<(ListenerQueue, bool), Error> {
+                Ok((ListenerQueue::from_row(row)?, row.get(10)?))
+            },
+        )?;
+
+        if !settled {
+            return Err(Error::QueryReturnedNoRows);
+        }
 
         if let Some(id) = entry.id {
             let updated_count = conn.execute(
@@ -131,6 +265,76 @@ impl ListenerQueue {
         Ok(entry)
     }
 
+    pub fn get(conn: &Connection, id: i64) -> Result<ListenerQueue, Error> {
+        let mut sql = conn.prepare(&format!("{} WHERE id = ?1", SELECT_QUEUE))?;
+
+        sql.query_row([&id], ListenerQueue::from_row)
+    }
+
+    pub fn list(
+        conn: &Connection,
+        status: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<ListenerQueue>, Error> {
+        let mut sql = conn.prepare(&format!(
+            "{} WHERE ?1 IS NULL OR status = ?1 ORDER BY id DESC LIMIT ?2",
+            SELECT_QUEUE
+        ))?;
+
+        let entries = sql
+            .query_map((status, &limit), ListenerQueue::from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(entries)
+    }
+
+    pub fn count_by_status(conn: &Connection) -> Result<Vec<(String, i64)>, Error> {
+        let mut sql = conn.prepare(
+            "SELECT status, COUNT(*) FROM listener_queue GROUP BY status ORDER BY status",
+        )?;
+
+        let counts = sql
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(counts)
+    }
+
+    // Makes a failed or stuck element eligible to be sent again in its place
+    pub fn reset(&self, conn: &Connection) -> Result<(), Error> {
+        conn.execute(
+            "UPDATE listener_queue SET status = 'init', queued_at = NULL WHERE id=(?1)",
+            (&self.id,),
+        )?;
+
+        Ok(())
+    }
+
+    /*
+     * Gives every element a new key. Elements sent again after a tracker reset
+     * are then taken as new writes instead of replays of the first ones.
+     */
+    pub fn renew_idempotency_keys(conn: &Connection) -> Result<usize, Error> {
+        let ids = conn
+            .prepare("SELECT id FROM listener_queue")?
+            .query_map([], |row| row.get::<_, i64>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut sql =
+            conn.prepare("UPDATE listener_queue SET idempotency_key = ?1 WHERE id = ?2")?;
+        for id in ids.iter() {
+            sql.execute((&Uuid::new_v4().to_string(), id))?;
+        }
+
+        Ok(ids.len())
+    }
+
+    pub fn last_outbox_id(conn: &Connection) -> Result<Option<i64>, Error> {
+        conn.query_row("SELECT MAX(outbox_id) FROM listener_queue", [], |row| {
+            row.get(0)
+        })
+    }
+
     pub fn remove(&self, conn: &Connection) -> Result<(), Error> {
         conn.execute("DELETE FROM listener_queue WHERE id=(?1)", (&self.id,))?;
 
@@ -0,0 +1,159 @@
+use std::str::FromStr;
+
+use chrono::DateTime;
+use rusqlite::{Connection, Error, Row};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    attachments::Attachment, conflicts::FieldClocks, directories::Directory, files::Files,
+    queue::Source, workspaces::Workspace,
+};
+use crate::error::corrupt_column;
+
+/*
+ * Marks a deleted record. Tombstones are written by the delete triggers before the
+ * row is gone, are sent to other devices in place of the row, and are kept so that
+ * a late insert or update for the same record can be told apart from a new one.
+ *
+ * A delete and an edit made concurrently on two devices are ordered by wall time:
+ * the later one wins and ties go to the delete. An edit that wins keeps the record
+ * on the device that made it, and brings it back on the device that deleted it.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Tombstone {
+    pub uid: String,
+    pub source: Source,
+    pub file_uid: Option<String>,
+    pub deleted_at: String,
+}
+
+impl Tombstone {
+    fn from_row(row: &Row) -> Result<Tombstone, Error> {
+        let source: String = row.get(1)?;
+
+        Ok(Tombstone {
+            uid: row.get(0)?,
+            source: Source::from_str(&source)
+                .map_err(|_| corrupt_column(1, format!("Unknown source {}", source)))?,
+            file_uid: row.get(2)?,
+            deleted_at: row.get(3)?,
+        })
+    }
+
+    pub fn get(conn: &Connection, source: &Source, uid: &str) -> Result<Tombstone, Error> {
+        let mut stmt = conn.prepare(
+            "SELECT uid, source, file_uid, deleted_at \
+            FROM tombstones \
+            WHERE source = ?1 AND uid = ?2",
+        )?;
+
+        stmt.query_row((&source.to_string(), uid), Tombstone::from_row)
+    }
+
+    pub fn create(&self, conn: &Connection) -> Result<(), Error> {
+        conn.execute(
+            "INSERT OR REPLACE INTO tombstones (source, uid, file_uid, deleted_at) VALUES (?1, ?2, ?3, ?4)",
+            (
+                &self.source.to_string(),
+                &self.uid,
+                &self.file_uid,
+                &self.deleted_at,
+            ),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn remove(conn: &Connection, source: &Source, uid: &str) -> Result<(), Error> {
+        conn.execute(
+            "DELETE FROM tombstones WHERE source = ?1 AND uid = ?2",
+            (&source.to_string(), uid),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn millis(timestamp: &str) -> i64 {
+        DateTime::parse_from_rfc3339(timestamp)
+            .map(|time| time.timestamp_millis())
+            .unwrap_or(0)
+    }
+
+    // Wall time of the latest edit to any field
+    pub fn last_edit(clocks: &FieldClocks) -> i64 {
+        clocks
+            .values()
+            .map(|field| field.clock.wall)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /*
+     * Whether a write received from another device may be applied. A write made
+     * after the delete clears the tombstone so the record can be created again.
+     */
+    pub fn admit(
+        conn: &Connection,
+        source: &Source,
+        uid: &str,
+        edited_at: i64,
+    ) -> Result<bool, Error> {
+        match Tombstone::get(conn, source, uid) {
+            Ok(tombstone) if edited_at > Tombstone::millis(&tombstone.deleted_at) => {
+                Tombstone::remove(conn, source, uid)?;
+                Ok(true)
+            }
+            Ok(_) => Ok(false),
+            Err(Error::QueryReturnedNoRows) => Ok(true),
+            Err(e) => Err(e),
+        }
+    }
+
+    /*
+     * Applies a tombstone received from another device. Returns whether the record
+     * was deleted. The tombstone is stored first, which keeps the delete triggers
+     * from sending it back.
+     */
+    pub fn apply_remote(&self, conn: &Connection) -> Result<bool, Error> {
+        let last_edit = match self.source {
+            Source::Workspace => Workspace::get_by_uid(conn, &self.uid)
+                .map(|workspace| Tombstone::last_edit(&workspace.field_clocks)),
+            Source::Directory => Directory::get_by_uid(conn, &self.uid)
+                .map(|dir| Tombstone::last_edit(&dir.field_clocks)),
+            Source::File => Files::get_by_uid(conn, &self.uid)
+                .map(|file| Tombstone::last_edit(&file.field_clocks)),
+            // Attachments are never edited
+            Source::Attachment => Attachment::get_by_uid(conn, &self.uid).map(|_| 0),
+            // Content goes away with its file
+            Source::Snapshot | Source::Update => return Ok(false),
+        };
+
+        let transaction = conn.unchecked_transaction()?;
+
+        match last_edit {
+            Ok(edited_at) if edited_at > Tombstone::millis(&self.deleted_at) => return Ok(false),
+            Ok(_) => {}
+            // Keep the tombstone so that a late insert is not applied
+            Err(Error::QueryReturnedNoRows) => {
+                self.create(&transaction)?;
+                transaction.commit()?;
+                return Ok(false);
+            }
+            Err(e) => return Err(e),
+        }
+
+        self.create(&transaction)?;
+
+        match self.source {
+            Source::Workspace => Workspace::delete(self.uid.clone(), &transaction)?,
+            Source::Directory => Directory::delete(self.uid.clone(), &transaction)?,
+            Source::File => Files::delete(self.uid.clone(), &transaction)?,
+            Source::Attachment => Attachment::delete(&self.uid, &transaction)?,
+            Source::Snapshot | Source::Update => {}
+        }
+
+        transaction.commit()?;
+
+        Ok(true)
+    }
+}
@@ -1,11 +1,12 @@
 use std::str::FromStr;
 
-use rusqlite::{params_from_iter, Connection, Error, ToSql};
+use rusqlite::{params_from_iter, Connection, Error, Row, ToSql};
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use uuid::Uuid;
 
 use super::queue::Source;
+use crate::error::corrupt_column;
 
 #[serde_as]
 #[derive(Serialize, Deserialize, Debug)]
@@ -77,23 +78,51 @@ impl Tracker {
                 .map(|source| Box::new(source.as_str()) as Box<dyn ToSql>),
         );
 
-        stmt.query_row(params_from_iter(params), |row| {
-            let device_id: String = row.get(2)?;
-            let source: String = row.get(3)?;
-            let user_id: String = row.get(4)?;
-
-            Ok(Tracker {
-                id: row.get(0)?,
-                record_id: row.get(1)?,
-                device_id: Uuid::from_str(&device_id)
-                    .expect("Failed to serialize uuid from string"),
-                source: Source::from_str(&source).expect("Failed to serialize source from string!"),
-                user_id: Uuid::from_str(&user_id).expect("Failed to serialize uuid from string"),
-                queue_entry_id: row.get(5)?,
-            })
+        stmt.query_row(params_from_iter(params), Tracker::from_row)
+    }
+
+    fn from_row(row: &Row) -> Result<Tracker, Error> {
+        let device_id: String = row.get(2)?;
+        let source: String = row.get(3)?;
+        let user_id: String = row.get(4)?;
+
+        Ok(Tracker {
+            id: row.get(0)?,
+            record_id: row.get(1)?,
+            device_id: Uuid::from_str(&device_id).map_err(|e| corrupt_column(2, e))?,
+            source: Source::from_str(&source)
+                .map_err(|_| corrupt_column(3, format!("Unknown source {}", source)))?,
+            user_id: Uuid::from_str(&user_id).map_err(|e| corrupt_column(4, e))?,
+            queue_entry_id: row.get(5)?,
         })
     }
 
+    // Records sent by this device and read from every other device
+    pub fn list(conn: &Connection, user_id: Uuid) -> Result<Vec<Tracker>, Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, record_id, device_id, source, user_id, queue_entry_id FROM tracker WHERE user_id=?1 ORDER BY device_id, source",
+        )?;
+
+        let records = stmt
+            .query_map([&user_id.to_string()], Tracker::from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(records)
+    }
+
+    /*
+     * Forgets what a device has sent. Every element in the queue is sent again,
+     * under new idempotency keys so that the server does not take them for
+     * replays. Records whose id now holds something else are moved to the next
+     * free one.
+     */
+    pub fn reset(conn: &Connection, device_id: Uuid, user_id: Uuid) -> Result<usize, Error> {
+        conn.execute(
+            "DELETE FROM tracker WHERE device_id=?1 AND user_id=?2",
+            (&device_id.to_string(), &user_id.to_string()),
+        )
+    }
+
     pub fn add(&self, conn: &Connection) -> Result<(), Error> {
         // Unique constraint on (source, device_id). Replaced on conflict
         let insert_sql =
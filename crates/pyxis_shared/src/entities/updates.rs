@@ -38,9 +38,25 @@ impl Updates {
             Ok(row)
         })?;
 
-        let updates: Vec<Vec<u8>> = updates_iter
-            .map(|result| result.expect("[Updates] Error while mapping rows"))
-            .collect();
+        updates_iter.collect()
+    }
+
+    // Content of the updates to a snapshot written before the given one
+    pub fn list_before(
+        conn: &Connection,
+        file_uid: &str,
+        snapshot_id: i64,
+        id: i64,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let mut sql = conn.prepare(
+            "SELECT u.content FROM updates u INNER JOIN files f on f.id = u.file_id \
+            WHERE f.uid=?1 AND u.snapshot_id=?2 AND u.id<?3 \
+            ORDER BY u.id ASC",
+        )?;
+
+        let updates = sql
+            .query_map((file_uid, &snapshot_id, &id), |row| row.get(0))?
+            .collect::<Result<Vec<Vec<u8>>, _>>()?;
 
         Ok(updates)
     }
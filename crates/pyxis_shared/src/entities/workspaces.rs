@@ -1,6 +1,34 @@
 use chrono::Utc;
 use nanoid::nanoid;
-use rusqlite::{Connection, Error};
+use rusqlite::{Connection, Error, Row};
+use serde_json::{from_str, to_string};
+
+use super::{
+    conflicts::{field_clocks_from_row, field_clocks_to_sql, update_local, FieldClocks, Versioned},
+    queue::Source,
+};
+use crate::error::corrupt_column;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Name,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct WorkspaceSettings {
+    pub default_dir_uid: Option<String>,
+    pub sort_order: SortOrder,
+    pub editor_font: Option<String>,
+    pub spellcheck_language: Option<String>,
+    pub daily_notes_dir_uid: Option<String>,
+    // strftime format used for daily note titles
+    pub daily_note_format: Option<String>,
+}
 
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct Workspace {
@@ -11,9 +39,27 @@ pub struct Workspace {
     pub created_at: String,
     pub updated_at: String,
     pub synced: Option<bool>,
+    // Older payloads from other devices do not carry settings
+    #[serde(default)]
+    pub settings: WorkspaceSettings,
+    #[serde(default)]
+    pub field_clocks: FieldClocks,
+}
+
+const SELECT_WORKSPACES: &str =
+    "SELECT id, uid, name, selected, created_at, updated_at, synced, settings, field_clocks from workspaces";
+
+fn settings_from_row(row: &Row, idx: usize) -> Result<WorkspaceSettings, Error> {
+    let settings: Option<String> = row.get(idx)?;
+
+    match settings {
+        Some(s) => from_str(&s).map_err(|e| corrupt_column(idx, e)),
+        None => Ok(WorkspaceSettings::default()),
+    }
 }
 
 impl Workspace {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         name: String,
         selected: bool,
@@ -22,6 +68,7 @@ impl Workspace {
         updated_at: Option<String>,
         uid: Option<String>,
         synced: Option<bool>,
+        settings: Option<WorkspaceSettings>,
     ) -> Self {
         let current_time = Utc::now().to_rfc3339();
 
@@ -33,25 +80,37 @@ impl Workspace {
             created_at: created_at.or(Some(String::from(&current_time))).unwrap(),
             updated_at: updated_at.or(Some(String::from(&current_time))).unwrap(),
             synced,
+            settings: settings.unwrap_or_default(),
+            field_clocks: FieldClocks::new(),
         }
     }
 
+    fn from_row(row: &Row) -> Result<Workspace, Error> {
+        let selected: i32 = row.get(3)?;
+
+        Ok(Workspace {
+            id: row.get(0)?,
+            uid: row.get(1)?,
+            name: row.get(2)?,
+            selected: selected != 0,
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+            synced: row.get(6)?,
+            settings: settings_from_row(row, 7)?,
+            field_clocks: field_clocks_from_row(row, 8)?,
+        })
+    }
+
     pub fn get(connection: &Connection, id: i64) -> Result<Workspace, Error> {
-        let mut sql = connection.prepare(
-            "SELECT id, uid, name, selected, created_at, updated_at, synced from workspaces WHERE id=?1",
-        )?;
+        let mut sql = connection.prepare(&format!("{} WHERE id=?1", SELECT_WORKSPACES))?;
 
-        sql.query_row(&[&id], |row| -> Result<Workspace, Error> {
-            Ok(Workspace {
-                id: row.get(0)?,
-                uid: row.get(1)?,
-                name: row.get(2)?,
-                selected: row.get(3)?,
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
-                synced: row.get(6)?,
-            })
-        })
+        sql.query_row([&id], Workspace::from_row)
+    }
+
+    pub fn get_by_uid(connection: &Connection, uid: &str) -> Result<Workspace, Error> {
+        let mut sql = connection.prepare(&format!("{} WHERE uid=?1", SELECT_WORKSPACES))?;
+
+        sql.query_row([uid], Workspace::from_row)
     }
 
     pub fn create(&self, conn: &Connection) -> Result<(), Error> {
@@ -60,7 +119,7 @@ impl Workspace {
             conn.execute(&update_sql, ())?;
         }
 
-        let sql = "INSERT INTO workspaces (name, uid, selected, created_at, updated_at, synced) VALUES (?1, ?2, ?3, ?4, ?5, ?6)";
+        let sql = "INSERT INTO workspaces (name, uid, selected, created_at, updated_at, synced, settings, field_clocks) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)";
 
         conn.execute(
             sql,
@@ -71,6 +130,8 @@ impl Workspace {
                 &self.created_at,
                 &self.updated_at,
                 &self.synced,
+                &to_string(&self.settings).expect("[Workspaces] Unable to serialize settings"),
+                &field_clocks_to_sql(&self.field_clocks),
             ),
         )?;
 
@@ -78,28 +139,10 @@ impl Workspace {
     }
 
     pub fn list(conn: &Connection) -> Result<Vec<Self>, Error> {
-        let mut stmt = conn.prepare(
-            "SELECT id, uid, name, selected, created_at, updated_at, synced from workspaces",
-        )?;
-        let workspace_iter = stmt.query_map([], |row| {
-            let selected: i32 = row.get(3)?;
-
-            Ok(Workspace {
-                id: row.get(0)?,
-                uid: row.get(1)?,
-                name: row.get(2)?,
-                selected: selected != 0,
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
-                synced: row.get(6)?,
-            })
-        })?;
-
-        let workspaces: Vec<Workspace> = workspace_iter
-            .map(|result| result.expect("[Workspaces] Error while mapping rows"))
-            .collect();
+        let mut stmt = conn.prepare(SELECT_WORKSPACES)?;
+        let workspace_iter = stmt.query_map([], Workspace::from_row)?;
 
-        Ok(workspaces)
+        workspace_iter.collect()
     }
 
     pub fn update(&self, conn: &Connection) -> Result<(), Error> {
@@ -109,7 +152,7 @@ impl Workspace {
         }
 
         let sql =
-            "UPDATE workspaces SET name = (?1), selected = (?2), updated_at = (?3), synced = (?4) WHERE uid = (?5)";
+            "UPDATE workspaces SET name = (?1), selected = (?2), updated_at = (?3), synced = (?4), settings = (?5), field_clocks = (?6) WHERE uid = (?7)";
 
         conn.execute(
             sql,
@@ -118,6 +161,8 @@ impl Workspace {
                 &(self.selected as i32),
                 &self.updated_at,
                 &self.synced,
+                &to_string(&self.settings).expect("[Workspaces] Unable to serialize settings"),
+                &field_clocks_to_sql(&self.field_clocks),
                 &self.uid,
             ),
         )?;
@@ -125,6 +170,28 @@ impl Workspace {
         Ok(())
     }
 
+    pub fn get_settings(conn: &Connection, uid: String) -> Result<WorkspaceSettings, Error> {
+        let mut stmt = conn.prepare("SELECT settings FROM workspaces WHERE uid = ?1")?;
+
+        stmt.query_row([&uid], |row| settings_from_row(row, 0))
+    }
+
+    pub fn update_settings(
+        conn: &Connection,
+        uid: String,
+        settings: &WorkspaceSettings,
+        synced: Option<bool>,
+    ) -> Result<(), Error> {
+        let mut workspace = Workspace::get_by_uid(conn, &uid)?;
+        workspace.settings = settings.clone();
+        workspace.updated_at = Utc::now().to_rfc3339();
+        workspace.synced = synced;
+
+        update_local(conn, workspace)?;
+
+        Ok(())
+    }
+
     pub fn delete(uid: String, conn: &Connection) -> Result<(), Error> {
         let sql = "DELETE FROM workspaces WHERE uid = ?1";
         conn.execute(sql, (uid,))?;
@@ -143,3 +210,25 @@ impl Workspace {
         stmt.query_row(&[&name], |row| -> Result<i64, Error> { Ok(row.get(0)?) })
     }
 }
+
+impl Versioned for Workspace {
+    const SOURCE: Source = Source::Workspace;
+    // Selection is per device and never synced
+    const FIELDS: &'static [&'static str] = &["name", "settings"];
+
+    fn uid(&self) -> &str {
+        &self.uid
+    }
+
+    fn field_clocks(&self) -> &FieldClocks {
+        &self.field_clocks
+    }
+
+    fn load(conn: &Connection, uid: &str) -> Result<Self, Error> {
+        Workspace::get_by_uid(conn, uid)
+    }
+
+    fn save(&self, conn: &Connection) -> Result<(), Error> {
+        self.update(conn)
+    }
+}
@@ -0,0 +1,107 @@
+use std::{fmt, io};
+
+use rusqlite::{types::Type, ErrorCode};
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+/*
+ * Errors returned by Tauri commands. The frontend receives them as
+ * `{ code, message }`, where the code is stable and meant to be matched on and
+ * the message is meant to be shown.
+ */
+#[derive(Debug)]
+pub enum AppError {
+    NotFound(String),
+    // A unique or foreign key constraint rejected the write
+    ConstraintViolation(String),
+    // Another connection held the database for longer than the busy timeout
+    DatabaseLocked(String),
+    // A row that can't be read back into its entity
+    CorruptRow(String),
+    InvalidInput(String),
+    Storage(String),
+    // The sync worker could not be started or stopped
+    Sidecar(String),
+    Database(String),
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "not_found",
+            AppError::ConstraintViolation(_) => "constraint_violation",
+            AppError::DatabaseLocked(_) => "database_locked",
+            AppError::CorruptRow(_) => "corrupt_row",
+            AppError::InvalidInput(_) => "invalid_input",
+            AppError::Storage(_) => "storage_error",
+            AppError::Sidecar(_) => "sidecar_error",
+            AppError::Database(_) => "database_error",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::NotFound(message)
+            | AppError::ConstraintViolation(message)
+            | AppError::DatabaseLocked(message)
+            | AppError::CorruptRow(message)
+            | AppError::InvalidInput(message)
+            | AppError::Storage(message)
+            | AppError::Sidecar(message)
+            | AppError::Database(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.code(), self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("AppError", 2)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("message", self.message())?;
+        error.end()
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(value: rusqlite::Error) -> Self {
+        match &value {
+            rusqlite::Error::QueryReturnedNoRows => {
+                AppError::NotFound(String::from("Record not found"))
+            }
+            rusqlite::Error::SqliteFailure(error, _) => match error.code {
+                ErrorCode::ConstraintViolation => AppError::ConstraintViolation(value.to_string()),
+                ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked => {
+                    AppError::DatabaseLocked(value.to_string())
+                }
+                _ => AppError::Database(value.to_string()),
+            },
+            rusqlite::Error::FromSqlConversionFailure(..)
+            | rusqlite::Error::InvalidColumnType(..)
+            | rusqlite::Error::IntegralValueOutOfRange(..) => {
+                AppError::CorruptRow(value.to_string())
+            }
+            _ => AppError::Database(value.to_string()),
+        }
+    }
+}
+
+impl From<io::Error> for AppError {
+    fn from(value: io::Error) -> Self {
+        match value.kind() {
+            io::ErrorKind::NotFound => AppError::NotFound(value.to_string()),
+            _ => AppError::Storage(value.to_string()),
+        }
+    }
+}
+
+// For row mappers. A column holding something its entity can't read makes the row corrupt.
+pub fn corrupt_column(column: usize, error: impl fmt::Display) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(column, Type::Text, error.to_string().into())
+}
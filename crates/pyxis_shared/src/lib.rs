@@ -1,4 +1,5 @@
 pub mod database;
 pub mod entities;
+pub mod error;
 pub mod payload;
 pub mod utils;
@@ -1,10 +1,373 @@
-use serde::{Deserialize, Serialize};
+use std::{error::Error, fmt, io::Read, str::FromStr};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::entities::{
+    attachments::Attachment, directories::Directory, files::Files, queue::Source,
+    snapshots::Snapshots, tombstones::Tombstone, updates::Updates, workspaces::Workspace,
+};
+
+// Version of the sync messages this build writes. Messages without one are version 1.
+pub const SYNC_MESSAGE_VERSION: u32 = 1;
+
+fn first_version() -> u32 {
+    1
+}
+
+#[derive(Debug)]
+pub enum PayloadError {
+    UnsupportedVersion(u32),
+    UnknownSource(String),
+    // The source never sends this operation
+    UnsupportedOperation {
+        source: String,
+        operation: String,
+    },
+    Malformed {
+        source: String,
+        operation: String,
+        error: String,
+    },
+}
+
+impl fmt::Display for PayloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PayloadError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported sync message version {}", version)
+            }
+            PayloadError::UnknownSource(source) => write!(f, "Unknown source {}", source),
+            PayloadError::UnsupportedOperation { source, operation } => {
+                write!(f, "Unsupported operation {} on {}", operation, source)
+            }
+            PayloadError::Malformed {
+                source,
+                operation,
+                error,
+            } => write!(f, "Malformed {} of {}: {}", operation, source, error),
+        }
+    }
+}
+
+impl Error for PayloadError {}
+
+// What a message does to a record. Deletes carry the tombstone left behind.
+pub enum RecordMessage<T> {
+    Insert(T),
+    Update(T),
+    Delete(Tombstone),
+}
+
+/*
+ * A change to one record as sent between devices, one variant per source, each
+ * carrying the operations that source sends. On the wire a message is still a
+ * source, an operation and the record as a json string, so that version 1 is
+ * what every client already sends.
+ */
+pub enum SyncMessage {
+    Workspace(RecordMessage<Workspace>),
+    Directory(RecordMessage<Directory>),
+    File(RecordMessage<Files>),
+    // Snapshots only go away with their file
+    Snapshot(RecordMessage<Snapshots>),
+    // Updates are only ever added
+    Update(Updates),
+    // Attachments are immutable
+    Attachment(RecordMessage<Attachment>),
+}
+
+impl SyncMessage {
+    pub fn parse(source: &str, operation: &str, payload: &str) -> Result<Self, PayloadError> {
+        let parsed =
+            Source::from_str(source).map_err(|_| PayloadError::UnknownSource(source.to_owned()))?;
+
+        let message = match (&parsed, operation) {
+            (Source::Workspace, _) => SyncMessage::Workspace(record(source, operation, payload)?),
+            (Source::Directory, _) => SyncMessage::Directory(record(source, operation, payload)?),
+            (Source::File, _) => SyncMessage::File(record(source, operation, payload)?),
+            (Source::Snapshot, "insert" | "update") => {
+                SyncMessage::Snapshot(record(source, operation, payload)?)
+            }
+            (Source::Update, "insert") => {
+                SyncMessage::Update(parse_json(source, operation, payload)?)
+            }
+            (Source::Attachment, "insert" | "delete") => {
+                SyncMessage::Attachment(record(source, operation, payload)?)
+            }
+            _ => {
+                return Err(PayloadError::UnsupportedOperation {
+                    source: source.to_owned(),
+                    operation: operation.to_owned(),
+                })
+            }
+        };
+
+        // A tombstone must be for a record of the source it is sent as
+        if let Some(tombstone) = message.tombstone() {
+            if tombstone.source != parsed {
+                return Err(PayloadError::Malformed {
+                    source: source.to_owned(),
+                    operation: operation.to_owned(),
+                    error: format!("Tombstone is for {}", tombstone.source.to_string()),
+                });
+            }
+        }
+
+        Ok(message)
+    }
+
+    pub fn source(&self) -> Source {
+        match self {
+            SyncMessage::Workspace(_) => Source::Workspace,
+            SyncMessage::Directory(_) => Source::Directory,
+            SyncMessage::File(_) => Source::File,
+            SyncMessage::Snapshot(_) => Source::Snapshot,
+            SyncMessage::Update(_) => Source::Update,
+            SyncMessage::Attachment(_) => Source::Attachment,
+        }
+    }
+
+    pub fn operation(&self) -> &'static str {
+        match self {
+            SyncMessage::Workspace(message) => message.operation(),
+            SyncMessage::Directory(message) => message.operation(),
+            SyncMessage::File(message) => message.operation(),
+            SyncMessage::Snapshot(message) => message.operation(),
+            SyncMessage::Update(_) => "insert",
+            SyncMessage::Attachment(message) => message.operation(),
+        }
+    }
+
+    fn tombstone(&self) -> Option<&Tombstone> {
+        match self {
+            SyncMessage::Workspace(RecordMessage::Delete(tombstone))
+            | SyncMessage::Directory(RecordMessage::Delete(tombstone))
+            | SyncMessage::File(RecordMessage::Delete(tombstone))
+            | SyncMessage::Snapshot(RecordMessage::Delete(tombstone))
+            | SyncMessage::Attachment(RecordMessage::Delete(tombstone)) => Some(tombstone),
+            _ => None,
+        }
+    }
+
+    // File the changed record belongs to, if any
+    pub fn file_uid(&self) -> Option<String> {
+        if let Some(tombstone) = self.tombstone() {
+            return tombstone.file_uid.clone();
+        }
+
+        match self {
+            SyncMessage::Snapshot(RecordMessage::Insert(snapshot))
+            | SyncMessage::Snapshot(RecordMessage::Update(snapshot)) => {
+                Some(snapshot.file_uid.clone())
+            }
+            SyncMessage::Update(update) => Some(update.file_uid.clone()),
+            SyncMessage::Attachment(RecordMessage::Insert(attachment))
+            | SyncMessage::Attachment(RecordMessage::Update(attachment)) => {
+                Some(attachment.file_uid.clone())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn snapshot_id(&self) -> Option<i64> {
+        match self {
+            SyncMessage::Update(update) => Some(update.snapshot_id),
+            _ => None,
+        }
+    }
+
+    // The record as sent in the payload
+    pub fn to_payload(&self) -> Result<String, serde_json::Error> {
+        match self {
+            SyncMessage::Workspace(message) => message.to_payload(),
+            SyncMessage::Directory(message) => message.to_payload(),
+            SyncMessage::File(message) => message.to_payload(),
+            SyncMessage::Snapshot(message) => message.to_payload(),
+            SyncMessage::Update(update) => serde_json::to_string(update),
+            SyncMessage::Attachment(message) => message.to_payload(),
+        }
+    }
+}
+
+impl<T: Serialize> RecordMessage<T> {
+    pub fn operation(&self) -> &'static str {
+        match self {
+            RecordMessage::Insert(_) => "insert",
+            RecordMessage::Update(_) => "update",
+            RecordMessage::Delete(_) => "delete",
+        }
+    }
+
+    fn to_payload(&self) -> Result<String, serde_json::Error> {
+        match self {
+            RecordMessage::Insert(record) | RecordMessage::Update(record) => {
+                serde_json::to_string(record)
+            }
+            RecordMessage::Delete(tombstone) => serde_json::to_string(tombstone),
+        }
+    }
+}
+
+fn parse_json<T: DeserializeOwned>(
+    source: &str,
+    operation: &str,
+    payload: &str,
+) -> Result<T, PayloadError> {
+    serde_json::from_str(payload).map_err(|e| PayloadError::Malformed {
+        source: source.to_owned(),
+        operation: operation.to_owned(),
+        error: e.to_string(),
+    })
+}
+
+fn record<T: DeserializeOwned>(
+    source: &str,
+    operation: &str,
+    payload: &str,
+) -> Result<RecordMessage<T>, PayloadError> {
+    match operation {
+        "insert" => Ok(RecordMessage::Insert(parse_json(
+            source, operation, payload,
+        )?)),
+        "update" => Ok(RecordMessage::Update(parse_json(
+            source, operation, payload,
+        )?)),
+        "delete" => Ok(RecordMessage::Delete(parse_json(
+            source, operation, payload,
+        )?)),
+        _ => Err(PayloadError::UnsupportedOperation {
+            source: source.to_owned(),
+            operation: operation.to_owned(),
+        }),
+    }
+}
+
+// Smaller content is sent as is, compression would not pay for its header
+const COMPRESS_ABOVE: usize = 256;
+const ZSTD_LEVEL: i32 = 3;
+/*
+ * Largest update content a payload may decompress to. Payloads come from other
+ * devices, so a few kilobytes of zstd must not be allowed to expand into
+ * gigabytes.
+ */
+pub const MAX_UPDATE_SIZE: usize = 32 * 1024 * 1024;
+
+/*
+ * How the payload of an update is written. `json` is the original format: the
+ * whole update serialised, with its content as an array of numbers. Version 1
+ * holds only the Loro bytes, base64 encoded, and zstd compressed first when that
+ * makes them smaller. Encodings are ordered, so a reader asking for one can read
+ * every encoding before it.
+ */
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub enum UpdateEncoding {
+    #[default]
+    #[serde(rename = "json")]
+    Json,
+    #[serde(rename = "v1")]
+    Base64,
+    #[serde(rename = "v1+zstd")]
+    Zstd,
+}
+
+impl FromStr for UpdateEncoding {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<UpdateEncoding, Self::Err> {
+        match input {
+            "json" => Ok(UpdateEncoding::Json),
+            "v1" => Ok(UpdateEncoding::Base64),
+            "v1+zstd" => Ok(UpdateEncoding::Zstd),
+            _ => Err(()),
+        }
+    }
+}
+
+impl UpdateEncoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UpdateEncoding::Json => "json",
+            UpdateEncoding::Base64 => "v1",
+            UpdateEncoding::Zstd => "v1+zstd",
+        }
+    }
+
+    /*
+     * Writes an update in this encoding, or in the one just before it when zstd
+     * would not make it smaller. Returns the encoding used along with the payload.
+     */
+    pub fn encode(&self, update: &Updates) -> Result<(UpdateEncoding, String), Box<dyn Error>> {
+        match self {
+            UpdateEncoding::Json => Ok((UpdateEncoding::Json, serde_json::to_string(update)?)),
+            UpdateEncoding::Base64 => {
+                Ok((UpdateEncoding::Base64, STANDARD.encode(&update.content)))
+            }
+            UpdateEncoding::Zstd => {
+                if update.content.len() > COMPRESS_ABOVE {
+                    let compressed = zstd::encode_all(update.content.as_slice(), ZSTD_LEVEL)?;
+                    if compressed.len() < update.content.len() {
+                        return Ok((UpdateEncoding::Zstd, STANDARD.encode(compressed)));
+                    }
+                }
+
+                UpdateEncoding::Base64.encode(update)
+            }
+        }
+    }
+
+    // The Loro bytes of a payload written in this encoding
+    pub fn decode_content(&self, payload: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        match self {
+            UpdateEncoding::Json => Ok(serde_json::from_str::<Updates>(payload)?.content),
+            UpdateEncoding::Base64 => Ok(STANDARD.decode(payload)?),
+            UpdateEncoding::Zstd => {
+                let compressed = STANDARD.decode(payload)?;
+                let mut content = Vec::new();
+                zstd::Decoder::new(compressed.as_slice())?
+                    .take(MAX_UPDATE_SIZE as u64 + 1)
+                    .read_to_end(&mut content)?;
+
+                if content.len() > MAX_UPDATE_SIZE {
+                    return Err(format!("Update is larger than {} bytes", MAX_UPDATE_SIZE).into());
+                }
+
+                Ok(content)
+            }
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct UpdateWritePayload {
     pub payload: String,
     pub file_uid: String,
     pub snapshot_id: i64,
+    // Older clients send none
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+    // Older clients send the update as json
+    #[serde(default)]
+    pub encoding: UpdateEncoding,
+    #[serde(default = "first_version")]
+    pub version: u32,
+}
+
+impl UpdateWritePayload {
+    // The Loro bytes of the update, once the payload is known to be readable
+    pub fn content(&self) -> Result<Vec<u8>, PayloadError> {
+        if self.version > SYNC_MESSAGE_VERSION {
+            return Err(PayloadError::UnsupportedVersion(self.version));
+        }
+
+        self.encoding
+            .decode_content(&self.payload)
+            .map_err(|e| PayloadError::Malformed {
+                source: Source::Update.to_string(),
+                operation: String::from("insert"),
+                error: e.to_string(),
+            })
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -14,4 +377,19 @@ pub struct DocumentWritePayload {
     pub record_id: i64,
     pub source: String,
     pub file_uid: Option<String>,
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+    #[serde(default = "first_version")]
+    pub version: u32,
+}
+
+impl DocumentWritePayload {
+    // Checks the payload is a message this build understands
+    pub fn message(&self) -> Result<SyncMessage, PayloadError> {
+        if self.version > SYNC_MESSAGE_VERSION {
+            return Err(PayloadError::UnsupportedVersion(self.version));
+        }
+
+        SyncMessage::parse(&self.source, &self.operation, &self.payload)
+    }
 }
@@ -1 +1,3 @@
+pub mod blob_store;
 pub mod get_machine_id;
+pub mod hlc;
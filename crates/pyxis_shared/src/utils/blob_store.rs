@@ -0,0 +1,88 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::PathBuf,
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use sha2::{Digest, Sha256};
+
+/*
+ * Attachment content is stored on disk, addressed by its sha256 hash, so the
+ * same image pasted into several notes is only written once
+ */
+pub fn get_blob_dir() -> PathBuf {
+    let mut path = dirs::data_local_dir().expect("Failed to find local data directory");
+    path.push("pyxis");
+    path.push("attachments");
+    path
+}
+
+pub fn hash(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub fn is_valid_hash(hash: &str) -> bool {
+    hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+pub fn get_path(hash: &str) -> Result<PathBuf, Error> {
+    if !is_valid_hash(hash) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid blob hash: {}", hash),
+        ));
+    }
+
+    let mut path = get_blob_dir();
+    path.push(&hash[..2]);
+    path.push(hash);
+    Ok(path)
+}
+
+static NEXT_TMP: AtomicU64 = AtomicU64::new(0);
+
+pub fn write(content: &[u8]) -> Result<String, Error> {
+    let hash = hash(content);
+    let path = get_path(&hash)?;
+
+    if path.exists() {
+        return Ok(hash);
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    /*
+     * Written to a temporary file first so a crash never leaves a partial blob.
+     * The app and the sync worker may write the same blob at once, so every
+     * write gets its own file.
+     */
+    let tmp_path = path.with_extension(format!(
+        "{}-{}.tmp",
+        process::id(),
+        NEXT_TMP.fetch_add(1, Ordering::Relaxed)
+    ));
+    if let Err(e) = fs::write(&tmp_path, content).and_then(|_| fs::rename(&tmp_path, &path)) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+
+    Ok(hash)
+}
+
+pub fn read(hash: &str) -> Result<Vec<u8>, Error> {
+    fs::read(get_path(hash)?)
+}
+
+pub fn remove(hash: &str) -> Result<(), Error> {
+    match fs::remove_file(get_path(hash)?) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+    sync::{Mutex, OnceLock},
+};
+
+use chrono::Utc;
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+
+use super::get_machine_id::get_machine_id;
+
+/*
+ * Hybrid logical clock. Orders edits by wall time while staying monotonic
+ * when the system clock goes backwards or lags behind another device.
+ * Ties on wall time and counter are broken by the node id.
+ */
+#[derive(
+    Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, SerializeDisplay, DeserializeFromStr,
+)]
+pub struct Hlc {
+    pub wall: i64,
+    pub counter: u32,
+    pub node: String,
+}
+
+static LAST: Mutex<(i64, u32)> = Mutex::new((0, 0));
+static NODE: OnceLock<String> = OnceLock::new();
+
+fn node() -> String {
+    NODE.get_or_init(|| get_machine_id().simple().to_string())
+        .clone()
+}
+
+impl Hlc {
+    pub fn now() -> Hlc {
+        let wall = Utc::now().timestamp_millis();
+        let mut last = LAST.lock().expect("[HLC] Failed to acquire lock");
+
+        if wall > last.0 {
+            *last = (wall, 0);
+        } else {
+            last.1 += 1;
+        }
+
+        Hlc {
+            wall: last.0,
+            counter: last.1,
+            node: node(),
+        }
+    }
+
+    // Moves the local clock past a clock seen on another device or on disk
+    pub fn observe(other: &Hlc) {
+        let mut last = LAST.lock().expect("[HLC] Failed to acquire lock");
+
+        if (other.wall, other.counter) > *last {
+            *last = (other.wall, other.counter);
+        }
+    }
+}
+
+impl Display for Hlc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:015}-{:06}-{}", self.wall, self.counter, self.node)
+    }
+}
+
+impl FromStr for Hlc {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, '-');
+
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(wall), Some(counter), Some(node)) => Ok(Hlc {
+                wall: wall.parse().map_err(|_| format!("Invalid clock: {}", s))?,
+                counter: counter
+                    .parse()
+                    .map_err(|_| format!("Invalid clock: {}", s))?,
+                node: node.to_owned(),
+            }),
+            _ => Err(format!("Invalid clock: {}", s)),
+        }
+    }
+}
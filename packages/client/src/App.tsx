@@ -141,11 +141,9 @@ function App() {
         localFeatures,
       );
 
-      const deviceIds = devicesResponse?.devices;
       await addDevices(devicesResponse?.devices ?? []);
-      const devicesToSync = deviceIds?.filter((id) => id !== config.deviceId);
       try {
-        await syncDocuments(config.userId!, devicesToSync ?? []);
+        await syncDocuments(config.userId!);
       } catch (e) {
         console.error("Failed to sync. Error: ", e);
       }
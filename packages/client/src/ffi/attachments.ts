@@ -0,0 +1,98 @@
+import { invoke } from "./invoke";
+
+export type Attachment = {
+  id?: number;
+  uid: string;
+  file_uid: string;
+  name: string;
+  mime_type: string;
+  hash: string;
+  size: number;
+  created_at: string;
+  synced?: boolean;
+};
+
+type Args = {
+  add_attachment: {
+    fileUid: string;
+    name: string;
+    mimeType: string;
+    content: Uint8Array;
+    uid?: string;
+    createdAt?: string;
+    synced?: boolean;
+  };
+  get_attachment: { uid: string };
+  list_attachments: { fileUid: string };
+  delete_attachment: { uid: string };
+};
+
+export const attachmentUrl = (uid: string) =>
+  `attachment://localhost/${encodeURIComponent(uid)}`;
+
+export const addAttachment = async (
+  fileUid: string,
+  name: string,
+  mimeType: string,
+  content: Uint8Array,
+  uid?: string,
+  createdAt?: string,
+  synced?: boolean,
+) => {
+  try {
+    const attachment = await invoke<Args, Attachment | null>()(
+      "add_attachment",
+      {
+        fileUid,
+        name,
+        mimeType,
+        content,
+        uid,
+        createdAt,
+        synced,
+      },
+    );
+
+    if (!attachment) {
+      throw new Error("Empty response!");
+    }
+
+    return attachment;
+  } catch (e) {
+    console.error("[Attachment] Create failed!", e);
+    throw e;
+  }
+};
+
+export const getAttachment = async (uid: string) => {
+  try {
+    return (
+      (await invoke<Args, Attachment | null>()("get_attachment", { uid })) ??
+      undefined
+    );
+  } catch (e) {
+    console.error("[Attachment] Failed to fetch!", e);
+  }
+};
+
+export const getAttachments = async (fileUid: string) => {
+  try {
+    return (
+      (await invoke<Args, Array<Attachment> | null>()("list_attachments", {
+        fileUid,
+      })) ?? []
+    );
+  } catch (e) {
+    console.error("[Attachment] Failed to fetch!", e);
+    throw e;
+  }
+};
+
+export const deleteAttachment = async (uid: string) => {
+  try {
+    await invoke<Args, void>()("delete_attachment", { uid });
+  } catch (e) {
+    console.error("[Attachment] Failed to delete!", e);
+    throw e;
+  }
+};
@@ -0,0 +1,64 @@
+import { invoke } from "./invoke";
+
+export type BootstrapStatus = "requested" | "running" | "finished" | "failed";
+
+export type BootstrapProgress = {
+  device_id: string;
+  source: string;
+  record_id: number;
+};
+
+export type Bootstrap = {
+  status: BootstrapStatus;
+  requested_at: string;
+  finished_at?: string;
+  error?: string;
+  progress: Array<BootstrapProgress>;
+};
+
+type Args = {
+  request_bootstrap: never;
+  get_bootstrap: never;
+  repush_all: never;
+};
+
+// Downloads everything from the server. Resumes a bootstrap that did not finish.
+export const requestBootstrap = async () => {
+  try {
+    await invoke<Args, void>()("request_bootstrap", {} as never);
+
+    return true;
+  } catch (e) {
+    console.error("[Bootstrap] Failed to request!", e);
+    return false;
+  }
+};
+
+export const getBootstrap = async (): Promise<Bootstrap | undefined> => {
+  try {
+    return (
+      (await invoke<Args, Bootstrap | null>()("get_bootstrap", {} as never)) ??
+      undefined
+    );
+  } catch (e) {
+    console.error("[Bootstrap] Failed to get status!", e);
+  }
+};
+
+// Sends every local record to the server again. Resolves to how many were queued.
+export const repushAll = async () => {
+  try {
+    const count = await invoke<Args, number | null>()(
+      "repush_all",
+      {} as never,
+    );
+
+    if (count == null) {
+      throw new Error("Empty response!");
+    }
+
+    return count;
+  } catch (e) {
+    console.error("[Bootstrap] Failed to re-push records!", e);
+  }
+};
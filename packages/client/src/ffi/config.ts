@@ -26,23 +26,29 @@ export type ConfigRecord = {
   config?: Config;
 };
 
+export type NetworkSettings = {
+  server_url?: string;
+  proxy_url?: string;
+  ca_certificate?: string;
+  connect_timeout_seconds?: number;
+  request_timeout_seconds?: number;
+};
+
 type Args = {
   add_user_data: Config;
   remove_user_data: { userId: string };
   get_config: { userId: string };
   get_logged_in_user: never;
   get_device_id: never;
+  get_network_settings: never;
+  set_network_settings: { settings: NetworkSettings };
 };
 
 export const addUserData = async (payload: Config) => {
   try {
-    const res = await invoke<Args, boolean>()("add_user_data", payload);
-
-    if (!res) {
-      return;
-    }
+    await invoke<Args, void>()("add_user_data", payload);
 
-    return res;
+    return true;
   } catch (e) {
     console.error("[Configuration] Failed to add config to store!", e);
   }
@@ -50,13 +56,9 @@ export const addUserData = async (payload: Config) => {
 
 export const removeUserData = async (userId: string) => {
   try {
-    const res = await invoke<Args, boolean>()("remove_user_data", { userId });
+    await invoke<Args, void>()("remove_user_data", { userId });
 
-    if (!res) {
-      throw "Unknown failure!";
-    }
-
-    return res;
+    return true;
   } catch (e) {
     console.error("[Configuration] Failed to remove data from store!", e);
   }
@@ -125,3 +127,26 @@ export const getDeviceId = async () => {
     throw new Error("Failed to get device id!");
   }
 };
+
+export const getNetworkSettings = async (): Promise<
+  NetworkSettings | undefined
+> => {
+  try {
+    return await invoke<Args, NetworkSettings>()(
+      "get_network_settings",
+      {} as never,
+    );
+  } catch (e) {
+    console.error("[Configuration] Failed to get network settings!", e);
+  }
+};
+
+export const setNetworkSettings = async (settings: NetworkSettings) => {
+  try {
+    await invoke<Args, void>()("set_network_settings", { settings });
+
+    return true;
+  } catch (e) {
+    console.error("[Configuration] Failed to save network settings!", e);
+  }
+};
@@ -0,0 +1,54 @@
+import { invoke } from "./invoke";
+
+export type FieldClock = {
+  clock: string;
+  prev?: string;
+  // Latest clock seen from each device, by node
+  seen?: Record<string, string>;
+};
+
+export type FieldClocks = Record<string, FieldClock>;
+
+export type Resolution = "local" | "remote";
+
+export type Conflict = {
+  id: number;
+  source: "Workspace" | "Directory" | "File";
+  record_uid: string;
+  field: string;
+  local_value: unknown;
+  remote_value: unknown;
+  local_clock: string;
+  remote_clock: string;
+  winner: Resolution;
+  resolution?: Resolution;
+  created_at: string;
+  resolved_at?: string;
+};
+
+type Args = {
+  list_conflicts: { includeResolved?: boolean };
+  resolve_conflict: { id: number; resolution: Resolution };
+};
+
+export const getConflicts = async (includeResolved?: boolean) => {
+  try {
+    return (
+      (await invoke<Args, Array<Conflict> | null>()("list_conflicts", {
+        includeResolved,
+      })) ?? []
+    );
+  } catch (e) {
+    console.error("[Conflict] Failed to fetch!", e);
+    throw e;
+  }
+};
+
+export const resolveConflict = async (id: number, resolution: Resolution) => {
+  try {
+    await invoke<Args, void>()("resolve_conflict", { id, resolution });
+  } catch (e) {
+    console.error("[Conflict] Failed to resolve!", e);
+    throw e;
+  }
+};
@@ -0,0 +1,49 @@
+import type { File } from "./files";
+import { invoke } from "./invoke";
+
+export type DailyNote = {
+  date: string;
+  file: File;
+};
+
+type Args = {
+  open_daily_note: { workspaceUid: string; date: string };
+  list_daily_notes: { workspaceUid: string; from: string; to: string };
+};
+
+export const openDailyNote = async (workspaceUid: string, date: string) => {
+  try {
+    const file = await invoke<Args, File | null>()("open_daily_note", {
+      workspaceUid,
+      date,
+    });
+
+    if (!file) {
+      throw new Error("Empty response!");
+    }
+
+    return file;
+  } catch (e) {
+    console.error("[Daily Notes] Failed to open!", e);
+    throw e;
+  }
+};
+
+export const listDailyNotes = async (
+  workspaceUid: string,
+  from: string,
+  to: string,
+) => {
+  try {
+    return (
+      (await invoke<Args, Array<DailyNote> | null>()("list_daily_notes", {
+        workspaceUid,
+        from,
+        to,
+      })) ?? []
+    );
+  } catch (e) {
+    console.error("[Daily Notes] Failed to fetch!", e);
+    throw e;
+  }
+};
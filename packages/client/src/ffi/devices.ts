@@ -9,7 +9,7 @@ type Args = {
 
 export const addDevices = async (deviceIds: DeviceIds) => {
   try {
-    await invoke<Args, boolean>()("add_devices", { deviceIds });
+    await invoke<Args, void>()("add_devices", { deviceIds });
   } catch (e) {
     console.error("[Device] Failed to add devices!", e);
   }
@@ -1,3 +1,4 @@
+import type { FieldClocks } from "./conflicts";
 import { invoke } from "./invoke";
 
 export type Directory = {
@@ -10,6 +11,7 @@ export type Directory = {
   created_at: string;
   updated_at: string;
   synced?: boolean;
+  field_clocks?: FieldClocks;
 };
 
 type Args = {
@@ -33,6 +35,7 @@ type Args = {
     path: string;
     parentUid?: string;
     synced?: boolean;
+    fieldClocks?: FieldClocks;
   };
 };
 
@@ -88,13 +91,9 @@ export const getDirs = async (workspaceUid: string, parentUid?: string) => {
 
 export const deleteDir = async (uid: string) => {
   try {
-    if (
-      !(await invoke<Args, boolean>()("delete_dir", {
-        uid,
-      }))
-    ) {
-      throw new Error("Empty Response!");
-    }
+    await invoke<Args, void>()("delete_dir", {
+      uid,
+    });
 
     return;
   } catch (e) {
@@ -121,6 +120,7 @@ export const updateDir = async (
   path: string,
   parentUid?: string,
   synced?: boolean,
+  fieldClocks?: FieldClocks,
 ) => {
   try {
     const directory = await invoke<Args, Directory | null>()("update_dir", {
@@ -130,6 +130,7 @@ export const updateDir = async (
       path,
       parentUid,
       synced,
+      fieldClocks,
     });
 
     if (!directory) {
@@ -1,3 +1,4 @@
+import type { FieldClocks } from "./conflicts";
 import { invoke } from "./invoke";
 
 export type Link = {
@@ -17,8 +18,12 @@ export type File = {
   tags: Array<string>;
   links: Array<Link>;
   synced?: boolean;
+  pinned?: boolean;
+  field_clocks?: FieldClocks;
 };
 
+export type FileActivity = "open" | "edit";
+
 type Args = {
   list_files: { dirUid?: string; workspaceUid: string };
   create_file: {
@@ -32,6 +37,7 @@ type Args = {
     created_at?: string;
     updated_at?: string;
     synced?: boolean;
+    pinned?: boolean;
   };
   get_file_id: {
     path: string;
@@ -47,7 +53,14 @@ type Args = {
     links: Array<Link>;
     tags: Array<string>;
     synced?: boolean;
+    pinned?: boolean;
+    fieldClocks?: FieldClocks;
   };
+  pin_file: { uid: string };
+  unpin_file: { uid: string };
+  list_pinned_files: { workspaceUid: string };
+  list_recent_files: { limit: number };
+  record_file_activity: { fileUid: string; activity: FileActivity };
 };
 
 export const createFile = async (
@@ -117,13 +130,9 @@ export const getFileByPath = async (path: string, workspaceUid: string) => {
 
 export const deleteFile = async (uid: string) => {
   try {
-    if (
-      !(await invoke<Args, boolean>()("delete_file", {
-        uid,
-      }))
-    ) {
-      throw new Error("Empty Response!");
-    }
+    await invoke<Args, void>()("delete_file", {
+      uid,
+    });
 
     return;
   } catch (e) {
@@ -141,6 +150,8 @@ export const updateFile = async (
   tags: Array<string>,
   dirUid?: string,
   synced?: boolean,
+  pinned?: boolean,
+  fieldClocks?: FieldClocks,
 ) => {
   try {
     const file = await invoke<Args, File | null>()("update_file", {
@@ -152,6 +163,8 @@ export const updateFile = async (
       tags,
       links,
       synced,
+      pinned,
+      fieldClocks,
     });
 
     if (!file) {
@@ -164,3 +177,54 @@ export const updateFile = async (
     throw e;
   }
 };
+
+export const setPinned = async (uid: string, pinned: boolean) => {
+  try {
+    await invoke<Args, void>()(pinned ? "pin_file" : "unpin_file", {
+      uid,
+    });
+  } catch (e) {
+    console.error("[File] Failed to update pin!", e);
+    throw e;
+  }
+};
+
+export const getPinnedFiles = async (workspaceUid: string) => {
+  try {
+    return (
+      (await invoke<Args, Array<File> | null>()("list_pinned_files", {
+        workspaceUid,
+      })) ?? []
+    );
+  } catch (e) {
+    console.error("[File] Failed to get pinned files!", e);
+    throw e;
+  }
+};
+
+export const getRecentFiles = async (limit: number) => {
+  try {
+    return (
+      (await invoke<Args, Array<File> | null>()("list_recent_files", {
+        limit,
+      })) ?? []
+    );
+  } catch (e) {
+    console.error("[File] Failed to get recent files!", e);
+    throw e;
+  }
+};
+
+export const recordFileActivity = async (
+  fileUid: string,
+  activity: FileActivity,
+) => {
+  try {
+    await invoke<Args, void>()("record_file_activity", {
+      fileUid,
+      activity,
+    });
+  } catch (e) {
+    console.error("[File] Failed to record activity!", e);
+  }
+};
@@ -6,3 +6,10 @@ export * from "./config";
 export * from "./updates";
 export * from "./devices";
 export * from "./tracker";
+export * from "./daily-notes";
+export * from "./attachments";
+export * from "./conflicts";
+export * from "./tombstones";
+export * from "./sidecar";
+export * from "./bootstrap";
+export { CommandError, type CommandErrorCode } from "./invoke";
@@ -1,5 +1,33 @@
 import { invoke as invokeCommand } from "@tauri-apps/api/core";
 
+export type CommandErrorCode =
+  | "not_found"
+  | "constraint_violation"
+  | "database_locked"
+  | "corrupt_row"
+  | "invalid_input"
+  | "storage_error"
+  | "sidecar_error"
+  | "database_error";
+
+// Failures of commands, as sent by the backend
+export class CommandError extends Error {
+  code: CommandErrorCode;
+
+  constructor(code: CommandErrorCode, message: string) {
+    super(message);
+    this.code = code;
+  }
+}
+
+const isCommandError = (
+  e: unknown,
+): e is { code: CommandErrorCode; message: string } =>
+  typeof e === "object" &&
+  e !== null &&
+  typeof (<{ code?: unknown }>e).code === "string" &&
+  typeof (<{ message?: unknown }>e).message === "string";
+
 export const invoke =
   <T extends Record<string, Record<string, unknown>>, X>() =>
   async <U extends keyof T>(command: U, args: T[U]): Promise<X> => {
@@ -15,6 +43,10 @@ export const invoke =
         console.error("[Invoke Error]", e);
       }
 
+      if (isCommandError(e)) {
+        throw new CommandError(e.code, e.message);
+      }
+
       throw e;
     }
   };
@@ -0,0 +1,24 @@
+import { invoke } from "./invoke";
+
+type Args = {
+  start_sync: never;
+  stop_sync: never;
+  restart_sync: never;
+};
+
+const control = async (command: keyof Args) => {
+  try {
+    await invoke<Args, void>()(command, {} as never);
+
+    return true;
+  } catch (e) {
+    console.error(`[Sidecar] Failed to ${command.replace("_", " ")}!`, e);
+    return false;
+  }
+};
+
+export const startSync = () => control("start_sync");
+
+export const stopSync = () => control("stop_sync");
+
+export const restartSync = () => control("restart_sync");
@@ -15,14 +15,10 @@ type Args = {
 
 export const updateSnapshot = async (fileUid: string, content: Uint8Array) => {
   try {
-    if (
-      !(await invoke<Args, boolean>()("update_snapshot", {
-        fileUid,
-        content,
-      }))
-    ) {
-      throw new Error("Empty response!");
-    }
+    await invoke<Args, void>()("update_snapshot", {
+      fileUid,
+      content,
+    });
   } catch (e) {
     console.error("[Snapshot] Failed to update! Error: ", e);
     throw e;
@@ -0,0 +1,21 @@
+import { invoke } from "./invoke";
+
+export type Tombstone = {
+  uid: string;
+  source: "Workspace" | "Directory" | "File" | "Attachment";
+  file_uid?: string;
+  deleted_at: string;
+};
+
+type Args = {
+  apply_tombstone: { tombstone: Tombstone };
+};
+
+export const applyTombstone = async (tombstone: Tombstone) => {
+  try {
+    await invoke<Args, void>()("apply_tombstone", { tombstone });
+  } catch (e) {
+    console.error("[Tombstone] Failed to apply!", e);
+    throw e;
+  }
+};
@@ -5,7 +5,8 @@ export type Sources =
   | "directories"
   | "files"
   | "snapshots"
-  | "updates";
+  | "updates"
+  | "attachments";
 
 type Args = {
   last_synced_record_id: {
@@ -44,16 +45,12 @@ export const addSyncedRecord = async (
   userId: string,
 ) => {
   try {
-    const success = await invoke<Args, boolean>()("add_record", {
+    await invoke<Args, void>()("add_record", {
       deviceId,
       source,
       recordId,
       userId,
     });
-
-    if (!success) {
-      throw new Error("[Tracker] Failed to add record! Handler failed.");
-    }
   } catch (e) {
     console.error("[Tracker] Failed to add record!", e);
   }
@@ -19,15 +19,11 @@ export const insertUpdates = async (
   content: Uint8Array,
 ) => {
   try {
-    if (
-      !(await invoke<Args, boolean>()("insert_updates", {
-        fileUid,
-        content,
-        snapshotId,
-      }))
-    ) {
-      throw new Error("Empty Response!");
-    }
+    await invoke<Args, void>()("insert_updates", {
+      fileUid,
+      content,
+      snapshotId,
+    });
   } catch (e) {
     console.error("[Updates] Failed to update! Error: ", e);
     throw e;
@@ -1,5 +1,17 @@
+import type { FieldClocks } from "./conflicts";
 import { invoke } from "./invoke";
 
+export type SortOrder = "name" | "created_at" | "updated_at";
+
+export type WorkspaceSettings = {
+  default_dir_uid?: string;
+  sort_order: SortOrder;
+  editor_font?: string;
+  spellcheck_language?: string;
+  daily_notes_dir_uid?: string;
+  daily_note_format?: string;
+};
+
 export type Workspace = {
   id?: number;
   uid: string;
@@ -8,6 +20,8 @@ export type Workspace = {
   created_at: string;
   updated_at: string;
   synced?: boolean;
+  settings?: WorkspaceSettings;
+  field_clocks?: FieldClocks;
 };
 
 type Args = {
@@ -19,6 +33,7 @@ type Args = {
     created_at?: string;
     updated_at?: string;
     synced?: boolean;
+    settings?: WorkspaceSettings;
   };
   delete_workspace: { uid: string };
   update_workspace: {
@@ -26,8 +41,16 @@ type Args = {
     name: string;
     selected: boolean;
     synced?: boolean;
+    settings?: WorkspaceSettings;
+    fieldClocks?: FieldClocks;
   };
   get_workspace_id: { name: string };
+  get_workspace_settings: { uid: string };
+  update_workspace_settings: {
+    uid: string;
+    settings: WorkspaceSettings;
+    synced?: boolean;
+  };
 };
 
 export const createWorkspace = async (
@@ -37,6 +60,7 @@ export const createWorkspace = async (
   createdAt?: string,
   updatedAt?: string,
   synced?: boolean,
+  settings?: WorkspaceSettings,
 ) => {
   try {
     const workspace = await invoke<Args, Workspace>()("create_workspace", {
@@ -46,6 +70,7 @@ export const createWorkspace = async (
       created_at: createdAt,
       updated_at: updatedAt,
       synced,
+      settings,
     });
 
     if (!workspace) {
@@ -80,13 +105,9 @@ export const getWorkspaces = async () => {
 
 export const deleteWorkspace = async (uid: string) => {
   try {
-    if (
-      !(await invoke<Args, boolean>()("delete_workspace", {
-        uid,
-      }))
-    ) {
-      throw new Error("Empty response!");
-    }
+    await invoke<Args, void>()("delete_workspace", {
+      uid,
+    });
 
     return true;
   } catch (e) {
@@ -100,6 +121,8 @@ export const updateWorkspace = async (
   name: string,
   selected: boolean,
   synced?: boolean,
+  settings?: WorkspaceSettings,
+  fieldClocks?: FieldClocks,
 ) => {
   try {
     return await invoke<Args, void>()("update_workspace", {
@@ -107,6 +130,8 @@ export const updateWorkspace = async (
       name,
       selected,
       synced,
+      settings,
+      fieldClocks,
     });
   } catch (e) {
     console.error("[Workspace] Failed to update!", e);
@@ -123,3 +148,31 @@ export const getByName = async (name: string) => {
     console.error("[Workspace] Failed to get by name!", e);
   }
 };
+
+export const getWorkspaceSettings = async (uid: string) => {
+  try {
+    return await invoke<Args, WorkspaceSettings | null>()(
+      "get_workspace_settings",
+      { uid },
+    );
+  } catch (e) {
+    console.error("[Workspace] Failed to get settings!", e);
+    throw e;
+  }
+};
+
+export const updateWorkspaceSettings = async (
+  uid: string,
+  settings: WorkspaceSettings,
+  synced?: boolean,
+) => {
+  try {
+    return await invoke<Args, WorkspaceSettings | null>()(
+      "update_workspace_settings",
+      { uid, settings, synced },
+    );
+  } catch (e) {
+    console.error("[Workspace] Failed to update settings!", e);
+    throw e;
+  }
+};
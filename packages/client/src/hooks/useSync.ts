@@ -6,11 +6,13 @@ import {
   useWorkspace,
 } from "../store";
 import {
-  deleteWorkspace,
-  DeviceIds,
+  addAttachment,
+  applyTombstone,
+  type Attachment,
   type Directory,
   type File,
   type Sources,
+  type Tombstone,
   type Workspace,
 } from "../ffi";
 import { useSyncRequests } from "./useSyncRequests";
@@ -26,6 +28,31 @@ export type Document = {
   file_uid?: string;
 };
 
+// An entry of the change feed of the user, shared by all of their devices
+export type Change = {
+  seq: number;
+  device_id: string;
+  kind: "document" | "update";
+  source: Sources;
+  operation: Document["operation"];
+  record_id?: number;
+  file_uid?: string;
+  payload: string;
+};
+
+const DOCUMENT_SOURCES: Array<Sources> = [
+  "workspaces",
+  "files",
+  "directories",
+  "attachments",
+];
+
+/*
+ * The feed cursor spans every device, so it is tracked under the nil device id
+ * instead of one of them
+ */
+const CHANGE_FEED_DEVICE = "00000000-0000-0000-0000-000000000000";
+
 type DocumentSource<T extends DocumentSources> = T extends "files"
   ? File
   : T extends "directories"
@@ -41,12 +68,13 @@ type DocumentHandlers = {
 };
 
 export const useSync = () => {
-  const { getDocuments: getSyncedDocs } = useSyncRequests();
+  const { getChanges, getAttachmentContent } = useSyncRequests();
 
   const {
     isDuplicate: isDuplicateWorkspace,
     create: createWorkspace,
     updateWorkspace,
+    list: listWorkspaces,
   } = useWorkspace();
   const {
     isDuplicateFile,
@@ -55,8 +83,7 @@ export const useSync = () => {
     createDir,
     updateFile,
     updateDir,
-    deleteDir,
-    deleteFile,
+    createTree,
   } = useTreeStore();
   const { getSyncedRecordId, updateRecord } = useTracker();
 
@@ -113,11 +140,37 @@ export const useSync = () => {
         }
       },
       workspaces: async (workspace) => {
-        const { name, uid, created_at, updated_at } = workspace;
+        const { name, uid, created_at, updated_at, settings } = workspace;
         if (!(await isDuplicateWorkspace(name))) {
-          await createWorkspace(name, uid, created_at, updated_at, true);
+          await createWorkspace(
+            name,
+            uid,
+            created_at,
+            updated_at,
+            true,
+            settings,
+          );
         }
       },
+      attachments: async (attachment) => {
+        const { uid, file_uid, name, mime_type, hash, created_at } =
+          attachment;
+        const { response: content } = await getAttachmentContent(hash);
+        if (!content) {
+          console.error("[Sync] Failed to download attachment", uid);
+          return;
+        }
+
+        await addAttachment(
+          file_uid,
+          name,
+          mime_type,
+          content,
+          uid,
+          created_at,
+          true,
+        );
+      },
     }),
     [
       createDir,
@@ -126,6 +179,7 @@ export const useSync = () => {
       isDuplicateDirectory,
       isDuplicateFile,
       isDuplicateWorkspace,
+      getAttachmentContent,
     ],
   );
 
@@ -141,9 +195,11 @@ export const useSync = () => {
         await updateDir(directory as DirWithChildren, true),
       files: async (file) => await updateFile(file, true),
       workspaces: async (workspace) => {
-        const { name, uid } = workspace;
-        await updateWorkspace(name, uid, true);
+        const { name, uid, settings, field_clocks } = workspace;
+        await updateWorkspace(uid, name, true, settings, field_clocks);
       },
+      // Attachments are immutable
+      attachments: async () => {},
     }),
     [updateDir, updateFile, updateWorkspace],
   );
@@ -154,20 +210,18 @@ export const useSync = () => {
     [updateHandlers],
   );
 
-  const deleteHandlers: DocumentHandlers = useMemo(
-    () => ({
-      directories: async (directory) =>
-        await deleteDir(directory as DirWithChildren),
-      files: async (file) => await deleteFile(file),
-      workspaces: async (workspace) => await deleteWorkspace(workspace.uid),
-    }),
-    [deleteDir, deleteFile],
-  );
-
+  // Whether a delete or a concurrent edit wins is decided by the app
   const handleDeletes = useCallback(
-    async <T extends DocumentSources>(source: T, doc: DocumentSource<T>) =>
-      await deleteHandlers[source](doc),
-    [deleteHandlers],
+    async (tombstone: Tombstone) => {
+      await applyTombstone(tombstone);
+
+      const workspaces = await listWorkspaces();
+      const current = workspaces.find((workspace) => workspace.selected);
+      if (current) {
+        await createTree(current.uid);
+      }
+    },
+    [createTree, listWorkspaces],
   );
 
   const operationHandlers: {
@@ -187,87 +241,84 @@ export const useSync = () => {
         return handleUpdates(source, doc);
       },
       delete: async (document) => {
-        const { payload, source } = document;
-        const doc = JSON.parse(payload) as DocumentSource<typeof source>;
-        return handleDeletes(source, doc);
+        const tombstone = JSON.parse(document.payload) as Tombstone;
+        // Deletes from older versions only carry the row id of the other device
+        if (!tombstone.uid) {
+          return;
+        }
+
+        return handleDeletes(tombstone);
       },
     }),
     [handleDeletes, handleInserts, handleUpdates],
   );
 
-  const getDocuments = useCallback(
-    async (deviceId: string, userId: string) => {
-      const sources = ["workspaces", "files", "directories"] as Array<Sources>;
-
-      const lastSyncedRecordId = await getSyncedRecordId(
-        deviceId,
-        sources,
-        userId,
-      );
-
-      if (lastSyncedRecordId == null) {
-        console.error("Failed to fetch last record id. Aborting sync...");
-        return;
-      }
-
-      const { response } = await getSyncedDocs(
-        sources,
-        lastSyncedRecordId,
-        deviceId,
-      );
-
-      return response?.documents ?? [];
-    },
-    [getSyncedDocs, getSyncedRecordId],
-  );
-
+  // Follows the change feed from the last cursor until it is caught up
   const syncDocuments = useCallback(
-    async (userId: string, deviceIds: DeviceIds) => {
+    async (userId: string) => {
       try {
-        const documents = await Promise.all(
-          deviceIds.map((device) => getDocuments(device, userId!)),
+        let cursor = await getSyncedRecordId(
+          CHANGE_FEED_DEVICE,
+          DOCUMENT_SOURCES,
+          userId,
         );
 
-        // @todo: handle unsynced documents
-        await Promise.allSettled(
-          (documents || [])
-            .flat()
-            .filter(Boolean)
-            .map(async (doc) => operationHandlers[doc!.operation](doc!)),
-        );
+        if (cursor == null) {
+          console.error("Failed to fetch the change cursor. Aborting sync...");
+          return;
+        }
 
-        const documentsRecordIdMap = documents.reduce<
-          Record<string, { source: Sources; recordId: number }>
-        >((map, documentsByDevice) => {
-          const lastSyncedRecord = documentsByDevice?.sort(
-            (a, b) => b.sk - a.sk,
-          )[0];
+        for (;;) {
+          const { response } = await getChanges(cursor);
+          if (!response) {
+            return;
+          }
+
+          // Changes are applied in order. Content is synced on its own.
+          for (const change of response.changes) {
+            if (
+              change.kind !== "document" ||
+              !DOCUMENT_SOURCES.includes(change.source)
+            ) {
+              continue;
+            }
 
-          const deviceId = lastSyncedRecord?.pk?.split("/")?.[1];
+            const document: Document = {
+              pk: `${userId}/${change.device_id}`,
+              sk: change.record_id ?? change.seq,
+              payload: change.payload,
+              operation: change.operation,
+              source: change.source as DocumentSources,
+              file_uid: change.file_uid,
+            };
 
-          if (!lastSyncedRecord || !deviceId) {
-            return map;
+            try {
+              await operationHandlers[document.operation](document);
+            } catch (e) {
+              console.error("[Sync] Failed to apply change", change.seq, e);
+            }
           }
 
-          map[deviceId] = {
-            source: lastSyncedRecord.source,
-            recordId: lastSyncedRecord.sk,
-          };
-          return map;
-        }, {});
+          if (response.next_cursor !== cursor) {
+            cursor = response.next_cursor;
+            await updateRecord(
+              CHANGE_FEED_DEVICE,
+              "workspaces",
+              cursor,
+              userId,
+            );
+          }
 
-        await Promise.all(
-          Object.entries(documentsRecordIdMap).map(
-            ([deviceId, { source, recordId }]) =>
-              updateRecord(deviceId, source, recordId, userId!),
-          ),
-        );
+          if (!response.has_more) {
+            return;
+          }
+        }
       } catch (e) {
         console.error("[Sync] Failed operation!", e);
         throw e;
       }
     },
-    [getDocuments, operationHandlers, updateRecord],
+    [getChanges, getSyncedRecordId, operationHandlers, updateRecord],
   );
 
   return { syncDocuments };
@@ -1,6 +1,6 @@
 import { useCallback, useMemo } from "react";
 import { DeviceIds, Sources } from "../ffi";
-import { Document } from "./useSync";
+import { Change, Document } from "./useSync";
 import { useConfig, useDevices, useOffline } from "../store";
 import { request } from "../utils";
 
@@ -57,6 +57,26 @@ export const useSyncRequests = () => {
     [http, networkCall],
   );
 
+  // Writes of every other device after `since`, in order
+  const getChanges = useCallback(
+    async (since: number) =>
+      await networkCall(
+        () =>
+          http.get<{
+            changes: Array<Change>;
+            next_cursor: number;
+            has_more: boolean;
+          }>("/sync/changes", {
+            queryParams: { since },
+          }),
+        {
+          onError: async () => undefined,
+          onOffline: async () => undefined,
+        },
+      ),
+    [http, networkCall],
+  );
+
   const getUpdates = useCallback(
     async (deviceId: string, fileUid: string, snapshotId: number) =>
       await networkCall(
@@ -76,9 +96,20 @@ export const useSyncRequests = () => {
     [http, networkCall],
   );
 
+  const getAttachmentContent = useCallback(
+    async (hash: string) =>
+      await networkCall(() => http.getBytes(`/sync/attachment/${hash}`), {
+        onError: async () => undefined,
+        onOffline: async () => undefined,
+      }),
+    [http, networkCall],
+  );
+
   return {
     getDocuments,
+    getChanges,
     initDevices,
     getUpdates,
+    getAttachmentContent,
   };
 };
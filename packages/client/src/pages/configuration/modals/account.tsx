@@ -55,14 +55,9 @@ export const AccountForm = ({ onDone }: { onDone: () => void }) => {
         onDone();
       }
     } catch (e) {
-      if (e instanceof HTTPError && /Username taken/g.test(e.message)) {
-        toast(e.message);
-        return;
-      }
-
       if (
         e instanceof HTTPError &&
-        /Password verification failed/g.test(e.message)
+        (e.code === "user_exists" || e.code === "invalid_credentials")
       ) {
         toast(e.message);
         return;
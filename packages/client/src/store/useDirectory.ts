@@ -69,8 +69,17 @@ export const dirSlice: StateCreator<
       return;
     }
 
-    const { uid, name, workspace_uid, path, parent_uid } = directory;
-    await updateDir(uid, name, workspace_uid, path, parent_uid, synced);
+    const { uid, name, workspace_uid, path, parent_uid, field_clocks } =
+      directory;
+    await updateDir(
+      uid,
+      name,
+      workspace_uid,
+      path,
+      parent_uid,
+      synced,
+      field_clocks,
+    );
 
     await get().createTree(directory.workspace_uid);
   },
@@ -9,6 +9,7 @@ import {
   insertUpdates,
   getUpdates,
   getByPath,
+  recordFileActivity,
 } from "../ffi";
 import type { StateCreator } from "zustand";
 import { toast } from "../utils";
@@ -71,6 +72,8 @@ export const fileSlice: StateCreator<
     set({
       selectedFile: file,
     });
+
+    await recordFileActivity(file.uid, "open");
   },
 
   createFile: async (
@@ -134,7 +137,17 @@ export const fileSlice: StateCreator<
       return;
     }
 
-    const { uid, title, workspace_uid, path, dir_uid, tags, links } = file;
+    const {
+      uid,
+      title,
+      workspace_uid,
+      path,
+      dir_uid,
+      tags,
+      links,
+      pinned,
+      field_clocks,
+    } = file;
     await updateFile(
       uid,
       title,
@@ -144,6 +157,8 @@ export const fileSlice: StateCreator<
       tags,
       dir_uid,
       synced,
+      pinned,
+      field_clocks,
     );
 
     await get().createTree(workspace_uid);
@@ -182,7 +197,10 @@ export const fileSlice: StateCreator<
     fileUid: string,
     snapshotId: number,
     content: Uint8Array = new Uint8Array(),
-  ) => await insertUpdates(fileUid, snapshotId, content),
+  ) => {
+    await insertUpdates(fileUid, snapshotId, content);
+    await recordFileActivity(fileUid, "edit");
+  },
 
   getContent: async (fileUid: string) => {
     const snapshot = await getSnapshot(fileUid);
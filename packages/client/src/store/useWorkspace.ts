@@ -5,6 +5,8 @@ import {
   updateWorkspace,
   getByName,
   type Workspace,
+  type WorkspaceSettings,
+  type FieldClocks,
 } from "../ffi";
 import { create } from "zustand";
 
@@ -18,6 +20,7 @@ interface WorkspaceState {
     createdAt?: string,
     updatedAt?: string,
     synced?: boolean,
+    settings?: WorkspaceSettings,
   ) => Promise<Workspace | undefined>;
   delete: (uid: string) => Promise<void>;
   list: () => Promise<Array<Workspace>>;
@@ -27,6 +30,8 @@ interface WorkspaceState {
     uid: string,
     name: string,
     synced?: boolean,
+    settings?: WorkspaceSettings,
+    fieldClocks?: FieldClocks,
   ) => Promise<void>;
 }
 
@@ -41,7 +46,7 @@ export const useWorkspace = create<WorkspaceState>((set, get) => ({
       currentWorkspace: workspaces.find((w) => w.selected),
     }),
 
-  create: async (name, uid, createdAt, updatedAt, synced) => {
+  create: async (name, uid, createdAt, updatedAt, synced, settings) => {
     const workspace = await createWorkspace(
       name,
       true,
@@ -49,6 +54,7 @@ export const useWorkspace = create<WorkspaceState>((set, get) => ({
       createdAt,
       updatedAt,
       synced,
+      settings,
     );
     const { list } = get();
     await list();
@@ -97,8 +103,15 @@ export const useWorkspace = create<WorkspaceState>((set, get) => ({
     return !!workspaceId;
   },
 
-  updateWorkspace: async (uid, name, synced) => {
+  updateWorkspace: async (uid, name, synced, settings, fieldClocks) => {
     const currentWorkspace = get().workspaces?.find((w) => w.uid === uid);
-    await updateWorkspace(uid, name, !!currentWorkspace?.selected, synced);
+    await updateWorkspace(
+      uid,
+      name,
+      !!currentWorkspace?.selected,
+      synced,
+      settings,
+      fieldClocks,
+    );
   },
 }));
@@ -1,4 +1,5 @@
 import { fetch } from "@tauri-apps/plugin-http";
+import { getNetworkSettings } from "../ffi/config";
 
 type HTTPRequestParameters = {
   json?: Record<string, any>;
@@ -9,14 +10,24 @@ type HTTPRequestParameters = {
 export class HTTPError extends Error {
   status: number;
   statusText: string;
-
-  constructor(message: string, status: number, statusText: string) {
+  // Machine-readable code sent by the server, e.g. "user_exists"
+  code?: string;
+
+  constructor(
+    message: string,
+    status: number,
+    statusText: string,
+    code?: string,
+  ) {
     super(message);
     this.status = status;
     this.statusText = statusText;
+    this.code = code;
   }
 }
 
+type ErrorEnvelope = { error?: { code?: string; message?: string } };
+
 const responseParser = async <T>(res: Response) => {
   const hasContent = Number(res.headers.get("content-length")) > 0;
 
@@ -32,12 +43,15 @@ const responseParser = async <T>(res: Response) => {
   ) as T;
 
   if (!res.ok) {
+    const { error } = ((await response) ?? {}) as ErrorEnvelope;
+
     throw new HTTPError(
       isTextResponse
         ? (response as { message: string })?.["message"]
-        : JSON.stringify(response ?? ""),
+        : (error?.message ?? JSON.stringify((await response) ?? "")),
       res.status,
       res.statusText,
+      error?.code,
     );
   }
 
@@ -47,17 +61,21 @@ const responseParser = async <T>(res: Response) => {
 const request = ({
   headers: baseHeaders,
 }: Pick<HTTPRequestParameters, "headers"> = {}) => {
-  const prefixUrl = import.meta.env.VITE_APP_BASE_URL;
+  // A server set in the network settings takes over from the one built in
+  const prefixUrl = async () =>
+    (await getNetworkSettings())?.server_url ??
+    import.meta.env.VITE_APP_BASE_URL;
 
   const defaultHeaders = {
     Accept: "application/json text/html",
   };
 
-  const url = (
+  const url = async (
     endpoint: string,
     queryParams?: HTTPRequestParameters["queryParams"],
   ) => {
-    const url = prefixUrl ? new URL(endpoint, prefixUrl).href : endpoint;
+    const baseUrl = await prefixUrl();
+    const url = baseUrl ? new URL(endpoint, baseUrl).href : endpoint;
 
     if (queryParams && Object.keys(queryParams).length) {
       const params = Object.entries(queryParams).reduce<Record<string, string>>(
@@ -65,7 +83,7 @@ const request = ({
         {},
       );
 
-      return `url?${new URLSearchParams(params).toString()}`;
+      return `${url}?${new URLSearchParams(params).toString()}`;
     }
 
     return url;
@@ -75,7 +93,7 @@ const request = ({
     endpoint: string,
     { headers, queryParams }: HTTPRequestParameters = {},
   ) => {
-    const response = await fetch(url(endpoint, queryParams), {
+    const response = await fetch(await url(endpoint, queryParams), {
       method: "GET",
       headers: { ...defaultHeaders, ...baseHeaders, ...headers },
       proxy: {
@@ -88,11 +106,31 @@ const request = ({
     return responseParser<T>(response);
   };
 
+  const getBytes = async (
+    endpoint: string,
+    { headers, queryParams }: HTTPRequestParameters = {},
+  ) => {
+    const response = await fetch(await url(endpoint, queryParams), {
+      method: "GET",
+      headers: { ...baseHeaders, ...headers },
+    });
+
+    if (!response.ok) {
+      throw new HTTPError(
+        await response.text(),
+        response.status,
+        response.statusText,
+      );
+    }
+
+    return new Uint8Array(await response.arrayBuffer());
+  };
+
   const post = async <T>(
     endpoint: string,
     { headers, json, queryParams }: HTTPRequestParameters = {},
   ) => {
-    const response = await fetch(url(endpoint, queryParams), {
+    const response = await fetch(await url(endpoint, queryParams), {
       method: "POST",
       headers: {
         ...baseHeaders,
@@ -112,6 +150,7 @@ const request = ({
 
   return {
     get,
+    getBytes,
     post,
   };
 };